@@ -17,6 +17,15 @@ object SoloEngine {
     external fun engineInit(campaignJson: String, characterJson: String, seed: Long)
     external fun engineCurrentView(): String
     external fun engineChoose(choiceId: String)
+    external fun engineSave(): String
+    external fun engineLoad(campaignJson: String, saveJson: String)
+    external fun engineCharacterSheet(): String
+
+    // Character creation: each screen is a NodeView, driven like the campaign
+    external fun builderStart(name: String, seed: Long)
+    external fun builderCurrentView(): String
+    external fun builderChoose(choiceId: String)
+    external fun builderCharacter(): String
 
     @Serializable
     data class CharacterSummary(
@@ -24,12 +33,17 @@ object SoloEngine {
         val level: Int,
         val current_hp: Int,
         val max_hp: Int,
+        val dying: Boolean = false,
+        val conditions: List<String> = emptyList(),
+        val spell_slots: List<Int> = emptyList(),
+        val gold: Int = 0,
     )
 
     @Serializable
     data class ChoiceView(
         val id: String,
         val label: String,
+        val disabled: Boolean = false,
     )
 
     @Serializable
@@ -41,8 +55,36 @@ object SoloEngine {
         val log: String? = null,
     )
 
+    @Serializable
+    data class InventoryEntry(
+        val item: String,
+        val name: String,
+        val quantity: Int,
+        val weight: Float = 0f,
+        val slot: String? = null,
+        val equipped: Boolean = false,
+        val usable: Boolean = false,
+    )
+
+    @Serializable
+    data class CharacterSheet(
+        val name: String,
+        val race: String? = null,
+        val `class`: String? = null,
+        val level: Int,
+        val current_hp: Int,
+        val max_hp: Int,
+        val ac: Int,
+        val gold: Int = 0,
+        val inventory: List<InventoryEntry> = emptyList(),
+        val carried_weight: Float = 0f,
+        val carrying_capacity: Int = 0,
+    )
+
     private val json = Json { ignoreUnknownKeys = true }
 
+    fun parseSheet(raw: String): CharacterSheet = json.decodeFromString<CharacterSheet>(raw)
+
     fun parseView(raw: String): NodeView {
         return try {
             json.decodeFromString<NodeView>(raw)
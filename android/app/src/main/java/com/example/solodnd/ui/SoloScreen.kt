@@ -1,12 +1,15 @@
 package com.example.solodnd.ui
 
 import android.content.Context
+import android.util.Log
+import java.io.File
 import androidx.compose.foundation.layout.*
 import androidx.compose.foundation.rememberScrollState
 import androidx.compose.foundation.verticalScroll
 import androidx.compose.material3.Button
 import androidx.compose.material3.CircularProgressIndicator
 import androidx.compose.material3.Text
+import androidx.compose.material3.TextButton
 import androidx.compose.runtime.*
 import androidx.compose.ui.Alignment
 import androidx.compose.ui.Modifier
@@ -17,14 +20,22 @@ import androidx.compose.ui.unit.dp
 fun SoloScreen() {
     val context = LocalContext.current
     var view by remember { mutableStateOf<SoloEngine.NodeView?>(null) }
+    var campaignJson by remember { mutableStateOf("") }
+    // True while the player is still creating their character
+    var creating by remember { mutableStateOf(false) }
+    // Shown under the summary while open
+    var sheet by remember { mutableStateOf<SoloEngine.CharacterSheet?>(null) }
 
     LaunchedEffect(Unit) {
         // Replace this with loading from assets / resources
-        val campaignJson = loadAsset(context, "campaigns/road_to_redcrest.json")
-        val characterJson = loadAsset(context, "characters/pregen_fighter.json")
-        SoloEngine.engineInit(campaignJson, characterJson, System.currentTimeMillis())
-        val raw = SoloEngine.engineCurrentView()
-        view = SoloEngine.parseView(raw)
+        campaignJson = loadAsset(context, "campaigns/road_to_redcrest.json")
+        if (tryLoadSave(context, campaignJson)) {
+            view = SoloEngine.parseView(SoloEngine.engineCurrentView())
+        } else {
+            SoloEngine.builderStart("Adventurer", System.currentTimeMillis())
+            creating = true
+            view = SoloEngine.parseView(SoloEngine.builderCurrentView())
+        }
     }
 
     view?.let { node ->
@@ -50,8 +61,36 @@ fun SoloScreen() {
                 Text(
                     text = "${node.character_summary.name} " +
                     "Lv ${node.character_summary.level} " +
-                    "HP ${node.character_summary.current_hp}/${node.character_summary.max_hp}"
+                    "HP ${node.character_summary.current_hp}/${node.character_summary.max_hp} " +
+                    "${node.character_summary.gold} gp" +
+                    (if (node.character_summary.dying) " (dying)" else "") +
+                    (if (node.character_summary.conditions.isNotEmpty()) {
+                        " [${node.character_summary.conditions.joinToString(", ")}]"
+                    } else "") +
+                    if (node.character_summary.spell_slots.isNotEmpty()) {
+                        " Slots ${node.character_summary.spell_slots.joinToString("/")}"
+                    } else ""
                 )
+                if (!creating) {
+                    TextButton(onClick = {
+                        sheet = if (sheet == null) {
+                            SoloEngine.parseSheet(SoloEngine.engineCharacterSheet())
+                        } else null
+                    }) {
+                        Text(if (sheet == null) "Character sheet" else "Hide character sheet")
+                    }
+                }
+                sheet?.let { s ->
+                    Text(text = "${s.race ?: ""} ${s.`class` ?: ""} ${s.level}, AC ${s.ac}, ${s.gold} gp")
+                    s.inventory.forEach { entry ->
+                        Text(
+                            text = "${entry.name}" +
+                            (if (entry.quantity > 1) " x${entry.quantity}" else "") +
+                            (if (entry.equipped) " (${entry.slot ?: "equipped"})" else "")
+                        )
+                    }
+                    Text(text = "Carrying ${s.carried_weight} / ${s.carrying_capacity} lb")
+                }
                 node.log?.let {
                     Spacer(Modifier.height(8.dp))
                     Text(text = it)
@@ -63,10 +102,31 @@ fun SoloScreen() {
             node.choices.forEach { choice ->
                 Button(
                     onClick = {
-                        SoloEngine.engineChoose(choice.id)
-                        val raw = SoloEngine.engineCurrentView()
-                        view = SoloEngine.parseView(raw)
+                        if (creating) {
+                            SoloEngine.builderChoose(choice.id)
+                            val next = SoloEngine.parseView(SoloEngine.builderCurrentView())
+                            if (next.choices.isEmpty()) {
+                                // Character finished: start the campaign with it
+                                SoloEngine.engineInit(
+                                    campaignJson,
+                                    SoloEngine.builderCharacter(),
+                                    System.currentTimeMillis()
+                                )
+                                creating = false
+                                writeSave(context)
+                                view = SoloEngine.parseView(SoloEngine.engineCurrentView())
+                            } else {
+                                view = next
+                            }
+                        } else {
+                            SoloEngine.engineChoose(choice.id)
+                            writeSave(context)
+                            sheet = null
+                            val raw = SoloEngine.engineCurrentView()
+                            view = SoloEngine.parseView(raw)
+                        }
                     },
+                    enabled = !choice.disabled,
                     modifier = Modifier
                         .fillMaxWidth()
                         .padding(vertical = 4.dp)
@@ -86,3 +146,22 @@ fun SoloScreen() {
 private fun loadAsset(context: Context, path: String): String {
     return context.assets.open(path).bufferedReader().use { it.readText() }
 }
+
+private fun saveFile(context: Context): File = File(context.filesDir, "savegame.json")
+
+private fun tryLoadSave(context: Context, campaignJson: String): Boolean {
+    val file = saveFile(context)
+    if (!file.exists()) return false
+    return try {
+        SoloEngine.engineLoad(campaignJson, file.readText())
+        true
+    } catch (e: RuntimeException) {
+        Log.e("SoloScreen", "Discarding unreadable save", e)
+        file.delete()
+        false
+    }
+}
+
+private fun writeSave(context: Context) {
+    saveFile(context).writeText(SoloEngine.engineSave())
+}
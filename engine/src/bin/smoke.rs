@@ -1,20 +1,78 @@
-use solo_engine::campaign::Campaign;
+use solo_engine::builder::CharacterBuilder;
+use solo_engine::campaign::{Campaign, NodeView};
 use solo_engine::engine::Engine;
 use solo_engine::rules::Character;
 use std::io::{self, Write};
 
+/// Show a screen's choices and read a pick. `None` at end of input.
+fn prompt(view: &NodeView) -> io::Result<Option<usize>> {
+    println!("\nChoices:");
+    for (i, c) in view.choices.iter().enumerate() {
+        println!("  {}. {}", i + 1, c.label);
+    }
+    loop {
+        print!("\n> ");
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match line.trim().parse::<usize>() {
+            Ok(n) if n >= 1 && n <= view.choices.len() => return Ok(Some(n - 1)),
+            Ok(_) => println!("Choice out of range."),
+            Err(_) => println!("Please enter a number."),
+        }
+    }
+}
+
+/// Walk through character creation. `None` if input ran out first.
+fn build_character(name: &str) -> Result<Option<Character>, Box<dyn std::error::Error>> {
+    let mut builder = CharacterBuilder::new(name, 42);
+    while !builder.is_done() {
+        let view = builder.view();
+        if let Some(title) = &view.title {
+            println!("\n== {} ==", title);
+        }
+        for para in &view.text {
+            if !para.is_empty() {
+                println!("{}", para);
+            }
+        }
+        let Some(i) = prompt(&view)? else {
+            return Ok(None);
+        };
+        builder.choose(&view.choices[i].id)?;
+    }
+    Ok(Some(Character::from_json(&builder.finish()?)?))
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load JSON files from ../content (relative to the engine crate dir).
     let campaign_json = std::fs::read_to_string("../content/campaigns/road_to_redcrest.json")?;
-    let character_json = std::fs::read_to_string("../content/characters/pregen_fighter.json")?;
-
     let campaign: Campaign = Campaign::from_json(&campaign_json)?;
-    let character: Character = Character::from_json(&character_json)?;
 
-    let mut engine = Engine::new(campaign, character, 42);
+    // `smoke --new [name]` creates a character instead of using the pregen.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let character = if args.first().map(String::as_str) == Some("--new") {
+        let name = args.get(1).map_or("Adventurer", String::as_str);
+        match build_character(name)? {
+            Some(c) => c,
+            None => return Ok(()),
+        }
+    } else {
+        let character_json = std::fs::read_to_string("../content/characters/pregen_fighter.json")?;
+        Character::from_json(&character_json)?
+    };
+
+    for diag in campaign.validate() {
+        eprintln!("{}", diag);
+    }
+
+    let mut engine = Engine::new(campaign, character, 42)?;
 
     loop {
-        let view = engine.current_view();
+        let view = engine.current_view()?;
 
         if let Some(title) = &view.title {
             println!("\n== {} ==", title);
@@ -31,38 +89,53 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             view.character_summary.current_hp,
             view.character_summary.max_hp
         );
+        if !view.character_summary.conditions.is_empty() {
+            println!(
+                "Conditions: {}",
+                view.character_summary.conditions.join(", ")
+            );
+        }
+        if !view.character_summary.spell_slots.is_empty() {
+            let slots: Vec<String> = view
+                .character_summary
+                .spell_slots
+                .iter()
+                .enumerate()
+                .map(|(i, n)| format!("L{} {}", i + 1, n))
+                .collect();
+            println!("Spell slots: {}", slots.join(", "));
+        }
+
+        if let Some(combat) = &view.combat {
+            let order: Vec<String> = combat
+                .turn_order
+                .iter()
+                .map(|t| {
+                    if t.conditions.is_empty() {
+                        format!("{} {}/{}", t.name, t.current_hp, t.max_hp)
+                    } else {
+                        format!(
+                            "{} {}/{} ({})",
+                            t.name,
+                            t.current_hp,
+                            t.max_hp,
+                            t.conditions.join(", ")
+                        )
+                    }
+                })
+                .collect();
+            println!("Round {}: {}", combat.round, order.join(" | "));
+        }
 
         if view.choices.is_empty() {
             println!("\n[END OF ADVENTURE]");
             break;
         }
 
-        println!("\nChoices:");
-        for (i, c) in view.choices.iter().enumerate() {
-            println!("  {}. {}", i + 1, c.label);
-        }
-        print!("\n> ");
-        io::stdout().flush().unwrap();
-
-        let mut line = String::new();
-        io::stdin().read_line(&mut line)?;
-        let trimmed = line.trim();
-
-        let idx: usize = match trimmed.parse() {
-            Ok(n) => n,
-            Err(_) => {
-                println!("Please enter a number.");
-                continue;
-            }
+        let Some(idx) = prompt(&view)? else {
+            break;
         };
-
-        if idx == 0 || idx > view.choices.len() {
-            println!("Choice out of range.");
-            continue;
-        }
-
-        let choice = &view.choices[idx - 1];
-        engine.choose(&choice.id);
+        engine.choose(&view.choices[idx].id)?;
     }
 
     Ok(())
@@ -0,0 +1,1203 @@
+use serde_json::json;
+
+use crate::campaign::{CharacterSummary, ChoiceView, NodeView};
+use crate::classes::{srd_classes, CharacterClass, EquipmentOption};
+use crate::dice::DiceRoller;
+use crate::equipment::{srd_equipment, ArmorCategory, ItemKind, WeaponProperty};
+use crate::error::EngineError;
+use crate::levels::srd_levels;
+use crate::monsters::slugify;
+use crate::races::{srd_races, Race};
+use crate::rules::{Ability, Character, Equipment, ItemStack};
+use crate::spells::{srd_spells, Spell};
+
+/// Backgrounds the builder offers, with the two skills each grants.
+pub const BACKGROUNDS: [(&str, [&str; 2]); 6] = [
+    ("Acolyte", ["Insight", "Religion"]),
+    ("Criminal", ["Deception", "Stealth"]),
+    ("Folk Hero", ["Animal Handling", "Survival"]),
+    ("Noble", ["History", "Persuasion"]),
+    ("Sage", ["Arcana", "History"]),
+    ("Soldier", ["Athletics", "Intimidation"]),
+];
+
+/// Scores to hand out, one per ability, with the standard array method.
+pub const STANDARD_ARRAY: [i32; 6] = [15, 14, 13, 12, 10, 8];
+
+/// Points to spend with the point buy method. Every score starts at 8.
+pub const POINT_BUY_BUDGET: i32 = 27;
+
+/// Total point buy cost of each score from 8 to 15.
+const POINT_BUY_COST: [i32; 8] = [0, 1, 2, 3, 4, 5, 7, 9];
+
+fn point_buy_cost(score: i32) -> i32 {
+    POINT_BUY_COST[(score - 8).clamp(0, 7) as usize]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbilityMethod {
+    /// 4d6, drop the lowest die, six times.
+    Roll,
+    PointBuy,
+    StandardArray,
+}
+
+/// The screen the builder is on, worked out from what has been chosen so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    Method,
+    Assign(Ability),
+    PointBuy,
+    Race,
+    Subrace,
+    RaceBonus,
+    Language,
+    Class,
+    Background,
+    Skill,
+    Cantrip,
+    Spell,
+    /// Pick an option for starting equipment choice `n`.
+    EquipmentOption(usize),
+    /// Pick items from option `o` of starting equipment choice `n`.
+    EquipmentItem(usize, usize),
+    Review,
+    Done,
+}
+
+impl Step {
+    /// Reported as the node id when a choice doesn't fit the screen.
+    fn id(self) -> &'static str {
+        match self {
+            Step::Method | Step::Assign(_) | Step::PointBuy => "builder_abilities",
+            Step::Race | Step::Subrace | Step::RaceBonus | Step::Language => "builder_race",
+            Step::Class => "builder_class",
+            Step::Background => "builder_background",
+            Step::Skill => "builder_skills",
+            Step::Cantrip | Step::Spell => "builder_spells",
+            Step::EquipmentOption(_) | Step::EquipmentItem(..) => "builder_equipment",
+            Step::Review => "builder_review",
+            Step::Done => "builder_done",
+        }
+    }
+}
+
+struct Screen {
+    title: &'static str,
+    text: Vec<String>,
+    choices: Vec<ChoiceView>,
+}
+
+fn choice(id: String, label: String) -> ChoiceView {
+    ChoiceView::new(id, label)
+}
+
+/// Guided creation of a level 1 character: ability scores, race, class,
+/// background, skills, spells and starting equipment. Each step is a `NodeView`
+/// screen, so a front end drives it with the same view/choose loop as a
+/// campaign.
+#[derive(Clone, Debug)]
+pub struct CharacterBuilder {
+    name: String,
+    dice: DiceRoller,
+    method: Option<AbilityMethod>,
+    /// Rolled or standard array scores still to assign.
+    pool: Vec<i32>,
+    /// Base scores in `Ability::ALL` order: those assigned so far, or the
+    /// point buy scores.
+    scores: Vec<i32>,
+    abilities_done: bool,
+    race: Option<&'static Race>,
+    subrace: Option<String>,
+    ability_bonus_choices: Vec<Ability>,
+    languages: Vec<String>,
+    class: Option<&'static CharacterClass>,
+    background: Option<usize>,
+    skills: Vec<String>,
+    cantrips: Vec<String>,
+    /// Spells known, or prepared for classes that prepare them.
+    spells: Vec<String>,
+    /// Starting equipment choices finished so far.
+    equipment_done: usize,
+    /// The pick list option being picked from, and how many items are taken.
+    picking: Option<(usize, u32)>,
+    items: Vec<ItemStack>,
+    done: bool,
+    log: Option<String>,
+}
+
+impl CharacterBuilder {
+    pub fn new(name: &str, seed: u64) -> Self {
+        Self::with_dice(name, DiceRoller::new(seed))
+    }
+
+    fn with_dice(name: &str, dice: DiceRoller) -> Self {
+        Self {
+            name: name.to_string(),
+            dice,
+            method: None,
+            pool: Vec::new(),
+            scores: Vec::new(),
+            abilities_done: false,
+            race: None,
+            subrace: None,
+            ability_bonus_choices: Vec::new(),
+            languages: Vec::new(),
+            class: None,
+            background: None,
+            skills: Vec::new(),
+            cantrips: Vec::new(),
+            spells: Vec::new(),
+            equipment_done: 0,
+            picking: None,
+            items: Vec::new(),
+            done: false,
+            log: None,
+        }
+    }
+
+    /// True once the player has confirmed the finished character.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn step(&self) -> Step {
+        if self.done {
+            return Step::Done;
+        }
+        match self.method {
+            None => return Step::Method,
+            Some(AbilityMethod::PointBuy) if !self.abilities_done => return Step::PointBuy,
+            Some(_) if !self.abilities_done => {
+                return Step::Assign(Ability::ALL[self.scores.len()])
+            }
+            Some(_) => {}
+        }
+
+        let Some(race) = self.race else {
+            return Step::Race;
+        };
+        if !race.subraces.is_empty() && self.subrace.is_none() {
+            return Step::Subrace;
+        }
+        if let Some((n, _)) = &race.ability_choices {
+            if self.ability_bonus_choices.len() < *n as usize {
+                return Step::RaceBonus;
+            }
+        }
+        if self.languages.len() < race.extra_languages as usize
+            && !self.language_options().is_empty()
+        {
+            return Step::Language;
+        }
+
+        let Some(class) = self.class else {
+            return Step::Class;
+        };
+        if self.background.is_none() {
+            return Step::Background;
+        }
+        let skill_picks = class
+            .skill_choices
+            .as_ref()
+            .map_or(0, |c| c.choose as usize);
+        if self.skills.len() < skill_picks && !self.skill_options().is_empty() {
+            return Step::Skill;
+        }
+        let (cantrips, spells) = self.spell_picks();
+        if self.cantrips.len() < cantrips && !self.spell_options(0).is_empty() {
+            return Step::Cantrip;
+        }
+        if self.spells.len() < spells && !self.spell_options(1).is_empty() {
+            return Step::Spell;
+        }
+
+        let n = self.equipment_done;
+        if let Some(options) = class.starting_choices.get(n) {
+            if let Some((o, _)) = self.picking {
+                return Step::EquipmentItem(n, o);
+            }
+            if options.len() == 1 && options[0].is_pick_list() {
+                return Step::EquipmentItem(n, 0);
+            }
+            return Step::EquipmentOption(n);
+        }
+        Step::Review
+    }
+
+    /// The current screen. Its `character_summary` shows the character as
+    /// built so far.
+    pub fn view(&self) -> NodeView {
+        let screen = self.screen(self.step());
+        let mut text = screen.text;
+        if let Some(log) = &self.log {
+            text.push(String::new());
+            text.push(log.clone());
+        }
+        let character_summary = match self.character() {
+            Ok(c) => CharacterSummary::new(&c),
+            Err(_) => CharacterSummary {
+                name: self.name.clone(),
+                level: 1,
+                xp: 0,
+                current_hp: 0,
+                max_hp: 0,
+                ac: 10,
+                dying: false,
+                death_saves: None,
+                conditions: Vec::new(),
+                spell_slots: Vec::new(),
+                gold: 0,
+            },
+        };
+        NodeView {
+            title: Some(screen.title.to_string()),
+            text,
+            choices: screen.choices,
+            character_summary,
+            log: self.log.clone(),
+            combat: None,
+        }
+    }
+
+    fn screen(&self, step: Step) -> Screen {
+        match step {
+            Step::Method => Screen {
+                title: "Ability Scores",
+                text: vec!["How do you want to generate your ability scores?".to_string()],
+                choices: vec![
+                    choice(
+                        "method:roll".to_string(),
+                        "Roll 4d6 and drop the lowest die, six times".to_string(),
+                    ),
+                    choice(
+                        "method:point_buy".to_string(),
+                        format!("Point buy ({} points)", POINT_BUY_BUDGET),
+                    ),
+                    choice(
+                        "method:standard_array".to_string(),
+                        "Standard array (15, 14, 13, 12, 10, 8)".to_string(),
+                    ),
+                ],
+            },
+            Step::Assign(ability) => {
+                let mut values = self.pool.clone();
+                values.sort_unstable_by(|a, b| b.cmp(a));
+                values.dedup();
+                let mut text = vec![format!("Choose your {} score.", ability.name())];
+                if !self.scores.is_empty() {
+                    text.push(format!("So far: {}.", self.scores_line()));
+                }
+                Screen {
+                    title: "Ability Scores",
+                    text,
+                    choices: values
+                        .into_iter()
+                        .map(|v| choice(format!("assign:{}", v), v.to_string()))
+                        .collect(),
+                }
+            }
+            Step::PointBuy => {
+                let left = self.points_left();
+                let mut choices = Vec::new();
+                for (i, ability) in Ability::ALL.iter().enumerate() {
+                    let score = self.scores[i];
+                    let cost = point_buy_cost(score + 1) - point_buy_cost(score);
+                    if score < 15 && cost <= left {
+                        choices.push(choice(
+                            format!("raise:{}", ability.short()),
+                            format!(
+                                "+1 {} ({} -> {}, {} point{})",
+                                ability.name(),
+                                score,
+                                score + 1,
+                                cost,
+                                if cost == 1 { "" } else { "s" }
+                            ),
+                        ));
+                    }
+                }
+                for (i, ability) in Ability::ALL.iter().enumerate() {
+                    let score = self.scores[i];
+                    if score > 8 {
+                        choices.push(choice(
+                            format!("lower:{}", ability.short()),
+                            format!("-1 {} ({} -> {})", ability.name(), score, score - 1),
+                        ));
+                    }
+                }
+                choices.push(choice(
+                    "abilities_done".to_string(),
+                    if left > 0 {
+                        format!("Done ({} points unspent)", left)
+                    } else {
+                        "Done".to_string()
+                    },
+                ));
+                Screen {
+                    title: "Ability Scores",
+                    text: vec![
+                        "Every score starts at 8 and can be bought up to 15.".to_string(),
+                        format!("{}. Points left: {}.", self.scores_line(), left),
+                    ],
+                    choices,
+                }
+            }
+            Step::Race => Screen {
+                title: "Race",
+                text: vec!["Choose your race.".to_string()],
+                choices: srd_races()
+                    .all()
+                    .into_iter()
+                    .map(|r| {
+                        let mut bonuses = bonus_list(&r.grants.ability_bonuses);
+                        if let Some((n, _)) = &r.ability_choices {
+                            bonuses.push(format!("+1 to {} others", n));
+                        }
+                        choice(
+                            format!("race:{}", slugify(&r.name)),
+                            format!("{} ({})", r.name, bonuses.join(", ")),
+                        )
+                    })
+                    .collect(),
+            },
+            Step::Subrace => Screen {
+                title: "Race",
+                text: vec!["Choose your subrace.".to_string()],
+                choices: self
+                    .race
+                    .into_iter()
+                    .flat_map(|r| &r.subraces)
+                    .filter_map(|s| srd_races().subrace(s))
+                    .map(|s| {
+                        choice(
+                            format!("subrace:{}", slugify(&s.name)),
+                            format!(
+                                "{} ({})",
+                                s.name,
+                                bonus_list(&s.grants.ability_bonuses).join(", ")
+                            ),
+                        )
+                    })
+                    .collect(),
+            },
+            Step::RaceBonus => {
+                let (n, from) = self
+                    .race
+                    .and_then(|r| r.ability_choices.as_ref())
+                    .map_or((0, &[][..]), |(n, from)| (*n, from.as_slice()));
+                Screen {
+                    title: "Race",
+                    text: vec![format!(
+                        "Choose an ability to increase by 1 ({} of {}).",
+                        self.ability_bonus_choices.len() + 1,
+                        n
+                    )],
+                    choices: from
+                        .iter()
+                        .filter(|a| !self.ability_bonus_choices.contains(a))
+                        .map(|a| choice(format!("race_bonus:{}", a.short()), a.name().to_string()))
+                        .collect(),
+                }
+            }
+            Step::Language => Screen {
+                title: "Race",
+                text: vec!["Choose an extra language.".to_string()],
+                choices: self
+                    .language_options()
+                    .into_iter()
+                    .map(|l| choice(format!("language:{}", slugify(l)), l.to_string()))
+                    .collect(),
+            },
+            Step::Class => Screen {
+                title: "Class",
+                text: vec!["Choose your class.".to_string()],
+                choices: srd_classes()
+                    .all()
+                    .into_iter()
+                    .map(|c| {
+                        choice(
+                            format!("class:{}", slugify(&c.name)),
+                            format!("{} (d{} hit die)", c.name, c.hit_die),
+                        )
+                    })
+                    .collect(),
+            },
+            Step::Background => Screen {
+                title: "Background",
+                text: vec!["Choose your background.".to_string()],
+                choices: BACKGROUNDS
+                    .iter()
+                    .map(|(name, skills)| {
+                        choice(
+                            format!("background:{}", slugify(name)),
+                            format!("{} ({})", name, skills.join(", ")),
+                        )
+                    })
+                    .collect(),
+            },
+            Step::Skill => {
+                let n = self
+                    .class
+                    .and_then(|c| c.skill_choices.as_ref())
+                    .map_or(0, |c| c.choose);
+                Screen {
+                    title: "Skills",
+                    text: vec![format!(
+                        "Choose a class skill ({} of {}).",
+                        self.skills.len() + 1,
+                        n
+                    )],
+                    choices: self
+                        .skill_options()
+                        .into_iter()
+                        .map(|s| choice(format!("skill:{}", slugify(s)), s.to_string()))
+                        .collect(),
+                }
+            }
+            Step::Cantrip | Step::Spell => {
+                let (level, kind, known, n) = match (step, self.spell_picks()) {
+                    (Step::Cantrip, (n, _)) => (0, "cantrip", &self.cantrips, n),
+                    (_, (_, n)) => (1, "spell", &self.spells, n),
+                };
+                Screen {
+                    title: "Spells",
+                    text: vec![format!(
+                        "Choose a {} ({} of {}).",
+                        if level == 0 {
+                            "cantrip"
+                        } else {
+                            "1st-level spell"
+                        },
+                        known.len() + 1,
+                        n
+                    )],
+                    choices: self
+                        .spell_options(level)
+                        .into_iter()
+                        .map(|s| choice(format!("{}:{}", kind, slugify(&s.name)), spell_label(s)))
+                        .collect(),
+                }
+            }
+            Step::EquipmentOption(n) => Screen {
+                title: "Starting Equipment",
+                text: vec!["Choose one.".to_string()],
+                choices: self.equipment_choice(n).map_or(Vec::new(), |options| {
+                    options
+                        .iter()
+                        .enumerate()
+                        .map(|(i, o)| choice(format!("equipment:{}", i), option_label(o)))
+                        .collect()
+                }),
+            },
+            Step::EquipmentItem(n, o) => {
+                let option = self.equipment_choice(n).and_then(|options| options.get(o));
+                let taken = self.picking.map_or(0, |(_, taken)| taken);
+                let left = option.map_or(0, |o| o.choose.max(1) - taken);
+                Screen {
+                    title: "Starting Equipment",
+                    text: vec![if left > 1 {
+                        format!("Choose {} items, one at a time.", left)
+                    } else {
+                        "Choose one.".to_string()
+                    }],
+                    choices: option.map_or(Vec::new(), |o| {
+                        o.items
+                            .iter()
+                            .map(|s| choice(format!("item:{}", s.item), stack_label(s)))
+                            .collect()
+                    }),
+                }
+            }
+            Step::Review => Screen {
+                title: "Review",
+                text: self.review_text(),
+                choices: vec![
+                    choice("finish".to_string(), format!("Create {}", self.name)),
+                    choice("start_over".to_string(), "Start over".to_string()),
+                ],
+            },
+            Step::Done => Screen {
+                title: "Character Created",
+                text: vec![format!("{} is ready for adventure.", self.name)],
+                choices: Vec::new(),
+            },
+        }
+    }
+
+    /// Apply one choice from the current screen.
+    pub fn choose(&mut self, choice_id: &str) -> Result<(), EngineError> {
+        let step = self.step();
+        if !self.screen(step).choices.iter().any(|c| c.id == choice_id) {
+            return Err(EngineError::UnknownChoice {
+                node_id: step.id().to_string(),
+                choice_id: choice_id.to_string(),
+            });
+        }
+        self.log = None;
+        let (kind, arg) = choice_id.split_once(':').unwrap_or((choice_id, ""));
+        match kind {
+            "method" => self.choose_method(arg),
+            "assign" => {
+                let value: i32 = arg.parse().unwrap_or_default();
+                if let Some(i) = self.pool.iter().position(|&v| v == value) {
+                    self.pool.remove(i);
+                    self.scores.push(value);
+                }
+                self.abilities_done = self.scores.len() == Ability::ALL.len();
+            }
+            "raise" | "lower" => {
+                if let Some(i) = Ability::ALL.iter().position(|a| a.short() == arg) {
+                    self.scores[i] += if kind == "raise" { 1 } else { -1 };
+                }
+            }
+            "abilities_done" => self.abilities_done = true,
+            "race" => {
+                self.race = srd_races().get(arg);
+                self.subrace = None;
+                self.ability_bonus_choices.clear();
+                self.languages.clear();
+            }
+            "subrace" => {
+                self.subrace = srd_races().subrace(arg).map(|s| s.name.clone());
+            }
+            "race_bonus" => {
+                if let Some(&a) = Ability::ALL.iter().find(|a| a.short() == arg) {
+                    self.ability_bonus_choices.push(a);
+                }
+            }
+            "language" => {
+                if let Some(l) = self
+                    .language_options()
+                    .into_iter()
+                    .find(|l| slugify(l) == arg)
+                {
+                    self.languages.push(l.to_string());
+                }
+            }
+            "class" => {
+                self.class = srd_classes().get(arg);
+                for stack in self.class.map_or(&[][..], |c| c.starting_items.as_slice()) {
+                    add_item(&mut self.items, stack);
+                }
+            }
+            "background" => {
+                self.background = BACKGROUNDS.iter().position(|(b, _)| slugify(b) == arg);
+            }
+            "skill" => {
+                if let Some(s) = self.skill_options().into_iter().find(|s| slugify(s) == arg) {
+                    self.skills.push(s.to_string());
+                }
+            }
+            "cantrip" | "spell" => {
+                let level = if kind == "cantrip" { 0 } else { 1 };
+                if let Some(spell) = self
+                    .spell_options(level)
+                    .into_iter()
+                    .find(|s| slugify(&s.name) == arg)
+                {
+                    let list = if level == 0 {
+                        &mut self.cantrips
+                    } else {
+                        &mut self.spells
+                    };
+                    list.push(spell.name.clone());
+                }
+            }
+            "equipment" => {
+                let i: usize = arg.parse().unwrap_or_default();
+                let Step::EquipmentOption(n) = step else {
+                    return Ok(());
+                };
+                let Some(option) = self.equipment_choice(n).and_then(|o| o.get(i)) else {
+                    return Ok(());
+                };
+                if option.is_pick_list() {
+                    self.picking = Some((i, 0));
+                } else {
+                    for stack in &option.items {
+                        add_item(&mut self.items, stack);
+                    }
+                    self.log = Some(format!("You take {}.", option_label(option)));
+                    self.equipment_done += 1;
+                }
+            }
+            "item" => {
+                let Step::EquipmentItem(n, o) = step else {
+                    return Ok(());
+                };
+                let Some(option) = self.equipment_choice(n).and_then(|opts| opts.get(o)) else {
+                    return Ok(());
+                };
+                if let Some(stack) = option.items.iter().find(|s| s.item == arg) {
+                    add_item(&mut self.items, stack);
+                    self.log = Some(format!("You take {}.", stack_label(stack)));
+                }
+                let taken = self.picking.map_or(0, |(_, taken)| taken) + 1;
+                if taken >= option.choose.max(1) {
+                    self.picking = None;
+                    self.equipment_done += 1;
+                } else {
+                    self.picking = Some((o, taken));
+                }
+            }
+            "finish" => {
+                self.character()?;
+                self.done = true;
+            }
+            "start_over" => {
+                let dice = self.dice.clone();
+                *self = Self::with_dice(&self.name, dice);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn choose_method(&mut self, method: &str) {
+        match method {
+            "roll" => {
+                self.method = Some(AbilityMethod::Roll);
+                let rolls: Vec<String> = (0..6)
+                    .map(|_| {
+                        let roll = self
+                            .dice
+                            .roll_expr("4d6kh3")
+                            .expect("4d6kh3 is a valid dice expression");
+                        self.pool.push(roll.total);
+                        roll.to_string()
+                    })
+                    .collect();
+                self.log = Some(rolls.join("\n"));
+            }
+            "point_buy" => {
+                self.method = Some(AbilityMethod::PointBuy);
+                self.scores = vec![8; Ability::ALL.len()];
+            }
+            _ => {
+                self.method = Some(AbilityMethod::StandardArray);
+                self.pool = STANDARD_ARRAY.to_vec();
+            }
+        }
+    }
+
+    fn points_left(&self) -> i32 {
+        POINT_BUY_BUDGET - self.scores.iter().map(|&s| point_buy_cost(s)).sum::<i32>()
+    }
+
+    /// "STR 15, DEX 14" for the scores given so far, before racial bonuses.
+    fn scores_line(&self) -> String {
+        self.scores
+            .iter()
+            .zip(Ability::ALL)
+            .map(|(s, a)| format!("{} {}", a.short().to_uppercase(), s))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn language_options(&self) -> Vec<&'static str> {
+        let Some(race) = self.race else {
+            return Vec::new();
+        };
+        race.language_options
+            .iter()
+            .filter(|l| !race.grants.languages.contains(l) && !self.languages.contains(l))
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn skill_options(&self) -> Vec<&'static str> {
+        let known: Vec<String> = self.known_skills().iter().map(|s| slugify(s)).collect();
+        self.class
+            .and_then(|c| c.skill_choices.as_ref())
+            .map_or(Vec::new(), |c| {
+                c.from
+                    .iter()
+                    .filter(|s| !known.contains(&slugify(s)))
+                    .map(String::as_str)
+                    .collect()
+            })
+    }
+
+    /// Skills from the race, background and class picks.
+    fn known_skills(&self) -> Vec<String> {
+        let mut skills: Vec<String> = self
+            .race
+            .into_iter()
+            .flat_map(|r| r.grants.skills.iter().cloned())
+            .chain(
+                self.subrace
+                    .as_deref()
+                    .and_then(|s| srd_races().subrace(s))
+                    .into_iter()
+                    .flat_map(|s| s.grants.skills.iter().cloned()),
+            )
+            .collect();
+        skills.extend(self.background_skills());
+        skills.extend(self.skills.iter().cloned());
+        skills
+    }
+
+    /// The two skills the background grants.
+    fn background_skills(&self) -> Vec<String> {
+        self.background
+            .map_or(&[][..], |b| &BACKGROUNDS[b].1[..])
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    /// Cantrips and 1st-level spells to pick: spells known from the class
+    /// table, or for classes that prepare spells, the casting modifier plus one
+    /// (at least one).
+    fn spell_picks(&self) -> (usize, usize) {
+        let Some(row) = self.class.and_then(|c| srd_levels().get(&c.name, 1)) else {
+            return (0, 0);
+        };
+        let spells = match row.spells_known {
+            _ if row.spell_slots[0] == 0 => 0,
+            Some(n) => n as usize,
+            None => {
+                let modifier = self.character().map_or(0, |c| c.spellcasting_modifier());
+                (modifier + 1).max(1) as usize
+            }
+        };
+        (row.cantrips_known as usize, spells)
+    }
+
+    /// Class spells of `level` not picked yet.
+    fn spell_options(&self, level: u8) -> Vec<&'static Spell> {
+        let Some(class) = self.class else {
+            return Vec::new();
+        };
+        let known = if level == 0 {
+            &self.cantrips
+        } else {
+            &self.spells
+        };
+        srd_spells()
+            .for_class(&class.name, level)
+            .into_iter()
+            .filter(|s| !known.contains(&s.name))
+            .collect()
+    }
+
+    fn equipment_choice(&self, n: usize) -> Option<&'static [EquipmentOption]> {
+        self.class
+            .and_then(|c| c.starting_choices.get(n))
+            .map(Vec::as_slice)
+    }
+
+    /// The best armor, shield and weapon among the starting items that the
+    /// class can use.
+    fn equipped(&self) -> Equipment {
+        let library = srd_equipment();
+        let proficient = |category| self.class.is_none_or(|c| c.has_armor(category));
+        let armor = self
+            .items
+            .iter()
+            .filter_map(|s| Some((s, library.armor(&s.item)?)))
+            .filter(|(_, a)| a.category != ArmorCategory::Shield && proficient(a.category))
+            .max_by_key(|(_, a)| a.base_ac)
+            .map(|(s, _)| s.item.clone());
+        let shield = proficient(ArmorCategory::Shield)
+            && self
+                .items
+                .iter()
+                .filter_map(|s| library.armor(&s.item))
+                .any(|a| a.category == ArmorCategory::Shield);
+        let weapon = self
+            .items
+            .iter()
+            .filter_map(|s| Some((s, library.weapon(&s.item)?)))
+            .filter(|(_, w)| !(shield && w.has(WeaponProperty::TwoHanded)))
+            .max_by_key(|(_, w)| {
+                (
+                    self.class.is_none_or(|c| c.has_weapon(w)),
+                    !w.ranged,
+                    w.damage.max_dice(),
+                )
+            })
+            .map(|(s, _)| s.item.clone());
+        Equipment {
+            weapon,
+            armor,
+            shield,
+            two_handed: false,
+        }
+    }
+
+    /// The character as built so far, in the pregen character JSON format:
+    /// base ability scores, with racial bonuses applied on load.
+    pub fn character_json(&self) -> String {
+        let score = |i: usize| self.scores.get(i).copied().unwrap_or(10);
+        let mut skills = self.background_skills();
+        skills.extend(self.skills.iter().cloned());
+        json!({
+            "name": self.name,
+            "class": self.class.map(|c| &c.name),
+            "race": self.race.map(|r| &r.name),
+            "subrace": self.subrace,
+            "ability_bonus_choices": self
+                .ability_bonus_choices
+                .iter()
+                .map(|a| a.short())
+                .collect::<Vec<_>>(),
+            "languages": self.languages,
+            "background": self.background.map(|b| BACKGROUNDS[b].0),
+            "level": 1,
+            "proficiency_bonus": 2,
+            "abilities": {
+                "str": score(0),
+                "dex": score(1),
+                "con": score(2),
+                "int": score(3),
+                "wis": score(4),
+                "cha": score(5),
+            },
+            "skill_proficiencies": skills,
+            "cantrips": self.cantrips,
+            "spells": self.spells,
+            "equipment": self.equipped(),
+            "items": self.items,
+        })
+        .to_string()
+    }
+
+    /// The validated character as built so far.
+    pub fn character(&self) -> Result<Character, EngineError> {
+        Character::from_json(&self.character_json())
+    }
+
+    /// Character JSON for the finished character, ready for `Engine::new`.
+    pub fn finish(&self) -> Result<String, EngineError> {
+        if !self.done {
+            return Err(EngineError::InvalidInput(
+                "character creation isn't finished".to_string(),
+            ));
+        }
+        Ok(self.character_json())
+    }
+
+    fn review_text(&self) -> Vec<String> {
+        let c = match self.character() {
+            Ok(c) => c,
+            Err(e) => return vec![format!("This character isn't valid: {}", e)],
+        };
+        let heritage = match (&c.subrace, &c.race) {
+            (Some(s), _) => s.clone(),
+            (None, Some(r)) => r.clone(),
+            (None, None) => String::new(),
+        };
+        let abilities: Vec<String> = Ability::ALL
+            .iter()
+            .map(|&a| {
+                format!(
+                    "{} {} ({:+})",
+                    a.short().to_uppercase(),
+                    c.abilities.score(a),
+                    c.abilities.modifier(a)
+                )
+            })
+            .collect();
+        let items: Vec<String> = c.items.iter().map(stack_label).collect();
+        let mut text = vec![
+            format!(
+                "{}, {} {}, {}.",
+                c.name,
+                heritage,
+                c.class.as_deref().unwrap_or_default(),
+                c.background.as_deref().unwrap_or_default()
+            ),
+            abilities.join(", "),
+            format!(
+                "HP {}, AC {}, speed {} ft.",
+                c.max_hp,
+                c.armor_class(),
+                c.speed()
+            ),
+            format!("Skills: {}.", c.skill_proficiencies.join(", ")),
+            format!("Languages: {}.", c.known_languages().join(", ")),
+            format!("Equipment: {}.", items.join(", ")),
+        ];
+        if !c.cantrips.is_empty() || !c.spells.is_empty() {
+            let spells: Vec<&str> = c
+                .cantrips
+                .iter()
+                .chain(&c.spells)
+                .map(String::as_str)
+                .collect();
+            text.push(format!("Spells: {}.", spells.join(", ")));
+        }
+        text
+    }
+}
+
+/// "CON +2, WIS +1"
+fn bonus_list(bonuses: &[(Ability, i32)]) -> Vec<String> {
+    bonuses
+        .iter()
+        .map(|(a, b)| format!("{} {:+}", a.short().to_uppercase(), b))
+        .collect()
+}
+
+/// "Longbow" or "Arrow x20".
+fn stack_label(stack: &ItemStack) -> String {
+    let name = srd_equipment()
+        .get(&stack.item)
+        .map_or(stack.item.as_str(), |i| i.name.as_str());
+    if stack.quantity > 1 {
+        format!("{} x{}", name, stack.quantity)
+    } else {
+        name.to_string()
+    }
+}
+
+/// "Fire Bolt (Evocation)". Spells the engine can't resolve in play yet say so.
+fn spell_label(spell: &Spell) -> String {
+    if spell.effect.is_some() {
+        format!("{} ({})", spell.name, spell.school)
+    } else {
+        format!("{} ({}, story use only)", spell.name, spell.school)
+    }
+}
+
+/// "Leather, Longbow, Arrow x20", or for a pick list "A martial weapon".
+fn option_label(option: &EquipmentOption) -> String {
+    if !option.is_pick_list() {
+        let items: Vec<String> = option.items.iter().map(stack_label).collect();
+        return items.join(", ");
+    }
+    let library = srd_equipment();
+    let categories: Vec<Option<&str>> = option
+        .items
+        .iter()
+        .map(|s| match library.get(&s.item).map(|i| &i.kind) {
+            Some(ItemKind::Weapon(w)) => Some(w.category.as_str()),
+            _ => None,
+        })
+        .collect();
+    match categories.first() {
+        Some(Some(category)) if categories.iter().all(|c| c == &Some(*category)) => {
+            if option.choose > 1 {
+                format!("{} {} weapons", option.choose, category.to_lowercase())
+            } else {
+                format!("A {} weapon", category.to_lowercase())
+            }
+        }
+        _ if option.items.len() <= 5 => {
+            let items: Vec<String> = option.items.iter().map(stack_label).collect();
+            format!("One of: {}", items.join(", "))
+        }
+        _ => format!("One of {} items", option.items.len()),
+    }
+}
+
+fn add_item(items: &mut Vec<ItemStack>, stack: &ItemStack) {
+    match items.iter_mut().find(|s| s.item == stack.item) {
+        Some(s) => s.quantity = s.quantity.saturating_add(stack.quantity),
+        None => items.push(stack.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(builder: &CharacterBuilder) -> Vec<String> {
+        builder.view().choices.into_iter().map(|c| c.id).collect()
+    }
+
+    fn unknown(node_id: &str, choice_id: &str) -> Result<(), EngineError> {
+        Err(EngineError::UnknownChoice {
+            node_id: node_id.to_string(),
+            choice_id: choice_id.to_string(),
+        })
+    }
+
+    #[test]
+    fn malformed_choice_ids() {
+        let mut b = CharacterBuilder::new("Kara", 1);
+        for id in [
+            "",
+            ":",
+            "method",
+            "method:",
+            "method:roll:again",
+            "METHOD:ROLL",
+            "assign:15",
+            "finish",
+        ] {
+            assert_eq!(b.choose(id), unknown("builder_abilities", id));
+        }
+        assert_eq!(ids(&b).len(), 3);
+    }
+
+    #[test]
+    fn huge_and_repeated_numbers() {
+        let mut b = CharacterBuilder::new("Kara", 1);
+        b.choose("method:standard_array").unwrap();
+        for id in [
+            "assign:99999999999999999999",
+            "assign:-15",
+            "assign:16",
+            "assign:",
+        ] {
+            assert_eq!(b.choose(id), unknown("builder_abilities", id));
+        }
+        b.choose("assign:15").unwrap();
+        assert_eq!(
+            b.choose("assign:15"),
+            unknown("builder_abilities", "assign:15")
+        );
+        for v in &STANDARD_ARRAY[1..] {
+            b.choose(&format!("assign:{}", v)).unwrap();
+        }
+        assert_eq!(b.scores, STANDARD_ARRAY);
+        assert!(ids(&b).iter().all(|id| id.starts_with("race:")));
+    }
+
+    #[test]
+    fn point_buy_stays_in_budget() {
+        let mut b = CharacterBuilder::new("Kara", 1);
+        b.choose("method:point_buy").unwrap();
+        assert_eq!(
+            b.choose("lower:str"),
+            unknown("builder_abilities", "lower:str")
+        );
+        while ids(&b).iter().any(|id| id == "raise:str") {
+            b.choose("raise:str").unwrap();
+        }
+        assert_eq!(b.scores[0], 15);
+        for a in ["dex", "con", "int", "wis", "cha"] {
+            let raise = format!("raise:{}", a);
+            while ids(&b).contains(&raise) {
+                b.choose(&raise).unwrap();
+            }
+        }
+        assert!(b.points_left() >= 0);
+        assert!(b.scores.iter().all(|s| (8..=15).contains(s)));
+    }
+
+    #[test]
+    fn first_choice_every_time_builds_a_valid_character() {
+        let mut b = CharacterBuilder::new("Kara", 7);
+        assert!(matches!(b.finish(), Err(EngineError::InvalidInput(_))));
+        for _ in 0..200 {
+            if b.is_done() {
+                break;
+            }
+            let first = ids(&b)
+                .into_iter()
+                .next()
+                .expect("every screen has a choice");
+            b.choose(&first).unwrap();
+        }
+        assert!(b.is_done());
+        let character = Character::from_json(&b.finish().unwrap()).unwrap();
+        assert_eq!(character.name, "Kara");
+        assert_eq!(character.level, 1);
+    }
+
+    /// Standard array in order, then each screen's `wanted` choice if it
+    /// offers one, or its first choice otherwise, up to the review screen.
+    fn build(wanted: &[&str]) -> CharacterBuilder {
+        let mut b = CharacterBuilder::new("Kara", 1);
+        b.choose("method:standard_array").unwrap();
+        for v in STANDARD_ARRAY {
+            b.choose(&format!("assign:{}", v)).unwrap();
+        }
+        while !ids(&b).iter().any(|id| id == "finish") {
+            let ids = ids(&b);
+            let pick = ids
+                .iter()
+                .find(|id| wanted.contains(&id.as_str()))
+                .unwrap_or(&ids[0]);
+            b.choose(pick).unwrap();
+        }
+        b
+    }
+
+    #[test]
+    fn rolling_keeps_the_best_three_of_four_d6() {
+        let mut b = CharacterBuilder::new("Kara", 3);
+        b.choose("method:roll").unwrap();
+        let mut dice = DiceRoller::new(3);
+        let expected: Vec<i32> = (0..6)
+            .map(|_| dice.roll_expr("4d6kh3").unwrap().total)
+            .collect();
+        assert_eq!(b.pool, expected);
+        assert!(b.pool.iter().all(|s| (3..=18).contains(s)));
+        assert_eq!(b.view().log.unwrap().lines().count(), 6);
+    }
+
+    #[test]
+    fn a_hill_dwarf_soldier_fighter_from_the_srd() {
+        let mut b = build(&[
+            "race:dwarf",
+            "class:fighter",
+            "background:soldier",
+            "skill:perception",
+            "skill:survival",
+        ]);
+        let kara = b.character().unwrap();
+        assert_eq!(kara.subrace.as_deref(), Some("Hill Dwarf"));
+        // STR 15, DEX 14, CON 13 + 2, INT 12, WIS 10 + 1, CHA 8.
+        assert_eq!((kara.abilities.con, kara.abilities.wis), (15, 11));
+        // d10 + CON +2 + Dwarven Toughness.
+        assert_eq!(kara.max_hp, 13);
+        // The soldier's skills, then the two fighter picks.
+        assert_eq!(
+            kara.skill_proficiencies,
+            ["Athletics", "Intimidation", "Perception", "Survival"]
+        );
+        // Chain mail and a shield from the first starting equipment option.
+        assert_eq!(kara.equipment.armor.as_deref(), Some("chain_mail"));
+        assert!(kara.equipment.shield);
+        assert_eq!(kara.armor_class(), 18);
+        assert!(kara
+            .items
+            .iter()
+            .any(|s| s.item == "crossbow_bolt" && s.quantity == 20));
+
+        b.choose("finish").unwrap();
+        assert!(b.is_done());
+        assert_eq!(b.finish().unwrap(), b.character_json());
+    }
+
+    #[test]
+    fn half_elves_pick_bonuses_and_wizards_pick_spells() {
+        let mut b = CharacterBuilder::new("Kara", 1);
+        b.choose("method:standard_array").unwrap();
+        for v in STANDARD_ARRAY {
+            b.choose(&format!("assign:{}", v)).unwrap();
+        }
+        b.choose("race:half_elf").unwrap();
+        // Half-elves already get CHA +2.
+        assert!(!ids(&b).contains(&"race_bonus:cha".to_string()));
+        b.choose("race_bonus:int").unwrap();
+        b.choose("race_bonus:dex").unwrap();
+        while ids(&b).iter().any(|id| id.starts_with("language:")) {
+            let first = ids(&b).remove(0);
+            b.choose(&first).unwrap();
+        }
+        b.choose("class:wizard").unwrap();
+        b.choose("background:sage").unwrap();
+        while !ids(&b)
+            .iter()
+            .any(|id| id.starts_with("equipment:") || id.starts_with("item:"))
+        {
+            let first = ids(&b).remove(0);
+            b.choose(&first).unwrap();
+        }
+        let wizard = b.character().unwrap();
+        assert_eq!((wizard.abilities.int_, wizard.abilities.cha), (13, 10));
+        // Three SRD wizard cantrips, and INT +1 plus one prepared spells.
+        assert_eq!(wizard.cantrips.len(), 3);
+        assert_eq!(wizard.spells.len(), 2);
+        assert!(wizard
+            .spells
+            .iter()
+            .all(|s| srd_spells().get(s).is_some_and(|s| s.level == 1)));
+    }
+}
@@ -1,7 +1,27 @@
-use crate::combat::{hero_attack, monster_attack, Encounter, Monster};
-use crate::dice::DiceRoller;
-use crate::rules::{ability_from_str, Character};
+use crate::combat::{cast_spell, hero_attack, Combatant, Encounter, Monster};
+use crate::conditions::{ActiveCondition, Condition};
+use crate::dice::{D20Test, DiceExpr, DiceRoller};
+use crate::effects::{apply_conditions, apply_effects, Effect};
+use crate::error::EngineError;
+use crate::expr::Expr;
+use crate::inventory::{inventory, item_info, use_item, ItemDef};
+use crate::levels::{xp_for_cr, LevelUp};
+use crate::monsters::{slugify, srd_library, MonsterLibrary};
+use crate::rules::{ability_from_str, Ability, Character, DeathSaves, HouseRules};
+use crate::skills::{srd_skills, Skill};
+use crate::spells::{slot_name, Spell, SpellEffect};
+use crate::template::{Paragraph, Template};
+use crate::vars::{Value, Vars};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How many nodes the hero can be sent through on arrival, by saving throws,
+/// knock-outs and instant fights, before the engine gives up on a loop.
+const MAX_CHAINED_ARRIVALS: usize = 64;
+
+/// Choice ids the engine answers itself during a fight. An authored choice
+/// with one of these ids only lends its label; "attack" isn't even offered.
+pub const COMBAT_CHOICE_IDS: [&str; 3] = ["attack", "continue", "death_save"];
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
@@ -13,8 +33,119 @@ pub enum NodeType {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SkillCheck {
+    /// Ability to roll. Optional when `skill` is given; set it to override the
+    /// skill's usual ability, e.g. Strength (Intimidation).
+    #[serde(default)]
+    pub ability: Option<String>,
+    /// Skill name or slug from skills.json: "Stealth", "sleight_of_hand".
+    #[serde(default)]
+    pub skill: Option<String>,
+    pub dc: i32,
+    #[serde(default)]
+    pub success_next: Option<String>,
+    #[serde(default)]
+    pub failure_next: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The situation helps or hinders: roll two d20s and keep the higher or
+    /// lower. Both together cancel out.
+    #[serde(default)]
+    pub advantage: bool,
+    #[serde(default)]
+    pub disadvantage: bool,
+    /// The sense the check relies on. Blinded or deafened heroes fail it outright.
+    #[serde(default)]
+    pub sense: Option<Sense>,
+    /// Conditions the hero gains on a failed check, e.g. poisoned by a needle trap.
+    #[serde(default)]
+    pub failure_conditions: Vec<ActiveCondition>,
+    /// Effects applied on a success, before moving on.
+    #[serde(default)]
+    pub success_effects: Vec<Effect>,
+    /// Effects applied on a failure, before moving on.
+    #[serde(default)]
+    pub failure_effects: Vec<Effect>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sense {
+    Sight,
+    Hearing,
+}
+
+impl SkillCheck {
+    /// The ability and optional skill this check rolls.
+    pub fn resolve(&self) -> Result<(Ability, Option<&'static Skill>), String> {
+        let skill = match &self.skill {
+            Some(name) => Some(
+                srd_skills()
+                    .get(name)
+                    .ok_or_else(|| format!("Unknown skill '{}' in skill check", name))?,
+            ),
+            None => None,
+        };
+        let ability = match (&self.ability, skill) {
+            (Some(a), _) => ability_from_str(a)
+                .ok_or_else(|| format!("Unknown ability '{}' in skill check", a))?,
+            (None, Some(s)) => s.ability,
+            (None, None) => return Err("Skill check needs an ability or a skill".to_string()),
+        };
+        Ok((ability, skill))
+    }
+
+    /// The d20 test for this check: the authored flags plus the hero's conditions.
+    pub fn d20_test(&self, character: &Character) -> D20Test {
+        let mut test = D20Test::new();
+        if self.advantage {
+            test.add_advantage("circumstance");
+        }
+        if self.disadvantage {
+            test.add_disadvantage("circumstance");
+        }
+        character.conditions.checking(&mut test);
+        test
+    }
+
+    /// The condition that makes the hero fail this check outright, if any.
+    pub fn auto_fail(&self, character: &Character) -> Option<Condition> {
+        let condition = match self.sense? {
+            Sense::Sight => Condition::Blinded,
+            Sense::Hearing => Condition::Deafened,
+        };
+        character.conditions.has(condition).then_some(condition)
+    }
+}
+
+/// A saving throw the hero must make: a trap, a poison needle, a dragon's
+/// breath. Damage is taken in full on a failure and halved on a success.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavingThrow {
+    /// "dex", "Constitution", ...
     pub ability: String,
     pub dc: i32,
+    /// Damage dice on a failed save, e.g. "2d6" or "8d6".
+    #[serde(default)]
+    pub damage: Option<String>,
+    /// "Fire", "Poison": halved again if the hero resists it.
+    #[serde(default)]
+    pub damage_type: Option<String>,
+    /// What the save is against, for racial advantage: "poison", "frightened".
+    #[serde(default)]
+    pub against: Option<String>,
+    #[serde(default)]
+    pub advantage: bool,
+    #[serde(default)]
+    pub disadvantage: bool,
+    /// Conditions the hero gains on a failed save.
+    #[serde(default)]
+    pub failure_conditions: Vec<ActiveCondition>,
+    /// Effects applied on a success, before moving on.
+    #[serde(default)]
+    pub success_effects: Vec<Effect>,
+    /// Effects applied on a failure, before moving on.
+    #[serde(default)]
+    pub failure_effects: Vec<Effect>,
     #[serde(default)]
     pub success_next: Option<String>,
     #[serde(default)]
@@ -23,6 +154,37 @@ pub struct SkillCheck {
     pub description: Option<String>,
 }
 
+impl SavingThrow {
+    /// The ability saved with and the damage dice, if any.
+    pub fn resolve(&self) -> Result<(Ability, Option<DiceExpr>), String> {
+        let ability = ability_from_str(&self.ability)
+            .ok_or_else(|| format!("Unknown ability '{}' in saving throw", self.ability))?;
+        let damage = match &self.damage {
+            Some(d) => Some(
+                DiceExpr::parse(d)
+                    .map_err(|e| format!("Bad damage '{}' in saving throw: {}", d, e))?,
+            ),
+            None => None,
+        };
+        Ok((ability, damage))
+    }
+
+    /// The d20 test for this save: the authored flags plus racial traits.
+    pub fn d20_test(&self, character: &Character) -> D20Test {
+        let mut test = match &self.against {
+            Some(effect) => character.save_test(effect),
+            None => D20Test::new(),
+        };
+        if self.advantage {
+            test.add_advantage("circumstance");
+        }
+        if self.disadvantage {
+            test.add_disadvantage("circumstance");
+        }
+        test
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Choice {
     pub id: String,
@@ -31,12 +193,69 @@ pub struct Choice {
     pub next: Option<String>,
     #[serde(default)]
     pub skill_check: Option<SkillCheck>,
+    #[serde(default)]
+    pub saving_throw: Option<SavingThrow>,
+    #[serde(default)]
+    pub rest: Option<RestSpec>,
+    /// A condition in the expression language (see `Expr`), e.g.
+    /// `"flag.spared_goblin and character.level >= 3"`. The choice is hidden
+    /// while it doesn't hold.
+    #[serde(default)]
+    pub requires: Option<String>,
+    /// Show the choice disabled instead of hiding it while `requires` isn't met.
+    #[serde(default)]
+    pub show_locked: bool,
+    /// Story variables set when the choice is taken, before anything else.
+    #[serde(default)]
+    pub set: BTreeMap<String, Value>,
+    /// Counters increased (or, with a negative amount, decreased) when the
+    /// choice is taken.
+    #[serde(default)]
+    pub increment: BTreeMap<String, i64>,
+    /// Effects applied when the choice is taken, before any check, save or rest.
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+}
+
+impl Choice {
+    /// Whether `requires` holds. One that doesn't parse never does; validation
+    /// reports it.
+    pub fn available(&self, state: &GameState) -> bool {
+        self.requires
+            .as_deref()
+            .is_none_or(|r| Expr::parse(r).is_ok_and(|e| e.test(state)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RestKind {
+    Short,
+    Long,
+}
+
+/// A choice that rests. A short rest lets the player spend hit dice before
+/// moving on to the choice's `next`; a long rest is applied at once.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RestSpec {
+    #[serde(rename = "type")]
+    pub kind: RestKind,
+    /// Percent chance (d100) that something interrupts the rest before it starts.
+    #[serde(default)]
+    pub interruption_chance: u32,
+    /// Where an interrupted rest leads, e.g. an ambush. Without it the party
+    /// simply stays put, unrested.
+    #[serde(default)]
+    pub interrupted_next: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MonsterSpec {
     pub r#ref: String,
     pub count: u8,
+    /// Conditions the monsters start the fight with, e.g. prone or invisible.
+    #[serde(default)]
+    pub conditions: Vec<ActiveCondition>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -51,8 +270,9 @@ pub struct Node {
     pub kind: NodeType,
     #[serde(default)]
     pub title: Option<String>,
+    /// Paragraphs, each plain or conditional, with `{...}` placeholders.
     #[serde(default)]
-    pub text: Vec<String>,
+    pub text: Vec<Paragraph>,
     #[serde(default)]
     pub choices: Vec<Choice>,
     #[serde(default)]
@@ -61,28 +281,129 @@ pub struct Node {
     pub on_victory: Option<String>,
     #[serde(default)]
     pub on_defeat: Option<String>,
+    /// XP granted each time the hero enters this node.
+    #[serde(default)]
+    pub milestone_xp: u32,
+    /// A saving throw made on arrival, before any fight starts.
+    #[serde(default)]
+    pub saving_throw: Option<SavingThrow>,
+    /// Story variables set each time the hero enters this node.
+    #[serde(default)]
+    pub set: BTreeMap<String, Value>,
+    /// Counters increased each time the hero enters this node.
+    #[serde(default)]
+    pub increment: BTreeMap<String, i64>,
+    /// Effects applied each time the hero enters this node, before any saving
+    /// throw or fight.
+    #[serde(default)]
+    pub effects: Vec<Effect>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GameState {
     pub character: Character,
     pub current_node_id: String,
     pub encounter: Option<Encounter>,
     pub last_log: Option<String>,
+    /// Set while the player is spending hit dice on a short rest.
+    #[serde(default)]
+    pub short_rest: Option<ShortRest>,
+    /// Set while the player is making level-up choices.
+    #[serde(default)]
+    pub level_up: Option<LevelUp>,
+    /// Story flags, counters and text set by choices and nodes.
+    #[serde(default)]
+    pub vars: Vars,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShortRest {
+    /// Where to go once the rest is over.
+    #[serde(default)]
+    pub next: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ChoiceView {
     pub id: String,
     pub label: String,
+    /// Shown but can't be picked: its `requires` isn't met and the choice
+    /// asked to stay visible.
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+impl ChoiceView {
+    pub fn new(id: String, label: String) -> Self {
+        Self {
+            id,
+            label,
+            disabled: false,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CharacterSummary {
     pub name: String,
     pub level: u8,
+    #[serde(default)]
+    pub xp: u32,
+    pub current_hp: i32,
+    pub max_hp: i32,
+    pub ac: i32,
+    /// At 0 hit points and making death saves.
+    #[serde(default)]
+    pub dying: bool,
+    /// Set while the character is at 0 hit points.
+    #[serde(default)]
+    pub death_saves: Option<DeathSaves>,
+    /// Active conditions by name: "Poisoned".
+    #[serde(default)]
+    pub conditions: Vec<String>,
+    /// Unspent spell slots per spell level from 1st, up to the highest the
+    /// character has. Empty for non-casters.
+    #[serde(default)]
+    pub spell_slots: Vec<u32>,
+    #[serde(default)]
+    pub gold: u32,
+}
+
+impl CharacterSummary {
+    pub fn new(character: &Character) -> Self {
+        Self {
+            name: character.name.clone(),
+            level: character.level,
+            xp: character.xp,
+            current_hp: character.current_hp,
+            max_hp: character.max_hp,
+            ac: character.armor_class(),
+            dying: character.is_dying(),
+            death_saves: (character.current_hp <= 0).then_some(character.death_saves),
+            conditions: character.conditions.names(),
+            spell_slots: character.slots_left_by_level(),
+            gold: character.gold,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TurnView {
+    pub name: String,
+    pub initiative: i32,
+    pub is_hero: bool,
     pub current_hp: i32,
     pub max_hp: i32,
+    /// True for whoever is acting now.
+    pub active: bool,
+    #[serde(default)]
+    pub conditions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CombatView {
+    pub round: u32,
+    pub turn_order: Vec<TurnView>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -93,6 +414,8 @@ pub struct NodeView {
     pub character_summary: CharacterSummary,
     #[serde(default)]
     pub log: Option<String>,
+    #[serde(default)]
+    pub combat: Option<CombatView>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -101,43 +424,176 @@ pub struct Campaign {
     pub title: String,
     #[serde(rename = "startNodeId")]
     pub start_node_id: String,
+    /// Optional rule variants: `{"crits": "max_plus_roll", "defeat": "captured"}`.
+    #[serde(default)]
+    pub house_rules: HouseRules,
+    /// Items of the campaign's own, alongside SRD equipment.
+    #[serde(default)]
+    pub items: Vec<ItemDef>,
     pub nodes: Vec<Node>,
 }
 
 impl Campaign {
-    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(s)
+    pub fn from_json(s: &str) -> Result<Self, EngineError> {
+        serde_json::from_str(s).map_err(|e| EngineError::invalid_json("campaign", e))
+    }
+
+    /// Make sure every encounter monster ref resolves against `library`.
+    pub fn check_monster_refs(&self, library: &MonsterLibrary) -> Result<(), EngineError> {
+        for node in &self.nodes {
+            let Some(spec) = &node.encounter else {
+                continue;
+            };
+            for m in &spec.monsters {
+                if !library.contains(&m.r#ref) {
+                    return Err(EngineError::UnknownMonster {
+                        node_id: node.id.clone(),
+                        monster_ref: m.r#ref.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Make sure every expression in the campaign parses.
+    pub fn check_expressions(&self) -> Result<(), EngineError> {
+        for node in &self.nodes {
+            for p in &node.text {
+                let bad = p
+                    .when()
+                    .and_then(|w| Expr::parse(w).err().map(|m| format!("when: {}", m)))
+                    .or_else(|| {
+                        Template::parse(p.text())
+                            .err()
+                            .map(|m| format!("text: {}", m))
+                    });
+                if let Some(message) = bad {
+                    return Err(EngineError::InvalidExpression {
+                        node_id: node.id.clone(),
+                        choice_id: None,
+                        message,
+                    });
+                }
+            }
+            for c in &node.choices {
+                if let Some(Err(message)) = c.requires.as_deref().map(Expr::parse) {
+                    return Err(EngineError::InvalidExpression {
+                        node_id: node.id.clone(),
+                        choice_id: Some(c.id.clone()),
+                        message: format!("requires: {}", message),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn has_node(&self, id: &str) -> bool {
+        self.nodes.iter().any(|n| n.id == id)
     }
 
-    fn find_node(&self, id: &str) -> &Node {
+    fn find_node(&self, id: &str) -> Result<&Node, EngineError> {
         self.nodes
             .iter()
             .find(|n| n.id == id)
-            .unwrap_or_else(|| panic!("Node not found: {}", id))
+            .ok_or_else(|| EngineError::MissingNode(id.to_string()))
     }
 
-    pub fn view_node(&self, state: &GameState) -> NodeView {
-        let node = self.find_node(&state.current_node_id);
-        let choices = match node.kind {
-            NodeType::End => Vec::new(),
-            _ => node
-                .choices
+    pub fn view_node(&self, state: &GameState) -> Result<NodeView, EngineError> {
+        let node = self.find_node(&state.current_node_id)?;
+        let authored = node.choices.iter().filter_map(|c| {
+            let available = c.available(state);
+            (available || c.show_locked).then(|| ChoiceView {
+                disabled: !available,
+                ..ChoiceView::new(c.id.clone(), c.label.clone())
+            })
+        });
+        let choices = match (&node.kind, &state.encounter) {
+            // With nowhere to send a dead hero, the run ends where they fell.
+            _ if state.character.is_dead() && node.on_defeat.is_none() => Vec::new(),
+            _ if state.level_up.is_some() => state
+                .level_up
                 .iter()
-                .map(|c| ChoiceView {
-                    id: c.id.clone(),
-                    label: c.label.clone(),
-                })
+                .flat_map(|l| l.choices(&state.character))
+                .map(|(id, label)| ChoiceView::new(id, label))
                 .collect(),
+            (NodeType::End, _) => Vec::new(),
+            _ if state.short_rest.is_some() => {
+                let hero = &state.character;
+                let mut choices = Vec::new();
+                if hero.hit_dice_remaining() > 0 && hero.current_hp < hero.max_hp {
+                    choices.push(ChoiceView::new(
+                        "spend_hit_die".to_string(),
+                        format!(
+                            "Spend a hit die ({}, {} left)",
+                            hero.hit_die_expr()
+                                .map(|e| e.to_string())
+                                .unwrap_or_else(|_| format!("d{}", hero.hit_die())),
+                            hero.hit_dice_remaining()
+                        ),
+                    ));
+                }
+                choices.push(ChoiceView::new(
+                    "finish_rest".to_string(),
+                    "Finish resting".to_string(),
+                ));
+                choices
+            }
+            // During a fight the generic "attack" choice becomes one choice per living target.
+            (NodeType::Combat, Some(e)) if e.in_progress && state.character.current_hp <= 0 => {
+                vec![if state.character.is_dying() {
+                    ChoiceView::new(
+                        "death_save".to_string(),
+                        "Make a death saving throw".to_string(),
+                    )
+                } else {
+                    ChoiceView::new("continue".to_string(), "Lie still".to_string())
+                }]
+            }
+            (NodeType::Combat, Some(e))
+                if e.in_progress && state.character.conditions.incapacitated_by().is_some() =>
+            {
+                let condition = state.character.conditions.incapacitated_by();
+                vec![ChoiceView::new(
+                    "continue".to_string(),
+                    format!(
+                        "You are {} and can't act",
+                        condition.map_or("", Condition::slug)
+                    ),
+                )]
+            }
+            (NodeType::Combat, Some(e)) if e.in_progress => {
+                let mut choices: Vec<ChoiceView> = e
+                    .monsters
+                    .iter()
+                    .filter(|m| m.is_alive())
+                    .map(|m| {
+                        ChoiceView::new(
+                            format!("attack:{}", m.id),
+                            if m.is_bloodied() {
+                                format!("Attack {}, bloodied", m.name)
+                            } else {
+                                format!("Attack {}", m.name)
+                            },
+                        )
+                    })
+                    .collect();
+                choices.extend(spell_choices(&state.character, Some(&e.monsters)));
+                choices.extend(item_choices(&state.character, &self.items));
+                choices.extend(authored.filter(|c| c.id != "attack"));
+                choices
+            }
+            (NodeType::Narrative, _) => authored
+                .chain(spell_choices(&state.character, None))
+                .chain(item_choices(&state.character, &self.items))
+                .collect(),
+            _ => authored.collect(),
         };
 
-        let char_sum = CharacterSummary {
-            name: state.character.name.clone(),
-            level: state.character.level,
-            current_hp: state.character.current_hp,
-            max_hp: state.character.max_hp,
-        };
+        let char_sum = CharacterSummary::new(&state.character);
 
-        let mut text = node.text.clone();
+        let mut text: Vec<String> = node.text.iter().filter_map(|p| p.render(state)).collect();
         if let Some(log) = &state.last_log {
             if !log.is_empty() {
                 text.push(String::new());
@@ -145,38 +601,333 @@ impl Campaign {
             }
         }
 
-        NodeView {
+        Ok(NodeView {
             title: node.title.clone(),
             text,
             choices,
             character_summary: char_sum,
             log: state.last_log.clone(),
-        }
+            combat: state
+                .encounter
+                .as_ref()
+                .filter(|e| e.in_progress)
+                .map(|e| combat_view(e, &state.character)),
+        })
     }
 
-    fn build_encounter(&self, node: &Node) -> Option<Encounter> {
-        let spec = node.encounter.as_ref()?;
+    fn build_encounter(&self, node: &Node) -> Result<Option<Encounter>, EngineError> {
+        let Some(spec) = node.encounter.as_ref() else {
+            return Ok(None);
+        };
+        let library = srd_library();
         let mut monsters = Vec::new();
         for m in &spec.monsters {
             for _ in 0..m.count {
-                monsters.push(make_monster_from_ref(&m.r#ref));
+                let template =
+                    library
+                        .get(&m.r#ref)
+                        .ok_or_else(|| EngineError::UnknownMonster {
+                            node_id: node.id.clone(),
+                            monster_ref: m.r#ref.clone(),
+                        })?;
+                let mut monster = template.spawn()?;
+                for c in &m.conditions {
+                    monster.conditions.add(c.clone());
+                }
+                monsters.push(monster);
             }
         }
-        Some(Encounter {
-            monsters,
-            in_progress: true,
+        Ok(Some(Encounter::new(monsters)))
+    }
+
+    /// Enter the start node. Called once when a new game begins.
+    pub fn start(&self, state: &mut GameState, dice: &mut DiceRoller) -> Result<(), EngineError> {
+        let mut log_lines = Vec::new();
+        self.goto(&self.start_node_id, state, dice, &mut log_lines)?;
+        state.last_log = if log_lines.is_empty() {
+            None
+        } else {
+            Some(log_lines.join("\n"))
+        };
+        offer_level_up(state);
+        Ok(())
+    }
+
+    /// Move to `next` and run whatever happens on arrival, following any
+    /// saving throw, knock-out or instant fight on to the nodes they lead to.
+    fn goto(
+        &self,
+        next: &str,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+        log_lines: &mut Vec<String>,
+    ) -> Result<(), EngineError> {
+        let mut next = next.to_string();
+        for _ in 0..MAX_CHAINED_ARRIVALS {
+            match self.arrive(&next, state, dice, log_lines)? {
+                Some(onward) => next = onward,
+                None => return Ok(()),
+            }
+        }
+        Err(EngineError::NodeLoop(next))
+    }
+
+    /// Enter node `id`. Returns the node the hero is sent on to without a
+    /// choice, if any.
+    fn arrive(
+        &self,
+        id: &str,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+        log_lines: &mut Vec<String>,
+    ) -> Result<Option<String>, EngineError> {
+        let node = self.find_node(id)?;
+        state.current_node_id = node.id.clone();
+        state.encounter = None;
+        state.short_rest = None;
+        state.vars.apply(&node.set, &node.increment);
+        if node.milestone_xp > 0 {
+            state.character.xp = state.character.xp.saturating_add(node.milestone_xp);
+            log_lines.push(format!(
+                "Milestone reached: you gain {} XP.",
+                node.milestone_xp
+            ));
+        }
+        apply_effects(&node.effects, state, &self.items, dice, log_lines);
+        if state.character.current_hp <= 0 {
+            return self.knocked_out(node, state, dice, log_lines);
+        }
+        if let Some(st) = &node.saving_throw {
+            let onward = self.resolve_saving_throw(node, st, state, dice, log_lines)?;
+            if onward.is_some() {
+                return Ok(onward);
+            }
+        }
+        if let NodeType::Combat = node.kind {
+            return self.start_combat(node, state, dice, log_lines);
+        }
+        Ok(None)
+    }
+
+    /// Roll a saving throw and apply its damage and conditions. Returns its
+    /// `success_next`/`failure_next`.
+    fn resolve_saving_throw(
+        &self,
+        node: &Node,
+        st: &SavingThrow,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+        log_lines: &mut Vec<String>,
+    ) -> Result<Option<String>, EngineError> {
+        let (ability, damage) = match st.resolve() {
+            Ok(r) => r,
+            Err(msg) => {
+                log_lines.push(msg);
+                return Ok(None);
+            }
+        };
+        if let Some(desc) = &st.description {
+            log_lines.push(desc.clone());
+        }
+        let hero = &mut state.character;
+        let save = hero.saving_throw(ability, st.dc, st.d20_test(hero), dice);
+        let success = save.success();
+        log_lines.push(save.to_string());
+
+        if let Some(damage) = &damage {
+            let roll = dice.eval(damage);
+            let mut dmg = roll.total.max(0);
+            let mut notes = Vec::new();
+            if success {
+                dmg /= 2;
+                notes.push("halved");
+            }
+            if st.damage_type.as_deref().is_some_and(|t| hero.resists(t)) {
+                dmg /= 2;
+                notes.push("resisted");
+            }
+            let kind = st
+                .damage_type
+                .as_deref()
+                .map(|t| format!(" {}", t.to_lowercase()))
+                .unwrap_or_default();
+            let mut line = if notes.is_empty() {
+                format!("Damage {} => you take {}{} damage.", roll, dmg, kind)
+            } else {
+                format!(
+                    "Damage {} => you take {}{} damage ({}).",
+                    roll,
+                    dmg,
+                    kind,
+                    notes.join(", ")
+                )
+            };
+            if let Some(note) = hero.take_damage(dmg, false) {
+                line.push(' ');
+                line.push_str(&note);
+            }
+            log_lines.push(line);
+        }
+        if !success {
+            apply_conditions(hero, &st.failure_conditions, log_lines);
+        }
+        let effects = if success {
+            &st.success_effects
+        } else {
+            &st.failure_effects
+        };
+        apply_effects(effects, state, &self.items, dice, log_lines);
+
+        if state.character.current_hp <= 0 {
+            return self.knocked_out(node, state, dice, log_lines);
+        }
+        Ok(if success {
+            st.success_next.clone()
+        } else {
+            st.failure_next.clone()
         })
     }
 
-    pub fn apply_choice(&self, choice_id: &str, state: &mut GameState, dice: &mut DiceRoller) {
-        let node = self.find_node(&state.current_node_id);
-        match node.kind {
-            NodeType::Narrative => self.apply_narrative_choice(node, choice_id, state, dice),
-            NodeType::Combat => self.apply_combat_choice(node, choice_id, state, dice),
-            NodeType::End => {
-                state.last_log = Some("The adventure is over.".to_string());
+    /// The hero dropped to 0 hit points outside a fight. Death leads to the
+    /// node's `on_defeat`, which is returned; otherwise they stabilize and come
+    /// round with 1 hit point some hours later.
+    fn knocked_out(
+        &self,
+        node: &Node,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+        log_lines: &mut Vec<String>,
+    ) -> Result<Option<String>, EngineError> {
+        if !state.character.is_dead() {
+            state.character.heal(1);
+            log_lines.push(format!(
+                "You stabilize and come round {} hours later with 1 hit point.",
+                dice.roll(1, 4)
+            ));
+            return Ok(None);
+        }
+        if node.on_defeat.is_some() {
+            log_lines.push("You have been defeated...".to_string());
+        }
+        Ok(node.on_defeat.clone())
+    }
+
+    /// Build the encounter, roll initiative and let any monsters that beat the
+    /// hero act before the first choice. Returns where the hero goes if the
+    /// fight is over already.
+    fn start_combat(
+        &self,
+        node: &Node,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+        log_lines: &mut Vec<String>,
+    ) -> Result<Option<String>, EngineError> {
+        let Some(mut encounter) = self.build_encounter(node)? else {
+            return Ok(None);
+        };
+        log_lines.push(encounter.roll_initiative(&state.character, dice));
+        encounter.run_monster_turns(&mut state.character, &self.house_rules, dice, log_lines);
+        state.encounter = Some(encounter);
+        self.finish_combat_if_over(node, state, log_lines)
+    }
+
+    /// End the fight if it's over, returning `on_victory` or `on_defeat`.
+    fn finish_combat_if_over(
+        &self,
+        node: &Node,
+        state: &mut GameState,
+        log_lines: &mut Vec<String>,
+    ) -> Result<Option<String>, EngineError> {
+        let Some(encounter) = &mut state.encounter else {
+            return Ok(None);
+        };
+        if !encounter.is_over(&state.character, &self.house_rules) {
+            return Ok(None);
+        }
+        encounter.in_progress = false;
+        state.character.conditions.clear_expiring();
+
+        if state.character.current_hp > 0 {
+            let xp: u32 = encounter
+                .monsters
+                .iter()
+                .filter(|m| !m.is_alive())
+                .map(|m| xp_for_cr(m.challenge_rating))
+                .sum();
+            state.character.xp = state.character.xp.saturating_add(xp);
+            if node.on_victory.is_some() {
+                log_lines.push(format!("You won the fight! You gain {} XP.", xp));
+            } else {
+                log_lines.push(format!(
+                    "You have won and gain {} XP, but the story has nowhere to go.",
+                    xp
+                ));
+            }
+            return Ok(node.on_victory.clone());
+        }
+        if node.on_defeat.is_some() {
+            if state.character.is_dead() {
+                log_lines.push("You have been defeated...".to_string());
+            } else {
+                // Only reachable under `DefeatRule::Captured`.
+                state.character.heal(1);
+                log_lines.push("You are knocked out and captured...".to_string());
+            }
+        }
+        Ok(node.on_defeat.clone())
+    }
+
+    pub fn apply_choice(
+        &self,
+        choice_id: &str,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+    ) -> Result<(), EngineError> {
+        let node = self.find_node(&state.current_node_id)?;
+        if state.character.is_dead() && node.on_defeat.is_none() {
+            state.last_log = Some("You are dead. The adventure is over.".to_string());
+            return Ok(());
+        }
+        if state.level_up.is_some() {
+            self.apply_level_up_choice(node, choice_id, state, dice)?;
+        } else if state.short_rest.is_some() {
+            self.apply_short_rest_choice(node, choice_id, state, dice)?;
+        } else {
+            match node.kind {
+                NodeType::Narrative => self.apply_narrative_choice(node, choice_id, state, dice)?,
+                NodeType::Combat => self.apply_combat_choice(node, choice_id, state, dice)?,
+                NodeType::End => state.last_log = Some("The adventure is over.".to_string()),
             }
         }
+        offer_level_up(state);
+        Ok(())
+    }
+
+    fn apply_level_up_choice(
+        &self,
+        node: &Node,
+        choice_id: &str,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+    ) -> Result<(), EngineError> {
+        let Some(mut level_up) = state.level_up.take() else {
+            return Ok(());
+        };
+        let Some(line) = level_up.apply(choice_id, &mut state.character, dice) else {
+            state.level_up = Some(level_up);
+            return Err(EngineError::UnknownChoice {
+                node_id: node.id.clone(),
+                choice_id: choice_id.to_string(),
+            });
+        };
+        let mut log_lines = vec![line];
+        if level_up.is_done() {
+            log_lines.push(level_up.finish(&mut state.character));
+        } else {
+            state.level_up = Some(level_up);
+        }
+        state.last_log = Some(log_lines.join("\n"));
+        Ok(())
     }
 
     fn apply_narrative_choice(
@@ -185,150 +936,978 @@ impl Campaign {
         choice_id: &str,
         state: &mut GameState,
         dice: &mut DiceRoller,
-    ) {
-        let choice = match node.choices.iter().find(|c| c.id == choice_id) {
-            Some(c) => c,
-            None => {
-                state.last_log = Some(format!("Unknown choice: {}", choice_id));
-                return;
+    ) -> Result<(), EngineError> {
+        if let Some(cast) = choice_id.strip_prefix("cast:") {
+            // Only what `view_node` offered: healing, and only while hurt.
+            if !spell_choices(&state.character, None)
+                .iter()
+                .any(|c| c.id == choice_id)
+            {
+                return Err(EngineError::UnknownChoice {
+                    node_id: node.id.clone(),
+                    choice_id: choice_id.to_string(),
+                });
             }
-        };
+            state.last_log = Some(match prepare_cast(&state.character, cast, &[]) {
+                Ok((spell, slot, target)) => cast_spell(
+                    &mut state.character,
+                    spell,
+                    slot,
+                    &mut [],
+                    target,
+                    self.house_rules.crits,
+                    dice,
+                )
+                .join("\n"),
+                Err(msg) => msg,
+            });
+            return Ok(());
+        }
+        if let Some(item) = choice_id.strip_prefix("use:") {
+            // Only what `view_node` offered: healing-only items while hurt.
+            if !item_choices(&state.character, &self.items)
+                .iter()
+                .any(|c| c.id == choice_id)
+            {
+                return Err(EngineError::UnknownChoice {
+                    node_id: node.id.clone(),
+                    choice_id: choice_id.to_string(),
+                });
+            }
+            let mut log_lines = Vec::new();
+            let hero = &mut state.character;
+            match use_item(
+                item,
+                hero,
+                &mut state.vars,
+                &self.items,
+                dice,
+                &mut log_lines,
+            ) {
+                Ok(()) if state.character.current_hp <= 0 => {
+                    if let Some(next) = self.knocked_out(node, state, dice, &mut log_lines)? {
+                        self.goto(&next, state, dice, &mut log_lines)?;
+                    }
+                }
+                Ok(()) => {}
+                Err(msg) => log_lines.push(msg),
+            }
+            state.last_log = Some(log_lines.join("\n"));
+            return Ok(());
+        }
+
+        let choice = node
+            .choices
+            .iter()
+            .find(|c| c.id == choice_id)
+            .ok_or_else(|| EngineError::UnknownChoice {
+                node_id: node.id.clone(),
+                choice_id: choice_id.to_string(),
+            })?;
+        if !choice.available(state) {
+            if !choice.show_locked {
+                return Err(EngineError::UnknownChoice {
+                    node_id: node.id.clone(),
+                    choice_id: choice_id.to_string(),
+                });
+            }
+            state.last_log = Some("That choice isn't open to you yet.".to_string());
+            return Ok(());
+        }
+        state.vars.apply(&choice.set, &choice.increment);
+
+        let mut log_lines = Vec::new();
+        apply_effects(&choice.effects, state, &self.items, dice, &mut log_lines);
+        if state.character.current_hp <= 0 {
+            if let Some(next) = self.knocked_out(node, state, dice, &mut log_lines)? {
+                self.goto(&next, state, dice, &mut log_lines)?;
+            }
+            state.last_log = Some(log_lines.join("\n"));
+            return Ok(());
+        }
+
+        if let Some(rest) = &choice.rest {
+            return self.start_rest(choice, rest, state, dice, log_lines);
+        }
 
         if let Some(sc) = &choice.skill_check {
-            let ability = match ability_from_str(&sc.ability) {
-                Some(a) => a,
-                None => {
-                    state.last_log =
-                        Some(format!("Unknown ability in skill check: {}", sc.ability));
-                    return;
+            let (ability, skill) = match sc.resolve() {
+                Ok(r) => r,
+                Err(msg) => {
+                    log_lines.push(msg);
+                    state.last_log = Some(log_lines.join("\n"));
+                    return Ok(());
                 }
             };
-            let roll = dice.d20();
-            let modif = state.character.abilities.modifier(ability);
-            let total = roll + modif;
-            let success = total >= sc.dc;
-
-            let mut log = format!(
-                "Skill check ({}, DC {}): rolled {} + {} = {} => {}",
-                sc.ability,
-                sc.dc,
-                roll,
-                modif,
-                total,
-                if success { "success" } else { "failure" }
-            );
+            let check_name = match skill {
+                Some(s) => format!("{} ({})", ability.name(), s.name),
+                None => ability.name().to_string(),
+            };
             if let Some(desc) = &sc.description {
-                log = format!(
-                    "{}
-{}",
-                    desc, log
-                );
+                log_lines.push(desc.clone());
+            }
+
+            let success = if let Some(condition) = sc.auto_fail(&state.character) {
+                log_lines.push(format!(
+                    "{} check (DC {}): you are {} => failure",
+                    check_name,
+                    sc.dc,
+                    condition.slug()
+                ));
+                false
+            } else {
+                let roll = state
+                    .character
+                    .roll_d20(dice, &sc.d20_test(&state.character));
+                let modif = state.character.check_modifier(ability, skill);
+                let total = roll.natural + modif;
+                let success = total >= sc.dc;
+                log_lines.push(format!(
+                    "{} check (DC {}): rolled {} + {} = {} => {}",
+                    check_name,
+                    sc.dc,
+                    roll,
+                    modif,
+                    total,
+                    if success { "success" } else { "failure" }
+                ));
+                success
+            };
+
+            if !success {
+                apply_conditions(&mut state.character, &sc.failure_conditions, &mut log_lines);
+            }
+            let effects = if success {
+                &sc.success_effects
+            } else {
+                &sc.failure_effects
+            };
+            apply_effects(effects, state, &self.items, dice, &mut log_lines);
+            if state.character.current_hp <= 0 {
+                if let Some(next) = self.knocked_out(node, state, dice, &mut log_lines)? {
+                    self.goto(&next, state, dice, &mut log_lines)?;
+                }
+                state.last_log = Some(log_lines.join("\n"));
+                return Ok(());
             }
-            state.last_log = Some(log);
 
             let next_id = if success {
-                sc.success_next.clone()
+                sc.success_next.as_ref()
             } else {
-                sc.failure_next.clone()
+                sc.failure_next.as_ref()
             };
 
             if let Some(next) = next_id {
-                state.current_node_id = next;
+                self.goto(next, state, dice, &mut log_lines)?;
+            }
+            state.last_log = Some(log_lines.join("\n"));
+            return Ok(());
+        }
+
+        if let Some(st) = &choice.saving_throw {
+            if let Some(next) = self.resolve_saving_throw(node, st, state, dice, &mut log_lines)? {
+                self.goto(&next, state, dice, &mut log_lines)?;
             }
-            return;
+            state.last_log = Some(log_lines.join("\n"));
+            return Ok(());
         }
 
         if let Some(next) = &choice.next {
-            state.current_node_id = next.clone();
-            state.last_log = None;
+            self.goto(next, state, dice, &mut log_lines)?;
+            state.last_log = if log_lines.is_empty() {
+                None
+            } else {
+                Some(log_lines.join("\n"))
+            };
         } else {
             state.last_log = Some("Nowhere to go from here.".to_string());
         }
+        Ok(())
     }
 
-    fn apply_combat_choice(
+    /// Roll for interruption, then take a long rest or begin a short one.
+    fn start_rest(
         &self,
-        node: &Node,
-        choice_id: &str,
+        choice: &Choice,
+        rest: &RestSpec,
         state: &mut GameState,
         dice: &mut DiceRoller,
-    ) {
-        if state.encounter.is_none() {
-            state.encounter = self.build_encounter(node);
+        mut log_lines: Vec<String>,
+    ) -> Result<(), EngineError> {
+        if rest.interruption_chance > 0 {
+            let roll = dice.roll(1, 100) as u32;
+            if roll <= rest.interruption_chance {
+                log_lines.push(format!(
+                    "Interruption roll: {} (interrupted on {} or less) => your rest is interrupted!",
+                    roll, rest.interruption_chance
+                ));
+                if let Some(next) = &rest.interrupted_next {
+                    self.goto(next, state, dice, &mut log_lines)?;
+                }
+                state.last_log = Some(log_lines.join("\n"));
+                return Ok(());
+            }
+            log_lines.push(format!(
+                "Interruption roll: {} (interrupted on {} or less) => undisturbed.",
+                roll, rest.interruption_chance
+            ));
         }
 
-        let encounter = match &mut state.encounter {
-            Some(e) => e,
-            None => {
-                state.last_log = Some("No encounter to resolve.".to_string());
-                return;
+        match rest.kind {
+            RestKind::Long => match state.character.long_rest() {
+                Ok(line) => {
+                    log_lines.push(line);
+                    if let Some(next) = &choice.next {
+                        self.goto(next, state, dice, &mut log_lines)?;
+                    }
+                }
+                Err(msg) => log_lines.push(msg),
+            },
+            RestKind::Short => {
+                log_lines.push("You settle in for a short rest.".to_string());
+                state.short_rest = Some(ShortRest {
+                    next: choice.next.clone(),
+                });
             }
-        };
+        }
+        state.last_log = Some(log_lines.join("\n"));
+        Ok(())
+    }
 
+    fn apply_short_rest_choice(
+        &self,
+        node: &Node,
+        choice_id: &str,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+    ) -> Result<(), EngineError> {
         let mut log_lines = Vec::new();
-
         match choice_id {
-            "attack" => {
-                if let Some(monster) = encounter.first_alive_monster_mut() {
-                    log_lines.push(hero_attack(&state.character, monster, dice));
-                } else {
-                    log_lines.push("There is nothing left to attack.".to_string());
+            "spend_hit_die" => match state.character.spend_hit_die(dice) {
+                Ok(line) | Err(line) => log_lines.push(line),
+            },
+            "finish_rest" => {
+                log_lines.push("You finish your short rest.".to_string());
+                if let Some(next) = state.short_rest.take().and_then(|r| r.next) {
+                    self.goto(&next, state, dice, &mut log_lines)?;
                 }
             }
-            "continue" => {
-                log_lines.push("You press on...".to_string());
-            }
             _ => {
-                log_lines.push(format!("Unknown combat choice: {}", choice_id));
+                return Err(EngineError::UnknownChoice {
+                    node_id: node.id.clone(),
+                    choice_id: choice_id.to_string(),
+                })
             }
         }
+        state.last_log = Some(log_lines.join("\n"));
+        Ok(())
+    }
 
-        if state.character.current_hp > 0 {
-            for monster in encounter.monsters.iter() {
-                if monster.current_hp > 0 && state.character.current_hp > 0 {
-                    log_lines.push(monster_attack(monster, &mut state.character, dice));
+    fn apply_combat_choice(
+        &self,
+        node: &Node,
+        choice_id: &str,
+        state: &mut GameState,
+        dice: &mut DiceRoller,
+    ) -> Result<(), EngineError> {
+        let target = choice_id.strip_prefix("attack:");
+        let cast = choice_id.strip_prefix("cast:");
+        let item = choice_id.strip_prefix("use:");
+        let built_in = COMBAT_CHOICE_IDS.contains(&choice_id);
+        // Authored choices, such as fleeing, show alongside the attacks.
+        let authored = node.choices.iter().find(|c| c.id == choice_id && !built_in);
+        if target.is_none() && cast.is_none() && item.is_none() && authored.is_none() && !built_in {
+            return Err(EngineError::UnknownChoice {
+                node_id: node.id.clone(),
+                choice_id: choice_id.to_string(),
+            });
+        }
+        if let Some(choice) = authored.filter(|c| !c.available(state)) {
+            if !choice.show_locked {
+                return Err(EngineError::UnknownChoice {
+                    node_id: node.id.clone(),
+                    choice_id: choice_id.to_string(),
+                });
+            }
+            state.last_log = Some("That choice isn't open to you yet.".to_string());
+            return Ok(());
+        }
+
+        let mut log_lines = Vec::new();
+        // Where an authored choice takes the hero out of the fight.
+        let mut leave_to = None;
+
+        // Saves made before initiative existed may hold an encounter with no
+        // turn order, or none at all.
+        match &mut state.encounter {
+            None => {
+                if let Some(next) = self.start_combat(node, state, dice, &mut log_lines)? {
+                    self.goto(&next, state, dice, &mut log_lines)?;
+                    state.last_log = Some(log_lines.join("\n"));
+                    return Ok(());
                 }
             }
+            Some(e) if e.turn_order.is_empty() => {
+                e.assign_ids();
+                log_lines.push(e.roll_initiative(&state.character, dice));
+                e.run_monster_turns(
+                    &mut state.character,
+                    &self.house_rules,
+                    dice,
+                    &mut log_lines,
+                );
+            }
+            Some(_) => {}
         }
 
-        if encounter.is_over(&state.character) {
-            encounter.in_progress = false;
-            if state.character.current_hp > 0 {
-                if let Some(next) = &node.on_victory {
-                    log_lines.push("You won the fight!".to_string());
-                    state.current_node_id = next.clone();
-                } else {
-                    log_lines.push("You have won, but the story has nowhere to go.".to_string());
+        let encounter = match &mut state.encounter {
+            Some(e) if e.in_progress => e,
+            _ => {
+                state.last_log = Some("No encounter to resolve.".to_string());
+                return Ok(());
+            }
+        };
+
+        if encounter.current_turn() == Some(Combatant::Hero) && state.character.current_hp <= 0 {
+            // Down at 0 HP the hero's turn is a death save, whatever was chosen.
+            log_lines.push(if state.character.is_dying() {
+                state.character.death_save(dice)
+            } else {
+                "You lie unconscious.".to_string()
+            });
+            encounter.end_turn();
+        } else if encounter.current_turn() == Some(Combatant::Hero)
+            && state.character.conditions.incapacitated_by().is_some()
+        {
+            let condition = state.character.conditions.incapacitated_by();
+            log_lines.push(format!(
+                "You are {} and can't act.",
+                condition.map_or("", Condition::slug)
+            ));
+            log_lines.extend(state.character.end_turn_conditions(dice));
+            encounter.end_turn();
+        } else if let (Some(Combatant::Hero), Some(choice)) = (encounter.current_turn(), authored) {
+            // Taking the choice is the hero's action. It leaves the fight
+            // through `next` if the hero is still standing afterwards.
+            state.vars.apply(&choice.set, &choice.increment);
+            for effect in &choice.effects {
+                effect.apply(
+                    &mut state.character,
+                    &mut state.vars,
+                    &self.items,
+                    dice,
+                    &mut log_lines,
+                );
+            }
+            if state.character.current_hp > 0 && choice.next.is_some() {
+                leave_to = choice.next.as_ref();
+            } else {
+                log_lines.extend(state.character.end_turn_conditions(dice));
+                encounter.end_turn();
+            }
+        } else if let (Some(Combatant::Hero), Some(cast)) = (encounter.current_turn(), cast) {
+            // Like a bad target, a spell that can't be cast doesn't cost the turn.
+            let (spell, slot, target) =
+                match prepare_cast(&state.character, cast, &encounter.monsters) {
+                    Ok(c) => c,
+                    Err(msg) => {
+                        state.last_log = Some(msg);
+                        return Ok(());
+                    }
+                };
+            log_lines.extend(state.character.stand_up());
+            log_lines.extend(cast_spell(
+                &mut state.character,
+                spell,
+                slot,
+                &mut encounter.monsters,
+                target,
+                self.house_rules.crits,
+                dice,
+            ));
+            log_lines.extend(state.character.end_turn_conditions(dice));
+            encounter.end_turn();
+        } else if let (Some(Combatant::Hero), Some(item)) = (encounter.current_turn(), item) {
+            // Nothing happens, and the turn isn't spent, unless the item can be used.
+            let mut used = Vec::new();
+            let hero = &mut state.character;
+            if let Err(msg) = use_item(item, hero, &mut state.vars, &self.items, dice, &mut used) {
+                state.last_log = Some(msg);
+                return Ok(());
+            }
+            log_lines.extend(state.character.stand_up());
+            log_lines.append(&mut used);
+            log_lines.extend(state.character.end_turn_conditions(dice));
+            encounter.end_turn();
+        } else if encounter.current_turn() == Some(Combatant::Hero) {
+            let monster = match target {
+                Some(id) => match encounter.monster_by_id_mut(id) {
+                    Some(m) if m.is_alive() => Some(m),
+                    // A bad target doesn't cost the hero their turn.
+                    Some(m) => {
+                        state.last_log = Some(format!("{} is already down.", m.name));
+                        return Ok(());
+                    }
+                    None => {
+                        state.last_log = Some(format!("There is no '{}' to attack.", id));
+                        return Ok(());
+                    }
+                },
+                None if choice_id == "attack" => encounter.first_alive_monster_mut(),
+                None => {
+                    log_lines.push("You press on...".to_string());
+                    None
                 }
-            } else if let Some(next) = &node.on_defeat {
-                log_lines.push("You have been defeated...".to_string());
-                state.current_node_id = next.clone();
+            };
+            log_lines.extend(state.character.stand_up());
+            if let Some(monster) = monster {
+                log_lines.push(hero_attack(
+                    &state.character,
+                    monster,
+                    self.house_rules.crits,
+                    dice,
+                ));
+            } else if choice_id != "continue" {
+                log_lines.push("There is nothing left to attack.".to_string());
             }
+            log_lines.extend(state.character.end_turn_conditions(dice));
+            encounter.end_turn();
+        }
+
+        if let Some(next) = leave_to {
+            self.goto(next, state, dice, &mut log_lines)?;
+            state.last_log = Some(log_lines.join("\n"));
+            return Ok(());
+        }
+
+        encounter.run_monster_turns(
+            &mut state.character,
+            &self.house_rules,
+            dice,
+            &mut log_lines,
+        );
+        if let Some(next) = self.finish_combat_if_over(node, state, &mut log_lines)? {
+            self.goto(&next, state, dice, &mut log_lines)?;
         }
 
         state.last_log = Some(log_lines.join("\n"));
+        Ok(())
+    }
+}
+
+/// A `use:<item>` choice for each usable item the hero carries. Items that
+/// only heal are offered only when hurt.
+fn item_choices(hero: &Character, items: &[ItemDef]) -> Vec<ChoiceView> {
+    inventory(hero, items)
+        .into_iter()
+        .filter(|e| e.usable)
+        .filter_map(|e| item_info(&e.item, items))
+        .filter(|i| hero.current_hp < hero.max_hp || !i.heals_only())
+        .map(|i| {
+            let count = match hero.item_count(&i.id) {
+                1 => String::new(),
+                n => format!(" ({} left)", n),
+            };
+            ChoiceView::new(format!("use:{}", i.id), format!("Use {}{}", i.name, count))
+        })
+        .collect()
+}
+
+/// `cast:<spell>` and `cast:<spell>:<monster id>` choices for the spells the
+/// hero can cast right now. Outside a fight (`monsters` is `None`) only healing
+/// is offered, and only when hurt.
+fn spell_choices(hero: &Character, monsters: Option<&[Monster]>) -> Vec<ChoiceView> {
+    let mut choices = Vec::new();
+    for spell in hero.known_spells() {
+        let (Some(effect), Ok(slot)) = (&spell.effect, hero.slot_for(spell)) else {
+            continue;
+        };
+        let id = format!("cast:{}", slugify(&spell.name));
+        let slot = match slot {
+            0 => String::new(),
+            s => format!(" ({} slot)", slot_name(s)),
+        };
+        match (effect, monsters) {
+            (SpellEffect::Healing { .. }, _) if hero.current_hp < hero.max_hp => {
+                choices.push(ChoiceView::new(id, format!("Cast {}{}", spell.name, slot)));
+            }
+            (SpellEffect::Healing { .. }, _) | (_, None) => {}
+            (effect, Some(monsters)) if effect.needs_target() => {
+                choices.extend(monsters.iter().filter(|m| m.is_alive()).map(|m| {
+                    ChoiceView::new(
+                        format!("{}:{}", id, m.id),
+                        format!("Cast {} at {}{}", spell.name, m.name, slot),
+                    )
+                }));
+            }
+            (_, Some(_)) => {
+                choices.push(ChoiceView::new(id, format!("Cast {}{}", spell.name, slot)))
+            }
+        }
+    }
+    choices
+}
+
+/// Check a `cast:<spell>[:<monster id>]` choice before anything is spent.
+/// Returns the spell, the slot level to use (0 for a cantrip) and the target.
+fn prepare_cast(
+    hero: &Character,
+    cast: &str,
+    monsters: &[Monster],
+) -> Result<(&'static Spell, u8, Option<usize>), String> {
+    let (name, target) = match cast.split_once(':') {
+        Some((name, target)) => (name, Some(target)),
+        None => (cast, None),
+    };
+    let spell = hero
+        .known_spells()
+        .into_iter()
+        .find(|s| slugify(&s.name) == slugify(name))
+        .ok_or_else(|| format!("You don't know the spell '{}'.", name))?;
+    let Some(effect) = &spell.effect else {
+        return Err(format!("{} can't be cast here.", spell.name));
+    };
+    let slot = hero.slot_for(spell)?;
+    let target = match target {
+        _ if !effect.needs_target() => None,
+        None => return Err(format!("{} needs a target.", spell.name)),
+        Some(id) => {
+            let i = monsters
+                .iter()
+                .position(|m| m.id == id)
+                .ok_or_else(|| format!("There is no '{}' to target.", id))?;
+            if !monsters[i].is_alive() {
+                return Err(format!("{} is already down.", monsters[i].name));
+            }
+            Some(i)
+        }
+    };
+    Ok((spell, slot, target))
+}
+
+/// Begin a level-up once the hero has the XP for it and nothing else is going on.
+fn offer_level_up(state: &mut GameState) {
+    let busy = state.level_up.is_some()
+        || state.short_rest.is_some()
+        || state.encounter.as_ref().is_some_and(|e| e.in_progress)
+        || state.character.current_hp <= 0;
+    if busy {
+        return;
+    }
+    if let Some(level_up) = LevelUp::start(&state.character) {
+        let line = format!(
+            "You have enough experience to reach level {}!",
+            level_up.level
+        );
+        state.last_log = Some(match state.last_log.take() {
+            Some(log) if !log.is_empty() => format!("{}\n{}", log, line),
+            _ => line,
+        });
+        state.level_up = Some(level_up);
+    }
+}
+
+fn combat_view(encounter: &Encounter, hero: &Character) -> CombatView {
+    let turn_order = encounter
+        .turn_order
+        .iter()
+        .enumerate()
+        .map(|(i, slot)| {
+            let (current_hp, max_hp, conditions) = match slot.combatant {
+                Combatant::Hero => (hero.current_hp, hero.max_hp, hero.conditions.names()),
+                Combatant::Monster(m) => {
+                    let monster = &encounter.monsters[m];
+                    (
+                        monster.current_hp,
+                        monster.max_hp,
+                        monster.conditions.names(),
+                    )
+                }
+            };
+            TurnView {
+                name: encounter.combatant_name(slot.combatant),
+                initiative: slot.initiative,
+                is_hero: slot.combatant == Combatant::Hero,
+                current_hp,
+                max_hp,
+                active: i == encounter.turn,
+                conditions,
+            }
+        })
+        .collect();
+    CombatView {
+        round: encounter.round,
+        turn_order,
     }
 }
 
-fn make_monster_from_ref(r: &str) -> Monster {
-    match r {
-        "srd_goblin" => Monster {
-            name: "Goblin".to_string(),
-            ac: 15,
-            max_hp: 7,
-            current_hp: 7,
-            attack_bonus: 4,
-            damage_dice_count: 1,
-            damage_dice_sides: 6,
-        },
-        _ => Monster {
-            name: r.to_string(),
-            ac: 12,
-            max_hp: 8,
-            current_hp: 8,
-            attack_bonus: 3,
-            damage_dice_count: 1,
-            damage_dice_sides: 6,
-        },
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::RollMode;
+    use crate::engine::Engine;
+
+    const HERO: &str = include_str!("../../content/campaigns/pregen_fighter.json");
+
+    fn campaign(start: &str) -> Campaign {
+        Campaign::from_json(&format!(
+            r#"{{
+                "id": "loop", "title": "Loop", "startNodeId": "{}",
+                "nodes": [
+                    {{"id": "gate", "type": "narrative", "text": ["A gate."],
+                      "choices": [{{"id": "enter", "label": "Enter", "next": "trap"}}]}},
+                    {{"id": "trap", "type": "narrative", "text": ["A trap."],
+                      "saving_throw": {{"ability": "dex", "dc": 10,
+                                        "success_next": "trap", "failure_next": "trap"}}}},
+                    {{"id": "end", "type": "end", "text": ["The end."]}}
+                ]
+            }}"#,
+            start
+        ))
+        .unwrap()
+    }
+
+    fn cleric() -> Character {
+        let mut hero = Character::from_json(HERO).unwrap();
+        hero.class = Some("Cleric".to_string());
+        hero.cantrips = vec!["Sacred Flame".to_string()];
+        hero.spells = vec!["Cure Wounds".to_string()];
+        hero
+    }
+
+    #[test]
+    fn only_offered_spells_can_be_cast() {
+        let mut engine = Engine::new(campaign("gate"), cleric(), 1).unwrap();
+        let unknown = |id: &str| {
+            Err(EngineError::UnknownChoice {
+                node_id: "gate".to_string(),
+                choice_id: id.to_string(),
+            })
+        };
+        // Not offered: attack cantrips outside a fight, unknown spells, and
+        // healing at full hit points.
+        assert_eq!(
+            engine.choose("cast:sacred_flame"),
+            unknown("cast:sacred_flame")
+        );
+        assert_eq!(engine.choose("cast:fireball"), unknown("cast:fireball"));
+        assert_eq!(
+            engine.choose("cast:cure_wounds"),
+            unknown("cast:cure_wounds")
+        );
+
+        engine.state.character.current_hp = 3;
+        let offered = engine.current_view().unwrap().choices;
+        assert!(offered.iter().any(|c| c.id == "cast:cure_wounds"));
+        engine.choose("cast:cure_wounds").unwrap();
+        assert!(engine.state.character.current_hp > 3);
+    }
+
+    #[test]
+    fn a_dead_hero_with_nowhere_to_go_gets_no_choices() {
+        let mut engine =
+            Engine::new(campaign("gate"), Character::from_json(HERO).unwrap(), 1).unwrap();
+        engine.state.character.current_hp = 0;
+        engine.state.character.death_saves.failures = 3;
+        assert!(engine.current_view().unwrap().choices.is_empty());
+        engine.choose("enter").unwrap();
+        assert_eq!(engine.state.current_node_id, "gate");
+        assert_eq!(
+            engine.state.last_log.as_deref(),
+            Some("You are dead. The adventure is over.")
+        );
+    }
+
+    #[test]
+    fn only_offered_items_can_be_used() {
+        let mut hero = Character::from_json(HERO).unwrap();
+        hero.give_item("potion_of_healing", 1);
+        hero.give_item("torch", 1);
+        let mut engine = Engine::new(campaign("gate"), hero, 1).unwrap();
+        let unknown = |id: &str| {
+            Err(EngineError::UnknownChoice {
+                node_id: "gate".to_string(),
+                choice_id: id.to_string(),
+            })
+        };
+        // Not offered: items the hero lacks or that do nothing, and healing
+        // at full hit points.
+        assert_eq!(engine.choose("use:rope"), unknown("use:rope"));
+        assert_eq!(engine.choose("use:torch"), unknown("use:torch"));
+        assert_eq!(
+            engine.choose("use:potion_of_healing"),
+            unknown("use:potion_of_healing")
+        );
+        assert_eq!(engine.state.character.item_count("potion_of_healing"), 1);
+
+        engine.state.character.current_hp = 3;
+        engine.choose("use:potion_of_healing").unwrap();
+        assert!(engine.state.character.current_hp > 3);
+        assert_eq!(engine.state.character.item_count("potion_of_healing"), 0);
+    }
+
+    fn goblin_fight(count: u32) -> Campaign {
+        Campaign::from_json(&format!(
+            r#"{{
+                "id": "fight", "title": "Fight", "startNodeId": "fight",
+                "nodes": [
+                    {{"id": "fight", "type": "combat", "text": ["Goblins!"],
+                      "encounter": {{"monsters": [{{"ref": "srd_goblin", "count": {}}}]}},
+                      "on_victory": "safe", "on_defeat": "safe"}},
+                    {{"id": "safe", "type": "end", "text": ["Safe."]}}
+                ]
+            }}"#,
+            count
+        ))
+        .unwrap()
+    }
+
+    fn tough_hero() -> Character {
+        let mut hero = Character::from_json(HERO).unwrap();
+        hero.max_hp = 500;
+        hero.current_hp = 500;
+        hero
+    }
+
+    #[test]
+    fn the_hero_picks_which_monster_to_attack() {
+        let mut engine = Engine::new(goblin_fight(2), tough_hero(), 3).unwrap();
+        let attacks = |engine: &Engine| -> Vec<(String, String)> {
+            engine
+                .current_view()
+                .unwrap()
+                .choices
+                .into_iter()
+                .filter(|c| c.id.starts_with("attack:"))
+                .map(|c| (c.id, c.label))
+                .collect()
+        };
+        assert_eq!(
+            attacks(&engine),
+            [
+                (
+                    "attack:goblin_1".to_string(),
+                    "Attack Goblin #1".to_string()
+                ),
+                (
+                    "attack:goblin_2".to_string(),
+                    "Attack Goblin #2".to_string()
+                ),
+            ]
+        );
+
+        // A bad target doesn't cost the hero their turn.
+        let round = engine.state.encounter.as_ref().unwrap().round;
+        engine.choose("attack:goblin_9").unwrap();
+        assert_eq!(
+            engine.state.last_log.as_deref(),
+            Some("There is no 'goblin_9' to attack.")
+        );
+        engine.state.encounter.as_mut().unwrap().monsters[0].current_hp = 0;
+        engine.choose("attack:goblin_1").unwrap();
+        assert_eq!(
+            engine.state.last_log.as_deref(),
+            Some("Goblin #1 is already down.")
+        );
+        assert_eq!(engine.state.encounter.as_ref().unwrap().round, round);
+        assert_eq!(attacks(&engine).len(), 1);
+
+        engine.choose("attack:goblin_2").unwrap();
+        let log = engine.state.last_log.clone().unwrap();
+        assert!(log.contains("Goblin #2"), "{}", log);
+        assert!(!log.contains("Goblin #1"), "{}", log);
+    }
+
+    #[test]
+    fn authored_choices_in_a_fight() {
+        let camp = Campaign::from_json(
+            r#"{
+                "id": "fight", "title": "Fight", "startNodeId": "fight",
+                "nodes": [
+                    {"id": "fight", "type": "combat", "text": ["Goblins!"],
+                     "encounter": {"monsters": [{"ref": "srd_goblin", "count": 2}]},
+                     "choices": [
+                        {"id": "taunt", "label": "Taunt them",
+                         "effects": [{"type": "flag", "flag": "taunted"}]},
+                        {"id": "bribe", "label": "Bribe them",
+                         "requires": "character.gold >= 100"},
+                        {"id": "flee", "label": "Flee", "next": "safe",
+                         "effects": [{"type": "gold", "amount": -5}]}
+                     ],
+                     "on_victory": "safe", "on_defeat": "safe"},
+                    {"id": "safe", "type": "end", "text": ["Safe."]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut hero = Character::from_json(HERO).unwrap();
+        hero.max_hp = 500;
+        hero.current_hp = 500;
+        hero.gold = 20;
+        let mut engine = Engine::new(camp, hero, 3).unwrap();
+        let offered: Vec<String> = engine
+            .current_view()
+            .unwrap()
+            .choices
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert!(offered.iter().any(|c| c == "flee"));
+        assert!(!offered.iter().any(|c| c == "bribe"));
+        assert_eq!(
+            engine.choose("bribe"),
+            Err(EngineError::UnknownChoice {
+                node_id: "fight".to_string(),
+                choice_id: "bribe".to_string(),
+            })
+        );
+
+        engine.choose("taunt").unwrap();
+        assert!(engine.state.vars.get("taunted").is_some());
+        assert_eq!(engine.state.current_node_id, "fight");
+        assert!(engine.state.encounter.is_some());
+
+        engine.choose("flee").unwrap();
+        assert_eq!(engine.state.current_node_id, "safe");
+        assert!(engine.state.encounter.is_none());
+        assert_eq!(engine.state.character.gold, 15);
+    }
+
+    #[test]
+    fn self_referencing_saving_throw_stops_with_an_error() {
+        let hero = Character::from_json(HERO).unwrap();
+        let err = Engine::new(campaign("trap"), hero, 1).err().unwrap();
+        assert_eq!(err, EngineError::NodeLoop("trap".to_string()));
+        assert_eq!(err.code(), 19);
+    }
+
+    #[test]
+    fn choice_into_a_saving_throw_loop_stops_with_an_error() {
+        let hero = Character::from_json(HERO).unwrap();
+        let mut engine = Engine::new(campaign("gate"), hero, 1).unwrap();
+        assert_eq!(
+            engine.choose("enter"),
+            Err(EngineError::NodeLoop("trap".to_string()))
+        );
+    }
+
+    #[test]
+    fn skill_check_flags_and_conditions_set_the_roll_mode() {
+        let check = |json: &str| serde_json::from_str::<SkillCheck>(json).unwrap();
+        let mut hero = Character::from_json(HERO).unwrap();
+        let helped = check(r#"{"skill": "stealth", "dc": 12, "advantage": true}"#);
+        assert_eq!(helped.d20_test(&hero).mode(), RollMode::Advantage);
+        let plain = check(r#"{"skill": "stealth", "dc": 12}"#);
+        assert_eq!(plain.d20_test(&hero).mode(), RollMode::Normal);
+        let both =
+            check(r#"{"skill": "stealth", "dc": 12, "advantage": true, "disadvantage": true}"#);
+        assert_eq!(both.d20_test(&hero).mode(), RollMode::Normal);
+
+        // A poisoned hero loses the advantage.
+        hero.conditions.add(ActiveCondition {
+            condition: Condition::Poisoned,
+            rounds: None,
+            save: None,
+        });
+        let test = helped.d20_test(&hero);
+        assert_eq!(test.mode(), RollMode::Normal);
+        assert_eq!(test.disadvantage, ["poisoned"]);
+        assert_eq!(plain.d20_test(&hero).mode(), RollMode::Disadvantage);
+    }
+
+    /// A fire trap: 10 damage on a failed DEX save against `dc`, half on a success.
+    fn fire_trap(dc: i32) -> Campaign {
+        Campaign::from_json(&format!(
+            r#"{{
+                "id": "trap", "title": "Trap", "startNodeId": "gate",
+                "nodes": [
+                    {{"id": "gate", "type": "narrative", "text": ["A gate."],
+                      "choices": [{{"id": "enter", "label": "Enter", "next": "trap"}}]}},
+                    {{"id": "trap", "type": "narrative", "text": ["Flames!"],
+                      "saving_throw": {{"ability": "dex", "dc": {}, "damage": "10",
+                                        "damage_type": "Fire",
+                                        "success_next": "dodged", "failure_next": "burned"}}}},
+                    {{"id": "dodged", "type": "end", "text": ["Singed."]}},
+                    {{"id": "burned", "type": "end", "text": ["Burned."]}}
+                ]
+            }}"#,
+            dc
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn saving_throws_halve_damage_and_branch() {
+        for (dc, hp, node) in [(1, 7, "dodged"), (30, 2, "burned")] {
+            let hero = Character::from_json(HERO).unwrap();
+            let mut engine = Engine::new(fire_trap(dc), hero, 1).unwrap();
+            engine.choose("enter").unwrap();
+            assert_eq!(engine.state.character.current_hp, hp);
+            assert_eq!(engine.state.current_node_id, node);
+            let log = engine.current_view().unwrap().log;
+            assert!(
+                log.iter()
+                    .any(|l| l.starts_with(&format!("Dexterity saving throw (DC {})", dc))),
+                "{:?}",
+                log
+            );
+        }
+    }
+
+    #[test]
+    fn choices_set_variables_that_unlock_later_choices() {
+        let camp = Campaign::from_json(
+            r#"{
+                "id": "scout", "title": "Scout", "startNodeId": "scout",
+                "nodes": [
+                    {"id": "scout", "type": "narrative", "text": ["A goblin scout begs."],
+                     "choices": [
+                        {"id": "spare", "label": "Spare him", "next": "camp",
+                         "set": {"spared_goblin": true}, "increment": {"mercy": 1}},
+                        {"id": "drive_off", "label": "Drive him off", "next": "camp"}
+                     ]},
+                    {"id": "camp", "type": "narrative", "text": ["The goblin camp."],
+                     "choices": [
+                        {"id": "parley", "label": "Ask for the scout", "next": "end",
+                         "requires": "flag.spared_goblin"},
+                        {"id": "sneak", "label": "Sneak in", "next": "end",
+                         "requires": "mercy >= 2", "show_locked": true},
+                        {"id": "charge", "label": "Charge", "next": "end"}
+                     ]},
+                    {"id": "end", "type": "end", "text": ["The end."]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let choices = |engine: &Engine| {
+            engine
+                .current_view()
+                .unwrap()
+                .choices
+                .into_iter()
+                .map(|c| (c.id, c.disabled))
+                .collect::<Vec<_>>()
+        };
+        let sneak = ("sneak".to_string(), true);
+        let charge = ("charge".to_string(), false);
+
+        let hero = Character::from_json(HERO).unwrap();
+        let mut engine = Engine::new(camp.clone(), hero.clone(), 1).unwrap();
+        engine.choose("drive_off").unwrap();
+        assert_eq!(choices(&engine), [sneak.clone(), charge.clone()]);
+        engine.choose("sneak").unwrap();
+        assert_eq!(engine.state.current_node_id, "camp");
+        assert_eq!(
+            engine.state.last_log.as_deref(),
+            Some("That choice isn't open to you yet.")
+        );
+
+        let mut engine = Engine::new(camp, hero, 1).unwrap();
+        engine.choose("spare").unwrap();
+        assert_eq!(engine.state.vars.get("mercy"), Some(&Value::Int(1)));
+        assert_eq!(
+            choices(&engine),
+            [("parley".to_string(), false), sneak, charge]
+        );
     }
 }
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::equipment::{srd_equipment, ArmorCategory, Weapon};
+use crate::monsters::slugify;
+use crate::rules::{ability_from_str, Ability, ItemStack};
+
+/// SRD classes, subclasses and starting equipment, bundled into the library
+/// like the monster data.
+const SRD_CLASSES_JSON: &str = include_str!("../../data/json/classes.json");
+const SRD_SUBCLASSES_JSON: &str = include_str!("../../data/json/subclasses.json");
+const SRD_STARTING_EQUIPMENT_JSON: &str = include_str!("../../data/json/startingEquipment.json");
+
+lazy_static! {
+    static ref SRD_CLASSES: ClassLibrary = ClassLibrary::from_json(
+        SRD_CLASSES_JSON,
+        SRD_SUBCLASSES_JSON,
+        SRD_STARTING_EQUIPMENT_JSON
+    )
+    .expect("Bundled classes.json, subclasses.json or startingEquipment.json is invalid");
+}
+
+/// The class list built from the bundled SRD data.
+pub fn srd_classes() -> &'static ClassLibrary {
+    &SRD_CLASSES
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct NamedRef {
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawChoice {
+    choose: u32,
+    from: Vec<NamedRef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawClass {
+    name: String,
+    hit_die: u32,
+    #[serde(default)]
+    proficiency_choices: Vec<RawChoice>,
+    #[serde(default)]
+    proficiencies: Vec<NamedRef>,
+    #[serde(default)]
+    saving_throws: Vec<NamedRef>,
+    #[serde(default)]
+    subclasses: Vec<NamedRef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawSubclass {
+    name: String,
+    class: NamedRef,
+    #[serde(default)]
+    subclass_flavor: String,
+    #[serde(default)]
+    features: Vec<NamedRef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawItemCount {
+    item: NamedRef,
+    quantity: u32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawEquipmentOption {
+    choose: u32,
+    from: Vec<RawItemCount>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawStartingEquipment {
+    class: NamedRef,
+    #[serde(default)]
+    starting_equipment: Vec<RawItemCount>,
+    #[serde(default)]
+    choice_1: Vec<RawEquipmentOption>,
+    #[serde(default)]
+    choice_2: Vec<RawEquipmentOption>,
+    #[serde(default)]
+    choice_3: Vec<RawEquipmentOption>,
+    #[serde(default)]
+    choice_4: Vec<RawEquipmentOption>,
+    #[serde(default)]
+    choice_5: Vec<RawEquipmentOption>,
+}
+
+/// Pick `choose` skills from `from` (skill names as in skills.json).
+#[derive(Clone, Debug)]
+pub struct SkillChoice {
+    pub choose: u32,
+    pub from: Vec<String>,
+}
+
+/// One way to fill a starting equipment choice.
+#[derive(Clone, Debug)]
+pub struct EquipmentOption {
+    /// How many of `items` to pick from a pick list.
+    pub choose: u32,
+    pub items: Vec<ItemStack>,
+}
+
+impl EquipmentOption {
+    /// Options offering more than two items to choose from are a list to
+    /// pick from; the rest are taken whole. startingEquipment.json isn't
+    /// consistent about `choose` for pairs ("choose 1" of a light crossbow and
+    /// 20 bolts).
+    pub fn is_pick_list(&self) -> bool {
+        self.items.len() > 2 && (self.choose as usize) < self.items.len()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CharacterClass {
+    pub name: String,
+    pub hit_die: u32,
+    pub saving_throws: Vec<Ability>,
+    pub armor: Vec<ArmorCategory>,
+    pub simple_weapons: bool,
+    pub martial_weapons: bool,
+    /// Individual weapons by equipment slug, e.g. "rapier", "crossbow_hand".
+    pub weapons: Vec<String>,
+    /// Tools and anything else granted outright.
+    pub other_proficiencies: Vec<String>,
+    pub skill_choices: Option<SkillChoice>,
+    pub subclasses: Vec<String>,
+    /// Items every new character of the class gets.
+    pub starting_items: Vec<ItemStack>,
+    /// Starting equipment choices, each a list of options to pick one from.
+    pub starting_choices: Vec<Vec<EquipmentOption>>,
+}
+
+impl CharacterClass {
+    pub fn has_saving_throw(&self, ability: Ability) -> bool {
+        self.saving_throws.contains(&ability)
+    }
+
+    pub fn has_armor(&self, category: ArmorCategory) -> bool {
+        self.armor.contains(&category)
+    }
+
+    pub fn has_weapon(&self, weapon: &Weapon) -> bool {
+        match weapon.category.as_str() {
+            "Simple" if self.simple_weapons => true,
+            "Martial" if self.martial_weapons => true,
+            _ => self.weapons.contains(&slugify(&weapon.name)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Subclass {
+    pub name: String,
+    pub class: String,
+    /// What the class calls its subclasses: "Martial Archetype".
+    pub flavor: String,
+    pub features: Vec<String>,
+}
+
+/// Classes and subclasses keyed by slug: "fighter", "champion".
+#[derive(Clone, Debug, Default)]
+pub struct ClassLibrary {
+    classes: HashMap<String, CharacterClass>,
+    subclasses: HashMap<String, Subclass>,
+}
+
+impl ClassLibrary {
+    pub fn from_json(
+        classes: &str,
+        subclasses: &str,
+        starting_equipment: &str,
+    ) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawClass> = serde_json::from_str(classes)?;
+        let mut classes: HashMap<String, CharacterClass> = raw
+            .into_iter()
+            .map(|c| (slugify(&c.name), class_from_raw(c)))
+            .collect();
+        let raw: Vec<RawStartingEquipment> = serde_json::from_str(starting_equipment)?;
+        for s in raw {
+            if let Some(class) = classes.get_mut(&slugify(&s.class.name)) {
+                class.starting_items = s.starting_equipment.iter().map(item_stack).collect();
+                class.starting_choices =
+                    [s.choice_1, s.choice_2, s.choice_3, s.choice_4, s.choice_5]
+                        .into_iter()
+                        .filter(|group| !group.is_empty())
+                        .map(|group| {
+                            group
+                                .iter()
+                                .map(|o| EquipmentOption {
+                                    choose: o.choose,
+                                    items: o.from.iter().map(item_stack).collect(),
+                                })
+                                .collect()
+                        })
+                        .collect();
+            }
+        }
+        let raw: Vec<RawSubclass> = serde_json::from_str(subclasses)?;
+        let subclasses = raw
+            .into_iter()
+            .map(|s| {
+                (
+                    slugify(&s.name),
+                    Subclass {
+                        name: s.name,
+                        class: s.class.name,
+                        flavor: s.subclass_flavor,
+                        features: s.features.into_iter().map(|f| f.name).collect(),
+                    },
+                )
+            })
+            .collect();
+        Ok(Self {
+            classes,
+            subclasses,
+        })
+    }
+
+    /// Look a class up by slug or display name.
+    pub fn get(&self, name: &str) -> Option<&CharacterClass> {
+        self.classes.get(&slugify(name))
+    }
+
+    pub fn subclass(&self, name: &str) -> Option<&Subclass> {
+        self.subclasses.get(&slugify(name))
+    }
+
+    /// Every class, sorted by name.
+    pub fn all(&self) -> Vec<&CharacterClass> {
+        let mut all: Vec<&CharacterClass> = self.classes.values().collect();
+        all.sort_by(|a, b| a.name.cmp(&b.name));
+        all
+    }
+}
+
+fn class_from_raw(c: RawClass) -> CharacterClass {
+    let mut class = CharacterClass {
+        name: c.name,
+        hit_die: c.hit_die,
+        saving_throws: c
+            .saving_throws
+            .iter()
+            .filter_map(|s| ability_from_str(&s.name))
+            .collect(),
+        armor: Vec::new(),
+        simple_weapons: false,
+        martial_weapons: false,
+        weapons: Vec::new(),
+        other_proficiencies: Vec::new(),
+        skill_choices: None,
+        subclasses: c.subclasses.into_iter().map(|s| s.name).collect(),
+        starting_items: Vec::new(),
+        starting_choices: Vec::new(),
+    };
+
+    for p in c.proficiencies {
+        match p.name.as_str() {
+            "All armor" => class.armor.extend([
+                ArmorCategory::Light,
+                ArmorCategory::Medium,
+                ArmorCategory::Heavy,
+            ]),
+            "Light armor" => class.armor.push(ArmorCategory::Light),
+            "Medium armor" => class.armor.push(ArmorCategory::Medium),
+            "Heavy armor" => class.armor.push(ArmorCategory::Heavy),
+            "Shields" => class.armor.push(ArmorCategory::Shield),
+            "Simple weapons" => class.simple_weapons = true,
+            "Martial weapons" => class.martial_weapons = true,
+            name if is_weapon_proficiency(name) => class.weapons.push(weapon_slug(name)),
+            _ => class.other_proficiencies.push(p.name),
+        }
+    }
+
+    // Skill picks are listed as "Skill: Athletics"; tool and instrument picks
+    // sit alongside them and are left to the player.
+    class.skill_choices = c.proficiency_choices.into_iter().find_map(|choice| {
+        let from: Vec<String> = choice
+            .from
+            .iter()
+            .filter_map(|p| p.name.strip_prefix("Skill: ").map(str::to_string))
+            .collect();
+        (!from.is_empty()).then_some(SkillChoice {
+            choose: choice.choose,
+            from,
+        })
+    });
+
+    class
+}
+
+fn item_stack(raw: &RawItemCount) -> ItemStack {
+    ItemStack {
+        item: slugify(&raw.item.name),
+        quantity: raw.quantity,
+    }
+}
+
+fn is_weapon_proficiency(name: &str) -> bool {
+    srd_equipment().weapon(&weapon_slug(name)).is_some()
+}
+
+/// "Crossbows, hand" names the weapon "Crossbow, hand": slug "crossbow_hand".
+fn weapon_slug(name: &str) -> String {
+    let (head, tail) = name.split_once(',').unwrap_or((name, ""));
+    let head = head.strip_suffix('s').unwrap_or(head);
+    slugify(&format!("{}{}", head, tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EngineError;
+    use crate::rules::Character;
+
+    const PREGEN: &str = include_str!("../../content/campaigns/pregen_fighter.json");
+
+    #[test]
+    fn srd_classes_and_their_proficiencies() {
+        let library = srd_classes();
+        assert_eq!(library.all().len(), 12);
+        let fighter = library.get("fighter").unwrap();
+        assert_eq!(fighter.hit_die, 10);
+        assert_eq!(
+            fighter.saving_throws,
+            [Ability::Strength, Ability::Constitution]
+        );
+        assert!(
+            fighter.has_armor(ArmorCategory::Heavy) && fighter.has_armor(ArmorCategory::Shield)
+        );
+        assert!(fighter.martial_weapons);
+
+        let wizard = library.get("Wizard").unwrap();
+        assert_eq!(wizard.hit_die, 6);
+        assert!(wizard.has_saving_throw(Ability::Intelligence));
+        assert!(!wizard.has_armor(ArmorCategory::Light));
+        let equipment = srd_equipment();
+        assert!(wizard.has_weapon(equipment.weapon("dagger").unwrap()));
+        assert!(!wizard.has_weapon(equipment.weapon("longsword").unwrap()));
+
+        let rogue = library.get("rogue").unwrap();
+        assert!(rogue.has_weapon(equipment.weapon("rapier").unwrap()));
+        assert_eq!(rogue.skill_choices.as_ref().unwrap().choose, 4);
+        assert_eq!(library.subclass("champion").unwrap().class, "Fighter");
+    }
+
+    #[test]
+    fn class_save_proficiencies_apply_to_the_hero() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        // STR +3 and CON +2 add the +2 proficiency bonus; DEX +1 doesn't.
+        assert_eq!(hero.saving_throw_modifier(Ability::Strength), 5);
+        assert_eq!(hero.saving_throw_modifier(Ability::Constitution), 4);
+        assert_eq!(hero.saving_throw_modifier(Ability::Dexterity), 1);
+        hero.class = Some("Rogue".to_string());
+        assert_eq!(hero.saving_throw_modifier(Ability::Strength), 3);
+        assert_eq!(hero.saving_throw_modifier(Ability::Dexterity), 3);
+        assert_eq!(hero.hit_die(), 8);
+    }
+
+    #[test]
+    fn unknown_or_mismatched_classes_are_rejected() {
+        let json = |class: &str, subclass: &str| {
+            PREGEN.replace(
+                r#""class": "Fighter","#,
+                &format!(r#""class": "{}", "subclass": "{}","#, class, subclass),
+            )
+        };
+        assert!(Character::from_json(&json("Fighter", "Champion")).is_ok());
+        assert_eq!(
+            Character::from_json(&json("Fighter", "Thief")).err(),
+            Some(EngineError::InvalidClass(
+                "Thief is a Rogue subclass".to_string()
+            ))
+        );
+        assert_eq!(
+            Character::from_json(&json("Gunslinger", "Champion")).err(),
+            Some(EngineError::UnknownClass("Gunslinger".to_string()))
+        );
+    }
+}
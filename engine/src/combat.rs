@@ -1,27 +1,307 @@
-use crate::dice::DiceRoller;
-use crate::rules::{Ability, Character};
+use std::collections::HashMap;
+
+use crate::conditions::{Condition, Conditions};
+use crate::dice::{D20Roll, D20Test, DiceExpr, DiceRoller};
+use crate::monsters::slugify;
+use crate::rules::{Ability, AbilityScores, Character, DefeatRule, HouseRules, SaveRoll};
+use crate::spells::{slot_name, Spell, SpellDice, SpellEffect};
 use serde::{Deserialize, Serialize};
 
+/// How damage is worked out on a critical hit.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CritRule {
+    /// Roll the damage dice twice (the 5e rule).
+    #[default]
+    DoubleDice,
+    /// House rule: the dice's maximum plus a normal damage roll.
+    MaxPlusRoll,
+}
+
+impl CritRule {
+    /// Roll `damage` as a critical hit, before any flat bonus outside the expression.
+    pub fn roll(self, damage: &DiceExpr, dice: &mut DiceRoller) -> i32 {
+        match self {
+            CritRule::DoubleDice => dice.eval(&damage.doubled_dice()).total,
+            CritRule::MaxPlusRoll => damage.max_dice().saturating_add(dice.eval(damage).total),
+        }
+    }
+}
+
+/// Outcome of an attack roll: natural 1s always miss and natural 20s always hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackRoll {
+    Miss,
+    Hit,
+    Critical,
+}
+
+impl AttackRoll {
+    pub fn resolve(natural: i32, total: i32, ac: i32) -> Self {
+        match natural {
+            20 => AttackRoll::Critical,
+            1 => AttackRoll::Miss,
+            _ if total >= ac => AttackRoll::Hit,
+            _ => AttackRoll::Miss,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Monster {
+    /// Unique within an encounter, e.g. "goblin_2". Used in `attack:<id>` choices.
+    #[serde(default)]
+    pub id: String,
     pub name: String,
     pub ac: i32,
     pub max_hp: i32,
     pub current_hp: i32,
     pub attack_bonus: i32,
-    pub damage_dice_count: u8,
-    pub damage_dice_sides: u8,
+    pub damage: DiceExpr,
+    /// Type of the attack's main damage, e.g. "Piercing".
+    #[serde(default)]
+    pub damage_type: Option<String>,
+    pub abilities: AbilityScores,
+    /// Saving throw bonuses from the stat block, by ability: `{"dex": 4}`.
+    /// Other saves use the plain ability modifier.
+    #[serde(default)]
+    pub saving_throws: HashMap<String, i32>,
+    pub challenge_rating: f32,
+    #[serde(default)]
+    pub conditions: Conditions,
+}
+
+impl Monster {
+    pub fn is_alive(&self) -> bool {
+        self.current_hp > 0
+    }
+
+    /// At or below half hit points.
+    pub fn is_bloodied(&self) -> bool {
+        self.is_alive() && self.current_hp * 2 <= self.max_hp
+    }
+
+    /// Count down condition durations and roll saves to end them.
+    pub fn end_turn_conditions(&mut self, dice: &mut DiceRoller) -> Vec<String> {
+        let mut conditions = std::mem::take(&mut self.conditions);
+        let log = conditions.end_of_turn(&self.name, |_, ability, dc| {
+            self.saving_throw(ability, dc, D20Test::new(), dice)
+        });
+        self.conditions = conditions;
+        log
+    }
+
+    pub fn saving_throw_modifier(&self, ability: Ability) -> i32 {
+        self.saving_throws
+            .get(ability.short())
+            .copied()
+            .unwrap_or_else(|| self.abilities.modifier(ability))
+    }
+
+    /// Make a saving throw against `dc`. Conditions add to `test` and can fail
+    /// the save outright.
+    pub fn saving_throw(
+        &self,
+        ability: Ability,
+        dc: i32,
+        test: D20Test,
+        dice: &mut DiceRoller,
+    ) -> SaveRoll {
+        SaveRoll::make(
+            ability,
+            dc,
+            self.saving_throw_modifier(ability),
+            &self.conditions,
+            test,
+            || dice.d20(),
+        )
+    }
+
+    /// Stand up from prone at the start of a turn, if the monster can move.
+    pub fn stand_up(&mut self) -> Option<String> {
+        (!self.conditions.immobilized() && self.conditions.remove(Condition::Prone))
+            .then(|| format!("{} stands up.", self.name))
+    }
+}
+
+/// Who is acting: the hero, or a monster by index into `Encounter::monsters`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", content = "index", rename_all = "lowercase")]
+pub enum Combatant {
+    Hero,
+    Monster(usize),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InitiativeSlot {
+    pub combatant: Combatant,
+    pub initiative: i32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Encounter {
     pub monsters: Vec<Monster>,
     pub in_progress: bool,
+    /// Highest initiative first. Empty until `roll_initiative` is called.
+    #[serde(default)]
+    pub turn_order: Vec<InitiativeSlot>,
+    /// Index into `turn_order` of whoever is acting now.
+    #[serde(default)]
+    pub turn: usize,
+    #[serde(default)]
+    pub round: u32,
 }
 
 impl Encounter {
-    pub fn is_over(&self, hero: &Character) -> bool {
-        !self.in_progress || hero.current_hp <= 0 || self.monsters.iter().all(|m| m.current_hp <= 0)
+    pub fn new(monsters: Vec<Monster>) -> Self {
+        let mut encounter = Self {
+            monsters,
+            in_progress: true,
+            turn_order: Vec::new(),
+            turn: 0,
+            round: 0,
+        };
+        encounter.assign_ids();
+        encounter
+    }
+
+    /// Give each monster a unique id, numbering repeats: four goblins become
+    /// "Goblin #1".."Goblin #4" with ids "goblin_1".."goblin_4".
+    pub fn assign_ids(&mut self) {
+        let mut totals: HashMap<String, u32> = HashMap::new();
+        for m in &self.monsters {
+            *totals.entry(m.name.clone()).or_default() += 1;
+        }
+        let mut seen: HashMap<String, u32> = HashMap::new();
+        for m in &mut self.monsters {
+            let base = slugify(&m.name);
+            if totals[&m.name] > 1 {
+                let n = seen.entry(m.name.clone()).or_default();
+                *n += 1;
+                m.id = format!("{}_{}", base, n);
+                m.name = format!("{} #{}", m.name, n);
+            } else {
+                m.id = base;
+            }
+        }
+    }
+
+    pub fn monster_by_id_mut(&mut self, id: &str) -> Option<&mut Monster> {
+        self.monsters.iter_mut().find(|m| m.id == id)
+    }
+
+    /// Roll d20 + DEX for everyone and start round 1. Ties go to the higher DEX
+    /// modifier, then to the hero. The hero's roll is a DEX check, so their
+    /// conditions and Halfling Lucky apply.
+    pub fn roll_initiative(&mut self, hero: &Character, dice: &mut DiceRoller) -> String {
+        let hero_dex = hero.abilities.modifier(Ability::Dexterity);
+        let mut test = D20Test::new();
+        hero.conditions.checking(&mut test);
+        let hero_roll = hero.roll_d20(dice, &test);
+        let mut rolls = vec![(Combatant::Hero, hero_roll.natural + hero_dex, hero_dex)];
+        for (i, m) in self.monsters.iter().enumerate() {
+            let dex = m.abilities.modifier(Ability::Dexterity);
+            rolls.push((Combatant::Monster(i), dice.d20() + dex, dex));
+        }
+        rolls.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then(b.2.cmp(&a.2))
+                .then((b.0 == Combatant::Hero).cmp(&(a.0 == Combatant::Hero)))
+        });
+
+        self.turn_order = rolls
+            .into_iter()
+            .map(|(combatant, initiative, _)| InitiativeSlot {
+                combatant,
+                initiative,
+            })
+            .collect();
+        self.turn = 0;
+        self.round = 1;
+
+        let order: Vec<String> = self
+            .turn_order
+            .iter()
+            .map(|s| format!("{} {}", self.combatant_name(s.combatant), s.initiative))
+            .collect();
+        let mut line = format!("Initiative: {}", order.join(", "));
+        if let Some(note) = hero_roll.mode_note() {
+            line.push_str(&format!(" (your roll: {})", note));
+        }
+        line
+    }
+
+    pub fn combatant_name(&self, c: Combatant) -> String {
+        match c {
+            Combatant::Hero => "You".to_string(),
+            Combatant::Monster(i) => self.monsters[i].name.clone(),
+        }
+    }
+
+    pub fn current_turn(&self) -> Option<Combatant> {
+        self.turn_order.get(self.turn).map(|s| s.combatant)
+    }
+
+    /// Move to the next combatant, starting a new round after the last one.
+    pub fn end_turn(&mut self) {
+        if self.turn_order.is_empty() {
+            return;
+        }
+        self.turn += 1;
+        if self.turn >= self.turn_order.len() {
+            self.turn = 0;
+            self.round += 1;
+        }
+    }
+
+    /// Resolve monster turns until it is the hero's turn or the fight is over.
+    pub fn run_monster_turns(
+        &mut self,
+        hero: &mut Character,
+        rules: &HouseRules,
+        dice: &mut DiceRoller,
+        log: &mut Vec<String>,
+    ) {
+        while !self.is_over(hero, rules) {
+            match self.current_turn() {
+                Some(Combatant::Monster(i)) => {
+                    let mut situation = D20Test::new();
+                    if rules.flanking && self.flanking(i) {
+                        situation.add_advantage("flanking");
+                    }
+                    let monster = &mut self.monsters[i];
+                    if monster.current_hp > 0 {
+                        if let Some(c) = monster.conditions.incapacitated_by() {
+                            log.push(format!("{} is {} and can't act.", monster.name, c.slug()));
+                        } else {
+                            log.extend(monster.stand_up());
+                            log.push(monster_attack(monster, hero, rules.crits, situation, dice));
+                        }
+                        log.extend(monster.end_turn_conditions(dice));
+                    }
+                    self.end_turn();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Whether another monster able to act is fighting alongside monster `i`.
+    fn flanking(&self, i: usize) -> bool {
+        self.monsters
+            .iter()
+            .enumerate()
+            .any(|(j, m)| j != i && m.is_alive() && m.conditions.incapacitated_by().is_none())
+    }
+
+    /// Over once every monster is down, or the hero is dead. Under
+    /// `DefeatRule::Captured` dropping to 0 hit points is enough.
+    pub fn is_over(&self, hero: &Character, rules: &HouseRules) -> bool {
+        let hero_out = match rules.defeat {
+            DefeatRule::DeathSaves => hero.is_dead(),
+            DefeatRule::Captured => hero.current_hp <= 0,
+        };
+        !self.in_progress || hero_out || self.monsters.iter().all(|m| m.current_hp <= 0)
     }
 
     pub fn first_alive_monster_mut(&mut self) -> Option<&mut Monster> {
@@ -29,29 +309,418 @@ impl Encounter {
     }
 }
 
-pub fn hero_attack(hero: &Character, monster: &mut Monster, dice: &mut DiceRoller) -> String {
-    let roll = dice.d20();
-    let attack_bonus = hero.abilities.modifier(Ability::Strength) + hero.proficiency_bonus;
-    let total = roll + attack_bonus;
+pub fn hero_attack(
+    hero: &Character,
+    monster: &mut Monster,
+    crits: CritRule,
+    dice: &mut DiceRoller,
+) -> String {
+    let attack = hero.attack_profile();
+    let melee = !attack.ranged;
+    let mut test = D20Test::new();
+    hero.conditions.attacking(&mut test);
+    monster.conditions.defending(melee, &mut test);
+    let roll = hero.roll_d20(dice, &test);
+    let total = roll.natural + attack.attack_bonus;
+
+    let outcome = auto_crit(
+        AttackRoll::resolve(roll.natural, total, monster.ac),
+        &monster.conditions,
+        melee,
+    );
+    let dmg_roll = match outcome {
+        AttackRoll::Miss if roll.natural == 1 => {
+            return noted(
+                format!("You roll a natural 1 and miss {}.", monster.name),
+                &roll,
+            )
+        }
+        AttackRoll::Miss => return noted(format!("You miss {}.", monster.name), &roll),
+        AttackRoll::Hit => dice.eval(&attack.damage).total,
+        AttackRoll::Critical => crits.roll(&attack.damage, dice),
+    };
+    let dmg = (dmg_roll + attack.damage_bonus).max(1);
+    monster.current_hp -= dmg;
+    noted(
+        format!(
+            "{}You hit {} with your {} for {} {} damage!",
+            if outcome == AttackRoll::Critical {
+                "Critical hit! "
+            } else {
+                ""
+            },
+            monster.name,
+            attack.weapon_name.to_lowercase(),
+            dmg,
+            attack.damage_type.to_lowercase()
+        ),
+        &roll,
+    )
+}
 
-    if total >= monster.ac {
-        let dmg_roll = dice.roll(1, 8);
-        let dmg = (dmg_roll + hero.abilities.modifier(Ability::Strength)).max(1);
-        monster.current_hp -= dmg;
-        format!("You hit {} for {} damage!", monster.name, dmg)
+/// Cast `spell` using a `slot`-level spell slot (0 for a cantrip) and resolve
+/// it against `monsters[target]`, or every living monster for an area spell.
+/// The caller has already checked the slot and target.
+pub fn cast_spell(
+    hero: &mut Character,
+    spell: &Spell,
+    slot: u8,
+    monsters: &mut [Monster],
+    target: Option<usize>,
+    crits: CritRule,
+    dice: &mut DiceRoller,
+) -> Vec<String> {
+    let mut log = Vec::new();
+    if slot > 0 {
+        hero.expend_slot(slot);
+        log.push(format!(
+            "You cast {} with a {} slot ({} left).",
+            spell.name,
+            slot_name(slot),
+            hero.slots_left(slot)
+        ));
     } else {
-        format!("You miss {}.", monster.name)
+        log.push(format!("You cast {}.", spell.name));
+    }
+    let dice_for = |d: SpellDice| spell.dice_at(d, slot, hero.level).to_expr();
+
+    match &spell.effect {
+        Some(SpellEffect::Attack {
+            melee,
+            damage,
+            damage_type,
+        }) => {
+            let Some(monster) = target.and_then(|i| monsters.get_mut(i)) else {
+                return log;
+            };
+            let mut test = D20Test::new();
+            hero.conditions.attacking(&mut test);
+            monster.conditions.defending(*melee, &mut test);
+            let roll = hero.roll_d20(dice, &test);
+            let total = roll.natural + hero.spell_attack_bonus();
+            let outcome = auto_crit(
+                AttackRoll::resolve(roll.natural, total, monster.ac),
+                &monster.conditions,
+                *melee,
+            );
+            let expr = dice_for(*damage);
+            let dmg = match outcome {
+                AttackRoll::Miss => {
+                    log.push(noted(
+                        format!("Your {} misses {}.", spell.name, monster.name),
+                        &roll,
+                    ));
+                    return log;
+                }
+                AttackRoll::Hit => dice.eval(&expr).total,
+                AttackRoll::Critical => crits.roll(&expr, dice),
+            }
+            .max(1);
+            monster.current_hp -= dmg;
+            log.push(noted(
+                format!(
+                    "{}Your {} hits {} for {} {} damage!",
+                    if outcome == AttackRoll::Critical {
+                        "Critical hit! "
+                    } else {
+                        ""
+                    },
+                    spell.name,
+                    monster.name,
+                    dmg,
+                    damage_type
+                ),
+                &roll,
+            ));
+        }
+        Some(SpellEffect::Save {
+            ability,
+            damage,
+            damage_type,
+            half,
+            area,
+        }) => {
+            // One damage roll for everyone caught by the spell.
+            let roll = dice.eval(&dice_for(*damage));
+            log.push(format!("Damage {}", roll));
+            let dc = hero.spell_save_dc();
+            for (i, monster) in monsters.iter_mut().enumerate() {
+                if !monster.is_alive() || (!area && Some(i) != target) {
+                    continue;
+                }
+                let save = monster.saving_throw(*ability, dc, D20Test::new(), dice);
+                let dmg = match (save.success(), half) {
+                    (false, _) => roll.total,
+                    (true, true) => roll.total / 2,
+                    (true, false) => 0,
+                };
+                monster.current_hp -= dmg;
+                log.push(format!(
+                    "{}: {}, and takes {} {} damage.",
+                    monster.name, save, dmg, damage_type
+                ));
+            }
+        }
+        Some(SpellEffect::Darts {
+            count,
+            damage,
+            damage_type,
+        }) => {
+            let Some(monster) = target.and_then(|i| monsters.get_mut(i)) else {
+                return log;
+            };
+            // Each slot level above the spell's own adds a dart.
+            let darts = count + slot.saturating_sub(spell.level) as u32;
+            let expr = damage.to_expr();
+            let dmg: i32 = (0..darts).map(|_| dice.eval(&expr).total).sum();
+            monster.current_hp -= dmg;
+            log.push(format!(
+                "{} darts strike {} for {} {} damage.",
+                darts, monster.name, dmg, damage_type
+            ));
+        }
+        Some(SpellEffect::Healing { dice: heal }) => {
+            let roll = dice.eval(&dice_for(*heal));
+            let amount = (roll.total + hero.spellcasting_modifier()).max(0);
+            let healed = hero.heal(amount);
+            log.push(format!(
+                "Healing {} + {} => you regain {} HP ({}/{}).",
+                roll,
+                hero.spellcasting_modifier(),
+                healed,
+                hero.current_hp,
+                hero.max_hp
+            ));
+        }
+        None => {}
     }
+    log
 }
 
-pub fn monster_attack(monster: &Monster, hero: &mut Character, dice: &mut DiceRoller) -> String {
-    let roll = dice.d20();
-    let total = roll + monster.attack_bonus;
-    if total >= hero.ac {
-        let dmg = dice.roll(monster.damage_dice_count, monster.damage_dice_sides);
-        hero.current_hp -= dmg.max(1);
-        format!("{} hits you for {} damage!", monster.name, dmg.max(1))
+/// Resolve one monster attack against the hero. `situation` carries advantage
+/// or disadvantage from outside either side's conditions, such as flanking.
+pub fn monster_attack(
+    monster: &Monster,
+    hero: &mut Character,
+    crits: CritRule,
+    situation: D20Test,
+    dice: &mut DiceRoller,
+) -> String {
+    // Monster attacks are all treated as melee.
+    let mut test = situation;
+    monster.conditions.attacking(&mut test);
+    hero.conditions.defending(true, &mut test);
+    let roll = dice.d20_test(&test);
+    let total = roll.natural + monster.attack_bonus;
+    let outcome = auto_crit(
+        AttackRoll::resolve(roll.natural, total, hero.armor_class()),
+        &hero.conditions,
+        true,
+    );
+    let critical = outcome == AttackRoll::Critical;
+    let dmg = match outcome {
+        AttackRoll::Miss => return noted(format!("{} misses you.", monster.name), &roll),
+        AttackRoll::Hit => dice.eval(&monster.damage).total,
+        AttackRoll::Critical => crits.roll(&monster.damage, dice),
+    }
+    .max(1);
+    let resisted = monster
+        .damage_type
+        .as_deref()
+        .is_some_and(|t| hero.resists(t));
+    let dmg = if resisted { dmg / 2 } else { dmg };
+    let line = if resisted {
+        format!(
+            "{}{} hits you for {} damage (resisted)!",
+            if critical { "Critical hit! " } else { "" },
+            monster.name,
+            dmg
+        )
+    } else if critical {
+        format!(
+            "Critical hit! {} hits you for {} damage!",
+            monster.name, dmg
+        )
     } else {
-        format!("{} misses you.", monster.name)
+        format!("{} hits you for {} damage!", monster.name, dmg)
+    };
+    let mut line = noted(line, &roll);
+    if let Some(note) = hero.take_damage(dmg, critical) {
+        line.push(' ');
+        line.push_str(&note);
+    }
+    line
+}
+
+/// Put what advantage or disadvantage did before the closing punctuation:
+/// "You miss Goblin (disadvantage from poisoned: [4, (11)])."
+fn noted(mut line: String, roll: &D20Roll) -> String {
+    if let Some(note) = roll.mode_note() {
+        let end = line.pop();
+        line.push_str(&format!(" ({})", note));
+        line.extend(end);
+    }
+    line
+}
+
+/// A melee hit against a paralyzed or unconscious target is a critical hit.
+fn auto_crit(outcome: AttackRoll, target: &Conditions, melee: bool) -> AttackRoll {
+    match outcome {
+        AttackRoll::Hit if melee && target.melee_hits_are_critical() => AttackRoll::Critical,
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conditions::ActiveCondition;
+    use crate::monsters::srd_library;
+
+    fn hero() -> Character {
+        Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json")).unwrap()
+    }
+
+    fn goblins(count: usize) -> Encounter {
+        let goblin = srd_library().get("srd_goblin").unwrap();
+        Encounter::new((0..count).map(|_| goblin.spawn().unwrap()).collect())
+    }
+
+    fn hero_initiative(encounter: &Encounter) -> i32 {
+        encounter
+            .turn_order
+            .iter()
+            .find(|s| s.combatant == Combatant::Hero)
+            .unwrap()
+            .initiative
+    }
+
+    #[test]
+    fn initiative_orders_turns_and_rounds() {
+        let hero = hero();
+        for seed in 0..30 {
+            let mut encounter = goblins(3);
+            let line = encounter.roll_initiative(&hero, &mut DiceRoller::new(seed));
+            assert!(line.starts_with("Initiative: "), "{}", line);
+            assert_eq!(encounter.turn_order.len(), 4);
+            assert_eq!((encounter.turn, encounter.round), (0, 1));
+            for pair in encounter.turn_order.windows(2) {
+                assert!(pair[0].initiative >= pair[1].initiative);
+                // Goblins (DEX +2) win ties against the hero (DEX +1).
+                if pair[0].initiative == pair[1].initiative {
+                    assert_ne!(pair[0].combatant, Combatant::Hero);
+                }
+            }
+            for _ in 0..4 {
+                encounter.end_turn();
+            }
+            assert_eq!((encounter.turn, encounter.round), (0, 2));
+        }
+    }
+
+    #[test]
+    fn natural_rolls_decide_crits_and_misses() {
+        assert_eq!(AttackRoll::resolve(20, 21, 30), AttackRoll::Critical);
+        assert_eq!(AttackRoll::resolve(1, 40, 10), AttackRoll::Miss);
+        assert_eq!(AttackRoll::resolve(10, 15, 15), AttackRoll::Hit);
+        assert_eq!(AttackRoll::resolve(10, 14, 15), AttackRoll::Miss);
+    }
+
+    #[test]
+    fn crit_rules_roll_extra_damage() {
+        let damage = DiceExpr::parse("1d8+3").unwrap();
+        for seed in 0..20 {
+            let doubled = CritRule::DoubleDice.roll(&damage, &mut DiceRoller::new(seed));
+            let expected = DiceRoller::new(seed).roll_expr("2d8+3").unwrap().total;
+            assert_eq!(doubled, expected);
+
+            let max_plus = CritRule::MaxPlusRoll.roll(&damage, &mut DiceRoller::new(seed));
+            let expected = 8 + DiceRoller::new(seed).eval(&damage).total;
+            assert_eq!(max_plus, expected);
+        }
+    }
+
+    #[test]
+    fn only_a_natural_20_hits_an_untouchable_hero() {
+        let goblin = goblins(1).monsters.remove(0);
+        let mut hits = 0;
+        for seed in 0..200 {
+            let mut hero = hero();
+            hero.equipment.armor = None;
+            hero.ac = Some(100);
+            hero.max_hp = 500;
+            hero.current_hp = 500;
+            let line = monster_attack(
+                &goblin,
+                &mut hero,
+                CritRule::DoubleDice,
+                D20Test::new(),
+                &mut DiceRoller::new(seed),
+            );
+            if hero.current_hp < 500 {
+                hits += 1;
+                assert!(
+                    line.starts_with("Critical hit! Goblin hits you"),
+                    "{}",
+                    line
+                );
+            } else {
+                assert_eq!(line, "Goblin misses you.");
+            }
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn frightened_heroes_roll_initiative_with_disadvantage() {
+        let mut hero = hero();
+        hero.conditions.add(ActiveCondition {
+            condition: Condition::Frightened,
+            rounds: None,
+            save: None,
+        });
+        for seed in 0..20 {
+            let mut expected = DiceRoller::new(seed);
+            let lower = expected.d20().min(expected.d20());
+            let mut encounter = goblins(1);
+            let line = encounter.roll_initiative(&hero, &mut DiceRoller::new(seed));
+            assert_eq!(hero_initiative(&encounter), lower + 1);
+            assert!(line.contains("disadvantage from frightened"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn monsters_save_with_their_stat_block_bonuses() {
+        let mut dragon = srd_library()
+            .get("srd_adult_red_dragon")
+            .unwrap()
+            .spawn()
+            .unwrap();
+        // Proficient in DEX saves (+6); STR 27 gives a plain +8.
+        assert_eq!(dragon.saving_throw_modifier(Ability::Dexterity), 6);
+        assert_eq!(dragon.saving_throw_modifier(Ability::Strength), 8);
+
+        let natural = DiceRoller::new(9).d20();
+        let save = dragon.saving_throw(
+            Ability::Dexterity,
+            15,
+            D20Test::new(),
+            &mut DiceRoller::new(9),
+        );
+        assert_eq!(save.total(), natural + 6);
+
+        dragon.conditions.add(ActiveCondition {
+            condition: Condition::Stunned,
+            rounds: None,
+            save: None,
+        });
+        let save = dragon.saving_throw(
+            Ability::Dexterity,
+            1,
+            D20Test::new(),
+            &mut DiceRoller::new(9),
+        );
+        assert_eq!(save.auto_fail, Some(Condition::Stunned));
+        assert!(!save.success());
     }
 }
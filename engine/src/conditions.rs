@@ -0,0 +1,474 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::dice::D20Test;
+use crate::monsters::slugify;
+use crate::rules::{ability_from_str, Ability, SaveRoll};
+
+/// SRD condition rules text, bundled into the library like the monster data.
+const SRD_CONDITIONS_JSON: &str = include_str!("../../data/json/conditions.json");
+
+lazy_static! {
+    static ref SRD_CONDITIONS: ConditionLibrary = ConditionLibrary::from_json(SRD_CONDITIONS_JSON)
+        .expect("Bundled conditions.json is invalid");
+}
+
+/// The condition descriptions built from the bundled SRD data.
+pub fn srd_conditions() -> &'static ConditionLibrary {
+    &SRD_CONDITIONS
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawCondition {
+    name: String,
+    #[serde(default)]
+    desc: Vec<String>,
+}
+
+/// Condition rules text keyed by slug: "blinded", "poisoned".
+#[derive(Clone, Debug, Default)]
+pub struct ConditionLibrary {
+    by_ref: HashMap<String, Vec<String>>,
+}
+
+impl ConditionLibrary {
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawCondition> = serde_json::from_str(s)?;
+        let by_ref = raw
+            .into_iter()
+            .map(|c| (slugify(&c.name), c.desc))
+            .collect();
+        Ok(Self { by_ref })
+    }
+
+    /// Rules text for a condition, one bullet per line.
+    pub fn desc(&self, condition: Condition) -> &[String] {
+        self.by_ref.get(condition.slug()).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// The SRD conditions the rules act on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Blinded,
+    Charmed,
+    Deafened,
+    Frightened,
+    Grappled,
+    Incapacitated,
+    Invisible,
+    Paralyzed,
+    Petrified,
+    Poisoned,
+    Prone,
+    Restrained,
+    Stunned,
+    Unconscious,
+}
+
+impl Condition {
+    pub fn slug(self) -> &'static str {
+        match self {
+            Condition::Blinded => "blinded",
+            Condition::Charmed => "charmed",
+            Condition::Deafened => "deafened",
+            Condition::Frightened => "frightened",
+            Condition::Grappled => "grappled",
+            Condition::Incapacitated => "incapacitated",
+            Condition::Invisible => "invisible",
+            Condition::Paralyzed => "paralyzed",
+            Condition::Petrified => "petrified",
+            Condition::Poisoned => "poisoned",
+            Condition::Prone => "prone",
+            Condition::Restrained => "restrained",
+            Condition::Stunned => "stunned",
+            Condition::Unconscious => "unconscious",
+        }
+    }
+
+    /// Display name: "Poisoned".
+    pub fn name(self) -> String {
+        let slug = self.slug();
+        slug[..1].to_ascii_uppercase() + &slug[1..]
+    }
+
+    /// The creature can't take actions: its turn is lost.
+    pub fn incapacitates(self) -> bool {
+        matches!(
+            self,
+            Condition::Incapacitated
+                | Condition::Paralyzed
+                | Condition::Petrified
+                | Condition::Stunned
+                | Condition::Unconscious
+        )
+    }
+
+    /// The creature's speed drops to 0.
+    pub fn immobilizes(self) -> bool {
+        matches!(
+            self,
+            Condition::Grappled
+                | Condition::Paralyzed
+                | Condition::Petrified
+                | Condition::Restrained
+                | Condition::Stunned
+                | Condition::Unconscious
+        )
+    }
+}
+
+/// A saving throw the creature makes at the end of each of its turns; the
+/// condition ends on a success.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConditionSave {
+    /// "wis", "Constitution", ...
+    pub ability: String,
+    pub dc: i32,
+}
+
+impl ConditionSave {
+    pub fn resolve(&self) -> Result<Ability, String> {
+        ability_from_str(&self.ability)
+            .ok_or_else(|| format!("Unknown save ability '{}'", self.ability))
+    }
+}
+
+/// A condition on a creature, with how it ends. Without `rounds` or `save`
+/// it lasts until something removes it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ActiveCondition {
+    pub condition: Condition,
+    /// Turns of the affected creature left, counted down at the end of each.
+    #[serde(default)]
+    pub rounds: Option<u32>,
+    #[serde(default)]
+    pub save: Option<ConditionSave>,
+}
+
+/// Everything affecting one creature. Serializes as a plain list.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Conditions(Vec<ActiveCondition>);
+
+impl Conditions {
+    pub fn has(&self, condition: Condition) -> bool {
+        self.0.iter().any(|c| c.condition == condition)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveCondition> {
+        self.0.iter()
+    }
+
+    /// Apply a condition. One already in effect is replaced, so the newer
+    /// duration and save win.
+    pub fn add(&mut self, condition: ActiveCondition) {
+        self.remove(condition.condition);
+        self.0.push(condition);
+    }
+
+    /// Returns whether the condition was in effect.
+    pub fn remove(&mut self, condition: Condition) -> bool {
+        let before = self.0.len();
+        self.0.retain(|c| c.condition != condition);
+        self.0.len() != before
+    }
+
+    /// Drop everything that ends on its own, by duration or save, e.g. once a
+    /// fight is over.
+    pub fn clear_expiring(&mut self) {
+        self.0.retain(|c| c.rounds.is_none() && c.save.is_none());
+    }
+
+    /// Display names: ["Poisoned", "Prone"].
+    pub fn names(&self) -> Vec<String> {
+        self.0.iter().map(|c| c.condition.name()).collect()
+    }
+
+    /// The first condition that costs the creature its turn.
+    pub fn incapacitated_by(&self) -> Option<Condition> {
+        self.0
+            .iter()
+            .map(|c| c.condition)
+            .find(|c| c.incapacitates())
+    }
+
+    pub fn immobilized(&self) -> bool {
+        self.0.iter().any(|c| c.condition.immobilizes())
+    }
+
+    /// Add what the creature's conditions do to its own attack roll.
+    pub fn attacking(&self, test: &mut D20Test) {
+        for c in &self.0 {
+            match c.condition {
+                Condition::Invisible => test.add_advantage(c.condition.slug()),
+                Condition::Blinded
+                | Condition::Frightened
+                | Condition::Poisoned
+                | Condition::Prone
+                | Condition::Restrained => test.add_disadvantage(c.condition.slug()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Add what the creature's conditions do to an attack roll against it.
+    pub fn defending(&self, melee: bool, test: &mut D20Test) {
+        for c in &self.0 {
+            let source = format!("target {}", c.condition.slug());
+            match c.condition {
+                Condition::Blinded
+                | Condition::Paralyzed
+                | Condition::Petrified
+                | Condition::Restrained
+                | Condition::Stunned
+                | Condition::Unconscious => test.add_advantage(source),
+                Condition::Prone if melee => test.add_advantage(source),
+                Condition::Prone | Condition::Invisible => test.add_disadvantage(source),
+                _ => {}
+            }
+        }
+    }
+
+    /// Any melee hit against a paralyzed or unconscious creature is a critical hit.
+    pub fn melee_hits_are_critical(&self) -> bool {
+        self.has(Condition::Paralyzed) || self.has(Condition::Unconscious)
+    }
+
+    /// Add what the creature's conditions do to its ability checks.
+    pub fn checking(&self, test: &mut D20Test) {
+        for c in &self.0 {
+            if matches!(c.condition, Condition::Frightened | Condition::Poisoned) {
+                test.add_disadvantage(c.condition.slug());
+            }
+        }
+    }
+
+    /// The condition that makes the creature fail a save outright.
+    pub fn fails_save(&self, ability: Ability) -> Option<Condition> {
+        if !matches!(ability, Ability::Strength | Ability::Dexterity) {
+            return None;
+        }
+        self.0.iter().map(|c| c.condition).find(|c| {
+            matches!(
+                c,
+                Condition::Paralyzed
+                    | Condition::Petrified
+                    | Condition::Stunned
+                    | Condition::Unconscious
+            )
+        })
+    }
+
+    /// Add what the creature's conditions do to a saving throw.
+    pub fn saving(&self, ability: Ability, test: &mut D20Test) {
+        if ability == Ability::Dexterity && self.has(Condition::Restrained) {
+            test.add_disadvantage(Condition::Restrained.slug());
+        }
+    }
+
+    /// Count down durations and roll saves at the end of the creature's turn.
+    /// `save` rolls one saving throw for the condition against the DC. `target` names the creature in the log: "you", "Goblin #1".
+    pub fn end_of_turn(
+        &mut self,
+        target: &str,
+        mut save: impl FnMut(Condition, Ability, i32) -> SaveRoll,
+    ) -> Vec<String> {
+        let mut log = Vec::new();
+        let mut kept = Vec::new();
+        for mut active in std::mem::take(&mut self.0) {
+            let name = active.condition.slug();
+            if let Some(s) = &active.save {
+                if let Ok(ability) = s.resolve() {
+                    let roll = save(active.condition, ability, s.dc);
+                    let success = roll.success();
+                    log.push(format!("Ending {} on {}: {}", name, target, roll));
+                    if success {
+                        log.push(format!("The {} condition on {} ends.", name, target));
+                        continue;
+                    }
+                }
+            }
+            if let Some(rounds) = &mut active.rounds {
+                *rounds = rounds.saturating_sub(1);
+                if *rounds == 0 {
+                    log.push(format!("The {} condition on {} ends.", name, target));
+                    continue;
+                }
+            }
+            kept.push(active);
+        }
+        self.0 = kept;
+        log
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::RollMode;
+
+    const ALL: [Condition; 14] = [
+        Condition::Blinded,
+        Condition::Charmed,
+        Condition::Deafened,
+        Condition::Frightened,
+        Condition::Grappled,
+        Condition::Incapacitated,
+        Condition::Invisible,
+        Condition::Paralyzed,
+        Condition::Petrified,
+        Condition::Poisoned,
+        Condition::Prone,
+        Condition::Restrained,
+        Condition::Stunned,
+        Condition::Unconscious,
+    ];
+
+    fn with(conditions: &[ActiveCondition]) -> Conditions {
+        let mut all = Conditions::default();
+        for c in conditions {
+            all.add(c.clone());
+        }
+        all
+    }
+
+    fn lasting(condition: Condition) -> ActiveCondition {
+        ActiveCondition {
+            condition,
+            rounds: None,
+            save: None,
+        }
+    }
+
+    /// A save against `dc` where every d20 comes up `die`.
+    fn save_rolling(die: i32) -> impl FnMut(Condition, Ability, i32) -> SaveRoll {
+        move |_, ability, dc| {
+            SaveRoll::make(
+                ability,
+                dc,
+                0,
+                &Conditions::default(),
+                D20Test::new(),
+                || die,
+            )
+        }
+    }
+
+    #[test]
+    fn every_condition_has_srd_rules_text() {
+        for condition in ALL {
+            assert!(
+                !srd_conditions().desc(condition).is_empty(),
+                "{}",
+                condition.name()
+            );
+        }
+        assert_eq!(Condition::Poisoned.name(), "Poisoned");
+    }
+
+    #[test]
+    fn prone_helps_melee_and_hinders_ranged_attackers() {
+        let prone = with(&[lasting(Condition::Prone)]);
+        let mut melee = D20Test::new();
+        prone.defending(true, &mut melee);
+        assert_eq!(melee.mode(), RollMode::Advantage);
+        let mut ranged = D20Test::new();
+        prone.defending(false, &mut ranged);
+        assert_eq!(ranged.mode(), RollMode::Disadvantage);
+
+        // Its own attacks are at disadvantage too, unless it's also unseen.
+        let mut own = D20Test::new();
+        prone.attacking(&mut own);
+        assert_eq!(own.mode(), RollMode::Disadvantage);
+        let hidden = with(&[lasting(Condition::Prone), lasting(Condition::Invisible)]);
+        let mut own = D20Test::new();
+        hidden.attacking(&mut own);
+        assert_eq!(own.mode(), RollMode::Normal);
+    }
+
+    #[test]
+    fn stunned_creatures_lose_turns_and_fail_physical_saves() {
+        let stunned = with(&[lasting(Condition::Stunned)]);
+        assert_eq!(stunned.incapacitated_by(), Some(Condition::Stunned));
+        assert!(stunned.immobilized());
+        assert_eq!(
+            stunned.fails_save(Ability::Dexterity),
+            Some(Condition::Stunned)
+        );
+        assert_eq!(stunned.fails_save(Ability::Wisdom), None);
+        assert!(!stunned.melee_hits_are_critical());
+
+        let frightened = with(&[lasting(Condition::Frightened)]);
+        assert_eq!(frightened.incapacitated_by(), None);
+        let mut check = D20Test::new();
+        frightened.checking(&mut check);
+        assert_eq!(check.mode(), RollMode::Disadvantage);
+    }
+
+    #[test]
+    fn a_newer_condition_replaces_the_old_one() {
+        let mut conditions = with(&[ActiveCondition {
+            condition: Condition::Poisoned,
+            rounds: Some(1),
+            save: None,
+        }]);
+        conditions.add(lasting(Condition::Poisoned));
+        assert_eq!(conditions.names(), ["Poisoned"]);
+        assert_eq!(conditions.iter().next().unwrap().rounds, None);
+        assert!(conditions.remove(Condition::Poisoned));
+        assert!(!conditions.remove(Condition::Poisoned));
+    }
+
+    #[test]
+    fn durations_count_down_at_the_end_of_each_turn() {
+        let mut conditions = with(&[
+            ActiveCondition {
+                condition: Condition::Blinded,
+                rounds: Some(2),
+                save: None,
+            },
+            lasting(Condition::Prone),
+        ]);
+        assert!(conditions.end_of_turn("you", save_rolling(20)).is_empty());
+        assert_eq!(
+            conditions.end_of_turn("you", save_rolling(20)),
+            ["The blinded condition on you ends."]
+        );
+        assert_eq!(conditions.names(), ["Prone"]);
+    }
+
+    #[test]
+    fn a_successful_save_ends_the_condition() {
+        let held = ActiveCondition {
+            condition: Condition::Paralyzed,
+            rounds: None,
+            save: Some(ConditionSave {
+                ability: "wis".to_string(),
+                dc: 11,
+            }),
+        };
+
+        let mut conditions = with(std::slice::from_ref(&held));
+        let log = conditions.end_of_turn("Goblin #1", save_rolling(10));
+        assert_eq!(log.len(), 1);
+        assert!(log[0].starts_with("Ending paralyzed on Goblin #1: "));
+        assert!(conditions.has(Condition::Paralyzed));
+
+        let log = conditions.end_of_turn("Goblin #1", save_rolling(11));
+        assert_eq!(log[1], "The paralyzed condition on Goblin #1 ends.");
+        assert!(conditions.is_empty());
+
+        // Leaving the fight clears anything with a way to end.
+        let mut conditions = with(&[held, lasting(Condition::Poisoned)]);
+        conditions.clear_expiring();
+        assert_eq!(conditions.names(), ["Poisoned"]);
+    }
+}
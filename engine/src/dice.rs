@@ -1,15 +1,48 @@
-use rand::rngs::StdRng;
+use std::fmt;
+use std::str::FromStr;
+
 use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Clone, Debug)]
+/// Dice are rolled from ChaCha12 (the same generator as `StdRng`) so the stream
+/// position can be saved and restored exactly.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "DiceState", from = "DiceState")]
 pub struct DiceRoller {
-    rng: StdRng,
+    seed: u64,
+    rng: ChaCha12Rng,
+}
+
+/// Serialized form of a `DiceRoller`: the seed plus how far into the stream we are.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DiceState {
+    pub seed: u64,
+    pub word_pos: u128,
+}
+
+impl From<DiceRoller> for DiceState {
+    fn from(d: DiceRoller) -> Self {
+        DiceState {
+            seed: d.seed,
+            word_pos: d.rng.get_word_pos(),
+        }
+    }
+}
+
+impl From<DiceState> for DiceRoller {
+    fn from(s: DiceState) -> Self {
+        let mut roller = DiceRoller::new(s.seed);
+        roller.rng.set_word_pos(s.word_pos);
+        roller
+    }
 }
 
 impl DiceRoller {
     pub fn new(seed: u64) -> Self {
         Self {
-            rng: StdRng::seed_from_u64(seed),
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
         }
     }
 
@@ -17,9 +50,762 @@ impl DiceRoller {
         self.rng.gen_range(1..=20)
     }
 
+    /// Roll a d20 test: one die, or two with advantage or disadvantage.
+    pub fn d20_test(&mut self, test: &D20Test) -> D20Roll {
+        test.roll_with(|| self.d20())
+    }
+
     pub fn roll(&mut self, count: u8, sides: u8) -> i32 {
         (0..count)
             .map(|_| self.rng.gen_range(1..=sides as i32))
             .sum()
     }
+
+    fn die(&mut self, sides: u32) -> i32 {
+        self.rng.gen_range(1..=sides as i32)
+    }
+
+    /// Parse and roll a dice expression such as "2d6+3" or "4d6kh3".
+    pub fn roll_expr(&mut self, expr: &str) -> Result<RollResult, String> {
+        let parsed: DiceExpr = expr.parse()?;
+        Ok(self.eval(&parsed))
+    }
+
+    /// Roll an already parsed expression.
+    pub fn eval(&mut self, expr: &DiceExpr) -> RollResult {
+        let mut dice = Vec::new();
+        let mut modifier: i32 = 0;
+        let mut total: i32 = 0;
+
+        for term in &expr.terms {
+            match term {
+                Term::Constant(n) => {
+                    modifier = modifier.saturating_add(*n);
+                    total = total.saturating_add(*n);
+                }
+                Term::Dice(d) => {
+                    let start = dice.len();
+                    for _ in 0..d.count {
+                        let mut value = self.die(d.sides);
+                        let mut rerolled = Vec::new();
+                        if let Some(r) = &d.reroll {
+                            // Cap rerolls so "1d1r1" can't loop forever.
+                            let limit = if r.once { 1 } else { 100 };
+                            while rerolled.len() < limit && r.matches(value) {
+                                rerolled.push(value);
+                                value = self.die(d.sides);
+                            }
+                        }
+                        dice.push(DieRoll {
+                            sides: d.sides,
+                            value,
+                            rerolled,
+                            kept: true,
+                            negative: d.negative,
+                        });
+                    }
+
+                    let group = &mut dice[start..];
+                    if let Some(keep) = d.keep {
+                        drop_unkept(group, keep);
+                    }
+                    let sum: i32 = group.iter().filter(|r| r.kept).map(|r| r.value).sum();
+                    total = total.saturating_add(if d.negative { -sum } else { sum });
+                }
+            }
+        }
+
+        RollResult {
+            expr: expr.to_string(),
+            total,
+            dice,
+            modifier,
+        }
+    }
+}
+
+fn drop_unkept(group: &mut [DieRoll], keep: Keep) {
+    let mut order: Vec<usize> = (0..group.len()).collect();
+    let kept = match keep {
+        Keep::Highest(n) => {
+            order.sort_by(|&a, &b| group[b].value.cmp(&group[a].value));
+            n
+        }
+        Keep::Lowest(n) => {
+            order.sort_by(|&a, &b| group[a].value.cmp(&group[b].value));
+            n
+        }
+    };
+    for &i in order.iter().skip(kept as usize) {
+        group[i].kept = false;
+    }
+}
+
+/// A single die as rolled, including any values it replaced by rerolling.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DieRoll {
+    pub sides: u32,
+    pub value: i32,
+    pub rerolled: Vec<i32>,
+    pub kept: bool,
+    pub negative: bool,
+}
+
+/// The outcome of rolling a `DiceExpr`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RollResult {
+    pub expr: String,
+    pub total: i32,
+    pub dice: Vec<DieRoll>,
+    pub modifier: i32,
+}
+
+impl fmt::Display for RollResult {
+    /// e.g. "4d6kh3: [5, 4, (1), 3] = 12"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dice: Vec<String> = self
+            .dice
+            .iter()
+            .map(|d| {
+                let sign = if d.negative { "-" } else { "" };
+                if d.kept {
+                    format!("{}{}", sign, d.value)
+                } else {
+                    format!("({}{})", sign, d.value)
+                }
+            })
+            .collect();
+        write!(f, "{}: [{}]", self.expr, dice.join(", "))?;
+        if self.modifier > 0 {
+            write!(f, " + {}", self.modifier)?;
+        } else if self.modifier < 0 {
+            write!(f, " - {}", -self.modifier)?;
+        }
+        write!(f, " = {}", self.total)
+    }
+}
+
+/// How many d20s a test rolls and which one counts.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RollMode {
+    #[default]
+    Normal,
+    /// Roll two, keep the higher.
+    Advantage,
+    /// Roll two, keep the lower.
+    Disadvantage,
+}
+
+impl RollMode {
+    /// Any number of sources of advantage and disadvantage cancel each other
+    /// out completely.
+    pub fn from_flags(advantage: bool, disadvantage: bool) -> Self {
+        match (advantage, disadvantage) {
+            (true, false) => RollMode::Advantage,
+            (false, true) => RollMode::Disadvantage,
+            _ => RollMode::Normal,
+        }
+    }
+}
+
+/// A d20 test about to be rolled: an attack roll, ability check or saving
+/// throw, with whatever grants it advantage or disadvantage.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct D20Test {
+    /// Sources of advantage, for the log: "flanking", "Brave".
+    pub advantage: Vec<String>,
+    pub disadvantage: Vec<String>,
+}
+
+impl D20Test {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_advantage(&mut self, source: impl Into<String>) {
+        self.advantage.push(source.into());
+    }
+
+    pub fn add_disadvantage(&mut self, source: impl Into<String>) {
+        self.disadvantage.push(source.into());
+    }
+
+    pub fn mode(&self) -> RollMode {
+        RollMode::from_flags(!self.advantage.is_empty(), !self.disadvantage.is_empty())
+    }
+
+    /// Roll with `d20` producing each die, e.g. to let Halfling Lucky reroll 1s.
+    pub fn roll_with(&self, mut d20: impl FnMut() -> i32) -> D20Roll {
+        let mode = self.mode();
+        let dice = match mode {
+            RollMode::Normal => vec![d20()],
+            _ => vec![d20(), d20()],
+        };
+        let natural = match mode {
+            RollMode::Advantage => dice[0].max(dice[1]),
+            RollMode::Disadvantage => dice[0].min(dice[1]),
+            RollMode::Normal => dice[0],
+        };
+        D20Roll {
+            mode,
+            dice,
+            natural,
+            advantage: self.advantage.clone(),
+            disadvantage: self.disadvantage.clone(),
+        }
+    }
+}
+
+/// The outcome of a d20 test. Both dice are kept when two were rolled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct D20Roll {
+    pub mode: RollMode,
+    pub dice: Vec<i32>,
+    /// The die that counts.
+    pub natural: i32,
+    pub advantage: Vec<String>,
+    pub disadvantage: Vec<String>,
+}
+
+impl D20Roll {
+    /// What the roll mode did, e.g. "advantage from prone: [15, (3)]", or
+    /// `None` for a plain roll.
+    pub fn mode_note(&self) -> Option<String> {
+        let (label, sources) = match self.mode {
+            RollMode::Advantage => ("advantage", &self.advantage),
+            RollMode::Disadvantage => ("disadvantage", &self.disadvantage),
+            RollMode::Normal if !self.advantage.is_empty() && !self.disadvantage.is_empty() => {
+                return Some(format!(
+                    "advantage from {} cancels disadvantage from {}",
+                    self.advantage.join(", "),
+                    self.disadvantage.join(", ")
+                ))
+            }
+            RollMode::Normal => return None,
+        };
+        // Ties keep the first die.
+        let kept = self.dice.iter().position(|&d| d == self.natural);
+        let dice: Vec<String> = self
+            .dice
+            .iter()
+            .enumerate()
+            .map(|(i, d)| {
+                if Some(i) == kept {
+                    d.to_string()
+                } else {
+                    format!("({})", d)
+                }
+            })
+            .collect();
+        Some(format!(
+            "{} from {}: [{}]",
+            label,
+            sources.join(", "),
+            dice.join(", ")
+        ))
+    }
+}
+
+impl fmt::Display for D20Roll {
+    /// e.g. "15 (advantage from prone: [15, (3)])"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.natural)?;
+        if let Some(note) = self.mode_note() {
+            write!(f, " ({})", note)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RerollCmp {
+    Equal,
+    AtMost,
+}
+
+/// Reroll dice matching a threshold: `r1` (any 1), `r<2` (1 or 2), `ro1` (only once).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reroll {
+    pub cmp: RerollCmp,
+    pub value: i32,
+    pub once: bool,
+}
+
+impl Reroll {
+    fn matches(&self, v: i32) -> bool {
+        match self.cmp {
+            RerollCmp::Equal => v == self.value,
+            RerollCmp::AtMost => v <= self.value,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceTerm {
+    pub negative: bool,
+    pub count: u32,
+    pub sides: u32,
+    pub keep: Option<Keep>,
+    pub reroll: Option<Reroll>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    Dice(DiceTerm),
+    Constant(i32),
+}
+
+/// A parsed dice expression: one or more dice groups and constants joined by `+`/`-`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiceExpr {
+    pub terms: Vec<Term>,
+}
+
+const MAX_DICE: u32 = 1000;
+const MAX_SIDES: u32 = 1000;
+
+impl DiceExpr {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let src: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if src.is_empty() {
+            return Err("Empty dice expression".to_string());
+        }
+        let err = |msg: &str| format!("Invalid dice expression '{}': {}", s, msg);
+
+        let bytes = src.as_bytes();
+        let mut pos = 0;
+        let mut terms = Vec::new();
+
+        while pos < bytes.len() {
+            let negative = match bytes[pos] {
+                b'+' => {
+                    pos += 1;
+                    false
+                }
+                b'-' => {
+                    pos += 1;
+                    true
+                }
+                _ if terms.is_empty() => false,
+                c => return Err(err(&format!("unexpected '{}'", c as char))),
+            };
+
+            let count = read_number(bytes, &mut pos).map_err(|m| err(&m))?;
+            if pos < bytes.len() && (bytes[pos] == b'd' || bytes[pos] == b'D') {
+                pos += 1;
+                let count = count.unwrap_or(1);
+                let sides = if bytes.get(pos) == Some(&b'%') {
+                    pos += 1;
+                    100
+                } else {
+                    read_number(bytes, &mut pos)
+                        .map_err(|m| err(&m))?
+                        .ok_or_else(|| err("missing die size"))?
+                };
+                // The SRD writes flat damage as "0d4+1", so zero dice is allowed.
+                if count > MAX_DICE {
+                    return Err(err("dice count out of range"));
+                }
+                if sides == 0 || sides > MAX_SIDES {
+                    return Err(err("die size out of range"));
+                }
+
+                let mut term = DiceTerm {
+                    negative,
+                    count,
+                    sides,
+                    keep: None,
+                    reroll: None,
+                };
+                parse_modifiers(bytes, &mut pos, &mut term).map_err(|m| err(&m))?;
+                terms.push(Term::Dice(term));
+            } else {
+                let n = count.ok_or_else(|| err("expected a number or dice"))?;
+                let n = i32::try_from(n).map_err(|_| err("constant too large"))?;
+                terms.push(Term::Constant(if negative { -n } else { n }));
+            }
+        }
+
+        Ok(Self { terms })
+    }
+
+    /// True when the expression contains no dice at all, e.g. "3".
+    pub fn is_constant(&self) -> bool {
+        self.terms.iter().all(|t| matches!(t, Term::Constant(_)))
+    }
+
+    /// The same expression with every dice group rolled twice as many times,
+    /// as on a critical hit: "1d8+3" becomes "2d8+3".
+    pub fn doubled_dice(&self) -> DiceExpr {
+        let terms = self
+            .terms
+            .iter()
+            .map(|t| match t {
+                Term::Dice(d) => Term::Dice(DiceTerm {
+                    count: d.count * 2,
+                    keep: d.keep.map(|k| match k {
+                        Keep::Highest(n) => Keep::Highest(n * 2),
+                        Keep::Lowest(n) => Keep::Lowest(n * 2),
+                    }),
+                    ..d.clone()
+                }),
+                Term::Constant(n) => Term::Constant(*n),
+            })
+            .collect();
+        DiceExpr { terms }
+    }
+
+    /// The highest total the dice alone can show, ignoring constants.
+    pub fn max_dice(&self) -> i32 {
+        self.terms
+            .iter()
+            .map(|t| match t {
+                Term::Dice(d) => {
+                    let kept = match d.keep {
+                        Some(Keep::Highest(n)) | Some(Keep::Lowest(n)) => n,
+                        None => d.count,
+                    };
+                    let max = (kept * d.sides) as i32;
+                    if d.negative {
+                        -max
+                    } else {
+                        max
+                    }
+                }
+                Term::Constant(_) => 0,
+            })
+            .fold(0, i32::saturating_add)
+    }
+}
+
+fn parse_modifiers(bytes: &[u8], pos: &mut usize, term: &mut DiceTerm) -> Result<(), String> {
+    while *pos < bytes.len() {
+        match bytes[*pos] {
+            b'k' => {
+                *pos += 1;
+                let highest = match bytes.get(*pos) {
+                    Some(b'h') => {
+                        *pos += 1;
+                        true
+                    }
+                    Some(b'l') => {
+                        *pos += 1;
+                        false
+                    }
+                    _ => true,
+                };
+                let n = read_number(bytes, pos)?.unwrap_or(1);
+                if n == 0 || n > term.count {
+                    return Err("keep count out of range".to_string());
+                }
+                term.keep = Some(if highest {
+                    Keep::Highest(n)
+                } else {
+                    Keep::Lowest(n)
+                });
+            }
+            b'r' => {
+                *pos += 1;
+                let once = bytes.get(*pos) == Some(&b'o');
+                if once {
+                    *pos += 1;
+                }
+                let cmp = if bytes.get(*pos) == Some(&b'<') {
+                    *pos += 1;
+                    RerollCmp::AtMost
+                } else {
+                    RerollCmp::Equal
+                };
+                let value = read_number(bytes, pos)?.unwrap_or(1);
+                if value > term.sides {
+                    return Err("reroll value out of range".to_string());
+                }
+                let value = value as i32;
+                if cmp == RerollCmp::AtMost && value >= term.sides as i32 {
+                    return Err("reroll threshold covers every face".to_string());
+                }
+                term.reroll = Some(Reroll { cmp, value, once });
+            }
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+/// The number at `pos`, if there is one.
+fn read_number(bytes: &[u8], pos: &mut usize) -> Result<Option<u32>, String> {
+    let start = *pos;
+    while *pos < bytes.len() && bytes[*pos].is_ascii_digit() {
+        *pos += 1;
+    }
+    if *pos == start {
+        return Ok(None);
+    }
+    let digits = String::from_utf8_lossy(&bytes[start..*pos]);
+    digits
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("number {} too large", digits))
+}
+
+impl FromStr for DiceExpr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DiceExpr::parse(s)
+    }
+}
+
+impl fmt::Display for DiceExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            let negative = match term {
+                Term::Dice(d) => d.negative,
+                Term::Constant(n) => *n < 0,
+            };
+            if negative {
+                write!(f, "-")?;
+            } else if i > 0 {
+                write!(f, "+")?;
+            }
+            match term {
+                Term::Constant(n) => write!(f, "{}", n.abs())?,
+                Term::Dice(d) => {
+                    write!(f, "{}d{}", d.count, d.sides)?;
+                    match d.keep {
+                        Some(Keep::Highest(n)) => write!(f, "kh{}", n)?,
+                        Some(Keep::Lowest(n)) => write!(f, "kl{}", n)?,
+                        None => {}
+                    }
+                    if let Some(r) = &d.reroll {
+                        write!(f, "r")?;
+                        if r.once {
+                            write!(f, "o")?;
+                        }
+                        if r.cmp == RerollCmp::AtMost {
+                            write!(f, "<")?;
+                        }
+                        write!(f, "{}", r.value)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for DiceExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DiceExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        DiceExpr::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_err(s: &str) -> String {
+        DiceExpr::parse(s).unwrap_err()
+    }
+
+    #[test]
+    fn parses_and_prints() {
+        for s in [
+            "2d6+3", "4d6kh3", "2d20kl1", "1d8ro1", "2d6r<2", "1d4-1", "0d4+1", "-1d6",
+        ] {
+            assert_eq!(DiceExpr::parse(s).unwrap().to_string(), s);
+        }
+        assert_eq!(DiceExpr::parse("d%").unwrap().to_string(), "1d100");
+        assert_eq!(DiceExpr::parse(" 2 d 6 + 3 ").unwrap().to_string(), "2d6+3");
+        assert_eq!(DiceExpr::parse("1d1000").unwrap().to_string(), "1d1000");
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(parse_err(""), "Empty dice expression");
+        assert_eq!(
+            parse_err("2d"),
+            "Invalid dice expression '2d': missing die size"
+        );
+        assert_eq!(
+            parse_err("d6x"),
+            "Invalid dice expression 'd6x': unexpected 'x'"
+        );
+        assert_eq!(
+            parse_err("2d6+"),
+            "Invalid dice expression '2d6+': expected a number or dice"
+        );
+        assert_eq!(
+            parse_err("1d0"),
+            "Invalid dice expression '1d0': die size out of range"
+        );
+        assert_eq!(
+            parse_err("2d6kh3"),
+            "Invalid dice expression '2d6kh3': keep count out of range"
+        );
+        assert_eq!(
+            parse_err("1d6r<6"),
+            "Invalid dice expression '1d6r<6': reroll threshold covers every face"
+        );
+        assert_eq!(
+            parse_err("1d6r7"),
+            "Invalid dice expression '1d6r7': reroll value out of range"
+        );
+    }
+
+    #[test]
+    fn huge_numbers() {
+        assert_eq!(
+            parse_err("1001d6"),
+            "Invalid dice expression '1001d6': dice count out of range"
+        );
+        assert_eq!(
+            parse_err("1d1001"),
+            "Invalid dice expression '1d1001': die size out of range"
+        );
+        assert_eq!(
+            parse_err("99999999999d6"),
+            "Invalid dice expression '99999999999d6': number 99999999999 too large"
+        );
+        assert_eq!(
+            parse_err("1d99999999999"),
+            "Invalid dice expression '1d99999999999': number 99999999999 too large"
+        );
+        assert_eq!(
+            parse_err("4d6kh99999999999"),
+            "Invalid dice expression '4d6kh99999999999': number 99999999999 too large"
+        );
+        assert_eq!(
+            parse_err("1d6r4294967295"),
+            "Invalid dice expression '1d6r4294967295': reroll value out of range"
+        );
+        assert_eq!(
+            parse_err("2147483648"),
+            "Invalid dice expression '2147483648': constant too large"
+        );
+    }
+
+    #[test]
+    fn totals_saturate() {
+        let mut dice = DiceRoller::new(1);
+        assert_eq!(dice.roll_expr("2147483647+1").unwrap().total, i32::MAX);
+        assert_eq!(
+            dice.roll_expr("-2147483647-2147483647").unwrap().total,
+            -i32::MAX - 1
+        );
+        assert_eq!(dice.roll_expr("2147483647+1d6").unwrap().total, i32::MAX);
+        let many = vec!["1000d1000"; 3000].join("+");
+        assert_eq!(DiceExpr::parse(&many).unwrap().max_dice(), i32::MAX);
+    }
+
+    #[test]
+    fn rolls_stay_in_range() {
+        let mut dice = DiceRoller::new(7);
+        for _ in 0..100 {
+            let r = dice.roll_expr("4d6kh3+2").unwrap();
+            assert!((5..=20).contains(&r.total), "{}", r);
+            assert_eq!(r.dice.iter().filter(|d| d.kept).count(), 3);
+            let r = dice.roll_expr("1d1r1").unwrap();
+            assert_eq!(r.total, 1);
+        }
+    }
+
+    #[test]
+    fn keeps_the_highest_or_lowest_dice() {
+        let mut dice = DiceRoller::new(11);
+        for (expr, highest) in [("4d6kh3", true), ("2d20kl1", false)] {
+            for _ in 0..50 {
+                let r = dice.roll_expr(expr).unwrap();
+                let kept: Vec<i32> = r.dice.iter().filter(|d| d.kept).map(|d| d.value).collect();
+                let dropped: Vec<i32> =
+                    r.dice.iter().filter(|d| !d.kept).map(|d| d.value).collect();
+                assert_eq!(r.total, kept.iter().sum::<i32>(), "{}", r);
+                if highest {
+                    assert!(kept.iter().min() >= dropped.iter().max(), "{}", r);
+                } else {
+                    assert!(kept.iter().max() <= dropped.iter().min(), "{}", r);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let mut a = DiceRoller::new(42);
+        let mut b = DiceRoller::new(42);
+        for expr in ["1d20", "2d6+3", "4d6kh3", "1d8ro1", "1d4-1"] {
+            assert_eq!(a.roll_expr(expr), b.roll_expr(expr));
+        }
+    }
+
+    /// Hands out `dice` in order, one per d20.
+    fn rolling(dice: &[i32]) -> impl FnMut() -> i32 + '_ {
+        let mut dice = dice.iter();
+        move || *dice.next().expect("no more dice")
+    }
+
+    #[test]
+    fn advantage_and_disadvantage_cancel_out() {
+        assert_eq!(RollMode::from_flags(true, false), RollMode::Advantage);
+        assert_eq!(RollMode::from_flags(false, true), RollMode::Disadvantage);
+        assert_eq!(RollMode::from_flags(true, true), RollMode::Normal);
+
+        // Two sources of advantage don't outweigh one of disadvantage.
+        let mut test = D20Test::new();
+        test.add_advantage("flanking");
+        test.add_advantage("target prone");
+        test.add_disadvantage("poisoned");
+        let roll = test.roll_with(rolling(&[7]));
+        assert_eq!(
+            (roll.mode, roll.natural, roll.dice.len()),
+            (RollMode::Normal, 7, 1)
+        );
+        assert_eq!(
+            roll.to_string(),
+            "7 (advantage from flanking, target prone cancels disadvantage from poisoned)"
+        );
+    }
+
+    #[test]
+    fn two_dice_are_rolled_and_both_kept() {
+        let mut test = D20Test::new();
+        test.add_advantage("target prone");
+        let roll = test.roll_with(rolling(&[3, 15]));
+        assert_eq!((roll.dice.clone(), roll.natural), (vec![3, 15], 15));
+        assert_eq!(
+            roll.to_string(),
+            "15 (advantage from target prone: [(3), 15])"
+        );
+
+        let mut test = D20Test::new();
+        test.add_disadvantage("frightened");
+        let roll = test.roll_with(rolling(&[3, 15]));
+        assert_eq!(roll.natural, 3);
+        assert_eq!(
+            roll.mode_note().unwrap(),
+            "disadvantage from frightened: [3, (15)]"
+        );
+        // Ties keep the first die.
+        let roll = test.roll_with(rolling(&[9, 9]));
+        assert_eq!(
+            roll.mode_note().unwrap(),
+            "disadvantage from frightened: [9, (9)]"
+        );
+
+        assert_eq!(D20Test::new().roll_with(rolling(&[12])).mode_note(), None);
+    }
 }
@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+
+use crate::campaign::GameState;
+use crate::conditions::{srd_conditions, ActiveCondition};
+use crate::dice::{DiceExpr, DiceRoller};
+use crate::inventory::{item_info, ItemDef};
+use crate::rules::Character;
+use crate::vars::{Value, Vars};
+
+/// Something that happens to the hero when a node is entered or a choice,
+/// check or save resolves: a trapped chest, a healing spring, a reward.
+///
+/// ```json
+/// {"type": "damage", "dice": "2d6", "damage_type": "Fire", "description": "The chest bursts into flame."}
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Effect {
+    #[serde(flatten)]
+    pub kind: EffectKind,
+    /// Logged before the effect's own line.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EffectKind {
+    /// Roll `dice` and take that much damage, halved if the hero resists it.
+    Damage {
+        dice: String,
+        #[serde(default)]
+        damage_type: Option<String>,
+    },
+    /// Roll `dice` and regain that many hit points.
+    Heal {
+        dice: String,
+    },
+    /// Add items to the inventory, by campaign item id or SRD equipment slug.
+    GiveItem {
+        item: String,
+        #[serde(default = "one")]
+        quantity: u32,
+    },
+    /// Remove items from the inventory. The hero loses what they have, up to
+    /// `quantity`.
+    TakeItem {
+        item: String,
+        #[serde(default = "one")]
+        quantity: u32,
+    },
+    /// Gold pieces gained, or lost when negative.
+    Gold {
+        amount: i64,
+    },
+    Xp {
+        amount: u32,
+    },
+    /// A condition with how it ends:
+    /// `{"type": "condition", "condition": "poisoned", "rounds": 10}`.
+    Condition(ActiveCondition),
+    /// Set a story variable, to `true` unless `value` is given.
+    Flag {
+        flag: String,
+        #[serde(default = "yes")]
+        value: Value,
+    },
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn yes() -> Value {
+    Value::Bool(true)
+}
+
+impl Effect {
+    /// The damage or healing dice, if the effect rolls any.
+    pub fn dice(&self) -> Result<Option<DiceExpr>, String> {
+        match &self.kind {
+            EffectKind::Damage { dice, .. } | EffectKind::Heal { dice } => DiceExpr::parse(dice)
+                .map(Some)
+                .map_err(|e| format!("Bad dice '{}' in effect: {}", dice, e)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Check the effect's dice and item refs.
+    pub fn check(&self, items: &[ItemDef]) -> Result<(), String> {
+        self.dice()?;
+        match &self.kind {
+            EffectKind::GiveItem { item, .. } | EffectKind::TakeItem { item, .. }
+                if item_info(item, items).is_none() =>
+            {
+                Err(format!("Unknown item '{}' in effect", item))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the effect, logging what happened. A broken effect logs why and
+    /// does nothing.
+    pub fn apply(
+        &self,
+        hero: &mut Character,
+        vars: &mut Vars,
+        items: &[ItemDef],
+        dice: &mut DiceRoller,
+        log_lines: &mut Vec<String>,
+    ) {
+        let rolled = match self.check(items).and_then(|_| self.dice()) {
+            Ok(d) => d.map(|d| dice.eval(&d)),
+            Err(msg) => {
+                log_lines.push(msg);
+                return;
+            }
+        };
+        if let Some(desc) = &self.description {
+            log_lines.push(desc.clone());
+        }
+        let line = match (&self.kind, rolled) {
+            (EffectKind::Damage { damage_type, .. }, Some(roll)) => {
+                let mut dmg = roll.total.max(0);
+                let resisted = damage_type.as_deref().is_some_and(|t| hero.resists(t));
+                if resisted {
+                    dmg /= 2;
+                }
+                let kind = damage_type
+                    .as_deref()
+                    .map(|t| format!(" {}", t.to_lowercase()))
+                    .unwrap_or_default();
+                let mut line = format!(
+                    "Damage {} => you take {}{} damage{}.",
+                    roll,
+                    dmg,
+                    kind,
+                    if resisted { " (resisted)" } else { "" }
+                );
+                if let Some(note) = hero.take_damage(dmg, false) {
+                    line.push(' ');
+                    line.push_str(&note);
+                }
+                line
+            }
+            (EffectKind::Heal { .. }, Some(roll)) => {
+                let healed = hero.heal(roll.total);
+                format!("Healing {} => you regain {} hit points.", roll, healed)
+            }
+            (EffectKind::GiveItem { item, quantity }, _) => match hero.give_item(item, *quantity) {
+                0 => format!("You can't carry any more {}.", item_name(item, items)),
+                n => format!("You gain {}.", item_line(item, n, items)),
+            },
+            (EffectKind::TakeItem { item, quantity }, _) => match hero.take_item(item, *quantity) {
+                0 => format!("You have no {} to give up.", item_name(item, items)),
+                n => format!("You lose {}.", item_line(item, n, items)),
+            },
+            (EffectKind::Gold { amount }, _) => {
+                let before = hero.gold;
+                hero.gold = (before as i64)
+                    .saturating_add(*amount)
+                    .clamp(0, u32::MAX as i64) as u32;
+                if *amount >= 0 {
+                    format!("You gain {} gp.", hero.gold - before)
+                } else {
+                    format!("You lose {} gp.", before - hero.gold)
+                }
+            }
+            (EffectKind::Xp { amount }, _) => {
+                let before = hero.xp;
+                hero.xp = hero.xp.saturating_add(*amount);
+                format!("You gain {} XP.", hero.xp - before)
+            }
+            (EffectKind::Condition(c), _) => {
+                apply_conditions(hero, std::slice::from_ref(c), log_lines);
+                return;
+            }
+            (EffectKind::Flag { flag, value }, _) => {
+                vars.set(flag, value.clone());
+                format!("{} is now {}.", flag, value)
+            }
+            (EffectKind::Damage { .. } | EffectKind::Heal { .. }, None) => return,
+        };
+        log_lines.push(line);
+    }
+}
+
+/// Apply each effect in order. `items` are the campaign's own items.
+pub fn apply_effects(
+    effects: &[Effect],
+    state: &mut GameState,
+    items: &[ItemDef],
+    dice: &mut DiceRoller,
+    log_lines: &mut Vec<String>,
+) {
+    for effect in effects {
+        effect.apply(
+            &mut state.character,
+            &mut state.vars,
+            items,
+            dice,
+            log_lines,
+        );
+    }
+}
+
+/// Give the hero each condition, logging the first line of its rules text.
+pub fn apply_conditions(
+    hero: &mut Character,
+    conditions: &[ActiveCondition],
+    log_lines: &mut Vec<String>,
+) {
+    for c in conditions {
+        hero.conditions.add(c.clone());
+        let rules = srd_conditions().desc(c.condition);
+        log_lines.push(match rules.first() {
+            Some(first) => format!(
+                "You are {}. {}",
+                c.condition.slug(),
+                first.trim_start_matches("- ")
+            ),
+            None => format!("You are {}.", c.condition.slug()),
+        });
+    }
+}
+
+fn item_name(item: &str, items: &[ItemDef]) -> String {
+    item_info(item, items).map_or_else(|| item.to_string(), |i| i.name)
+}
+
+/// "Torch" or "Torch x3".
+fn item_line(item: &str, quantity: u32, items: &[ItemDef]) -> String {
+    match quantity {
+        1 => item_name(item, items),
+        n => format!("{} x{}", item_name(item, items), n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(kind: EffectKind, hero: &mut Character) -> Vec<String> {
+        let mut log_lines = Vec::new();
+        Effect {
+            kind,
+            description: None,
+        }
+        .apply(
+            hero,
+            &mut Vars::default(),
+            &[],
+            &mut DiceRoller::new(1),
+            &mut log_lines,
+        );
+        log_lines
+    }
+
+    #[test]
+    fn gold_and_xp_clamp_and_log_what_changed() {
+        let mut hero =
+            Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        hero.gold = 10;
+        assert_eq!(
+            apply(EffectKind::Gold { amount: -25 }, &mut hero),
+            ["You lose 10 gp."]
+        );
+        assert_eq!(hero.gold, 0);
+        assert_eq!(
+            apply(EffectKind::Gold { amount: i64::MAX }, &mut hero),
+            [format!("You gain {} gp.", u32::MAX)]
+        );
+        assert_eq!(hero.gold, u32::MAX);
+        assert_eq!(
+            apply(EffectKind::Gold { amount: i64::MIN }, &mut hero),
+            [format!("You lose {} gp.", u32::MAX)]
+        );
+        assert_eq!(hero.gold, 0);
+
+        hero.xp = u32::MAX - 5;
+        assert_eq!(
+            apply(EffectKind::Xp { amount: 100 }, &mut hero),
+            ["You gain 5 XP."]
+        );
+        assert_eq!(hero.xp, u32::MAX);
+    }
+
+    #[test]
+    fn item_stacks_saturate_and_log_what_was_added() {
+        let mut hero =
+            Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        let give = |quantity| EffectKind::GiveItem {
+            item: "torch".to_string(),
+            quantity,
+        };
+        apply(give(u32::MAX - 2), &mut hero);
+        assert_eq!(apply(give(10), &mut hero), ["You gain Torch x2."]);
+        assert_eq!(
+            apply(give(1), &mut hero),
+            ["You can't carry any more Torch."]
+        );
+        let torches = hero.items.iter().find(|s| s.item == "torch").unwrap();
+        assert_eq!(torches.quantity, u32::MAX);
+    }
+
+    /// Apply effects written as campaign JSON.
+    fn apply_json(json: &str, hero: &mut Character, vars: &mut Vars) -> Vec<String> {
+        let effects: Vec<Effect> = serde_json::from_str(json).unwrap();
+        let mut log_lines = Vec::new();
+        for effect in &effects {
+            effect.apply(hero, vars, &[], &mut DiceRoller::new(1), &mut log_lines);
+        }
+        log_lines
+    }
+
+    #[test]
+    fn damage_is_resisted_and_healing_capped() {
+        let mut hero =
+            Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        hero.race = Some("Tiefling".to_string());
+        let mut vars = Vars::default();
+        let log = apply_json(
+            r#"[
+                {"type": "damage", "dice": "6", "damage_type": "Fire",
+                 "description": "The chest bursts into flame."},
+                {"type": "damage", "dice": "4", "damage_type": "Poison"}
+            ]"#,
+            &mut hero,
+            &mut vars,
+        );
+        assert_eq!(log[0], "The chest bursts into flame.");
+        assert!(
+            log[1].ends_with("=> you take 3 fire damage (resisted)."),
+            "{}",
+            log[1]
+        );
+        assert!(
+            log[2].ends_with("=> you take 4 poison damage."),
+            "{}",
+            log[2]
+        );
+        assert_eq!(hero.current_hp, 5);
+
+        let log = apply_json(r#"[{"type": "heal", "dice": "20"}]"#, &mut hero, &mut vars);
+        assert!(
+            log[0].ends_with("=> you regain 7 hit points."),
+            "{}",
+            log[0]
+        );
+        assert_eq!(hero.current_hp, hero.max_hp);
+    }
+
+    #[test]
+    fn conditions_flags_and_lost_items() {
+        let mut hero =
+            Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        let mut vars = Vars::default();
+        let log = apply_json(
+            r#"[
+                {"type": "condition", "condition": "poisoned", "rounds": 10},
+                {"type": "flag", "flag": "opened_chest"},
+                {"type": "flag", "flag": "chest_gold", "value": 40},
+                {"type": "give_item", "item": "torch", "quantity": 2},
+                {"type": "take_item", "item": "torch", "quantity": 5},
+                {"type": "take_item", "item": "rope_hempen_50_feet"}
+            ]"#,
+            &mut hero,
+            &mut vars,
+        );
+        assert!(log[0].starts_with("You are poisoned. "), "{}", log[0]);
+        assert_eq!(hero.conditions.iter().next().unwrap().rounds, Some(10));
+        assert_eq!(
+            log[1..],
+            [
+                "opened_chest is now true.",
+                "chest_gold is now 40.",
+                "You gain Torch x2.",
+                "You lose Torch x2.",
+                "You have no Rope, hempen (50 feet) to give up.",
+            ]
+        );
+        assert_eq!(vars.get("chest_gold"), Some(&Value::Int(40)));
+    }
+
+    #[test]
+    fn broken_effects_log_why_and_do_nothing() {
+        let mut hero =
+            Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        let mut vars = Vars::default();
+        let log = apply_json(
+            r#"[
+                {"type": "damage", "dice": "2d", "description": "Never logged."},
+                {"type": "give_item", "item": "vorpal_spoon"}
+            ]"#,
+            &mut hero,
+            &mut vars,
+        );
+        assert_eq!(
+            log,
+            [
+                "Bad dice '2d' in effect: Invalid dice expression '2d': missing die size",
+                "Unknown item 'vorpal_spoon' in effect",
+            ]
+        );
+        assert_eq!(hero.current_hp, hero.max_hp);
+        assert!(hero.items.is_empty());
+    }
+}
@@ -1,6 +1,25 @@
+use serde::{Deserialize, Serialize};
+
 use crate::campaign::{Campaign, GameState, NodeView};
 use crate::dice::DiceRoller;
+use crate::error::EngineError;
+use crate::monsters::srd_library;
 use crate::rules::Character;
+use crate::sheet::CharacterSheet;
+use crate::vars::Vars;
+
+/// Bump when the save format changes in a way older saves can't be read.
+pub const SAVE_VERSION: u32 = 1;
+
+/// A versioned snapshot of a run in progress. The campaign itself is not
+/// included; it is matched by id when the save is loaded.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveGame {
+    pub version: u32,
+    pub campaign_id: String,
+    pub state: GameState,
+    pub dice: DiceRoller,
+}
 
 pub struct Engine {
     pub campaign: Campaign,
@@ -9,26 +28,146 @@ pub struct Engine {
 }
 
 impl Engine {
-    pub fn new(campaign: Campaign, character: Character, seed: u64) -> Self {
+    pub fn new(campaign: Campaign, character: Character, seed: u64) -> Result<Self, EngineError> {
+        if !campaign.has_node(&campaign.start_node_id) {
+            return Err(EngineError::MissingNode(campaign.start_node_id.clone()));
+        }
+        campaign.check_monster_refs(srd_library())?;
+        campaign.check_expressions()?;
         let start_id = campaign.start_node_id.clone();
-        Self {
+        let mut engine = Self {
             campaign,
             state: GameState {
                 character,
                 current_node_id: start_id,
                 encounter: None,
                 last_log: None,
+                short_rest: None,
+                level_up: None,
+                vars: Vars::default(),
             },
             dice: DiceRoller::new(seed),
+        };
+        engine.campaign.start(&mut engine.state, &mut engine.dice)?;
+        Ok(engine)
+    }
+
+    /// Restore a run from `save()` output. Continuing from here rolls the same
+    /// dice as if the game had never stopped.
+    pub fn load(campaign: Campaign, save_json: &str) -> Result<Self, EngineError> {
+        let save: SaveGame =
+            serde_json::from_str(save_json).map_err(|e| EngineError::invalid_json("save", e))?;
+        if save.version != SAVE_VERSION {
+            return Err(EngineError::InvalidSave(format!(
+                "unsupported version {} (expected {})",
+                save.version, SAVE_VERSION
+            )));
         }
+        if save.campaign_id != campaign.id {
+            return Err(EngineError::InvalidSave(format!(
+                "made for campaign '{}', not '{}'",
+                save.campaign_id, campaign.id
+            )));
+        }
+        if !campaign.has_node(&save.state.current_node_id) {
+            return Err(EngineError::MissingNode(save.state.current_node_id));
+        }
+        campaign.check_monster_refs(srd_library())?;
+        campaign.check_expressions()?;
+
+        Ok(Self {
+            campaign,
+            state: save.state,
+            dice: save.dice,
+        })
     }
 
-    pub fn current_view(&self) -> NodeView {
+    pub fn save(&self) -> Result<String, EngineError> {
+        let save = SaveGame {
+            version: SAVE_VERSION,
+            campaign_id: self.campaign.id.clone(),
+            state: self.state.clone(),
+            dice: self.dice.clone(),
+        };
+        serde_json::to_string(&save)
+            .map_err(|e| EngineError::Internal(format!("Failed to serialize save: {e}")))
+    }
+
+    pub fn current_view(&self) -> Result<NodeView, EngineError> {
         self.campaign.view_node(&self.state)
     }
 
-    pub fn choose(&mut self, choice_id: &str) {
+    /// The full character sheet, inventory included.
+    pub fn character_sheet(&self) -> CharacterSheet {
+        CharacterSheet::new(&self.state.character, &self.campaign.items)
+    }
+
+    pub fn choose(&mut self, choice_id: &str) -> Result<(), EngineError> {
         self.campaign
-            .apply_choice(choice_id, &mut self.state, &mut self.dice);
+            .apply_choice(choice_id, &mut self.state, &mut self.dice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMPAIGN: &str = include_str!("../../content/campaigns/road_to_redcrest.json");
+    const HERO: &str = include_str!("../../content/campaigns/pregen_fighter.json");
+
+    fn engine(seed: u64) -> Engine {
+        Engine::new(
+            Campaign::from_json(CAMPAIGN).unwrap(),
+            Character::from_json(HERO).unwrap(),
+            seed,
+        )
+        .unwrap()
+    }
+
+    /// Take the first choice `steps` times, or until the story ends.
+    fn play(engine: &mut Engine, steps: usize) {
+        for _ in 0..steps {
+            let Some(choice) = engine.current_view().unwrap().choices.into_iter().next() else {
+                return;
+            };
+            engine.choose(&choice.id).unwrap();
+        }
+    }
+
+    #[test]
+    fn a_loaded_save_plays_on_exactly_like_the_original() {
+        let mut original = engine(5);
+        play(&mut original, 2);
+        let save = original.save().unwrap();
+        let mut loaded = Engine::load(Campaign::from_json(CAMPAIGN).unwrap(), &save).unwrap();
+        play(&mut original, 12);
+        play(&mut loaded, 12);
+        assert_eq!(
+            serde_json::to_value(&loaded.state).unwrap(),
+            serde_json::to_value(&original.state).unwrap()
+        );
+        assert_eq!(loaded.save().unwrap(), original.save().unwrap());
+    }
+
+    #[test]
+    fn saves_from_other_versions_or_campaigns_are_refused() {
+        let save = engine(1).save().unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&save).unwrap();
+        value["version"] = (SAVE_VERSION + 1).into();
+        assert!(matches!(
+            Engine::load(Campaign::from_json(CAMPAIGN).unwrap(), &value.to_string()),
+            Err(EngineError::InvalidSave(_))
+        ));
+
+        let mut other = Campaign::from_json(CAMPAIGN).unwrap();
+        other.id = "other".to_string();
+        assert!(matches!(
+            Engine::load(other, &save),
+            Err(EngineError::InvalidSave(_))
+        ));
+        assert!(matches!(
+            Engine::load(Campaign::from_json(CAMPAIGN).unwrap(), "{"),
+            Err(EngineError::InvalidJson { .. })
+        ));
     }
 }
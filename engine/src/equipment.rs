@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::dice::{DiceExpr, Term};
+use crate::monsters::slugify;
+
+/// SRD equipment, bundled into the library like the monster data.
+const SRD_EQUIPMENT_JSON: &str = include_str!("../../data/json/equipment.json");
+
+lazy_static! {
+    static ref SRD_EQUIPMENT: EquipmentLibrary =
+        EquipmentLibrary::from_json(SRD_EQUIPMENT_JSON).expect("Bundled equipment.json is invalid");
+}
+
+/// The equipment library built from the bundled SRD data.
+pub fn srd_equipment() -> &'static EquipmentLibrary {
+    &SRD_EQUIPMENT
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct NamedRef {
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawDamage {
+    damage_dice: String,
+    damage_type: NamedRef,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawArmorClass {
+    base: i32,
+    dex_bonus: bool,
+    #[serde(default)]
+    max_bonus: Option<i32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawEquipment {
+    name: String,
+    equipment_category: String,
+    #[serde(default)]
+    weight: f32,
+    #[serde(default)]
+    desc: Vec<String>,
+    // Weapons
+    #[serde(default)]
+    weapon_category: Option<String>,
+    #[serde(default)]
+    weapon_range: Option<String>,
+    #[serde(default)]
+    damage: Option<RawDamage>,
+    #[serde(default, rename = "2h_damage")]
+    two_handed_damage: Option<RawDamage>,
+    #[serde(default)]
+    properties: Vec<NamedRef>,
+    // Armor
+    #[serde(default)]
+    armor_category: Option<String>,
+    #[serde(default)]
+    armor_class: Option<RawArmorClass>,
+    #[serde(default)]
+    str_minimum: i32,
+    #[serde(default)]
+    stealth_disadvantage: bool,
+}
+
+/// Weapon properties as named in `weaponProperties.json`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeaponProperty {
+    Ammunition,
+    Finesse,
+    Heavy,
+    Light,
+    Loading,
+    Monk,
+    Reach,
+    Special,
+    Thrown,
+    TwoHanded,
+    Versatile,
+}
+
+impl WeaponProperty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Ammunition" => Some(WeaponProperty::Ammunition),
+            "Finesse" => Some(WeaponProperty::Finesse),
+            "Heavy" => Some(WeaponProperty::Heavy),
+            "Light" => Some(WeaponProperty::Light),
+            "Loading" => Some(WeaponProperty::Loading),
+            "Monk" => Some(WeaponProperty::Monk),
+            "Reach" => Some(WeaponProperty::Reach),
+            "Special" => Some(WeaponProperty::Special),
+            "Thrown" => Some(WeaponProperty::Thrown),
+            "Two-Handed" => Some(WeaponProperty::TwoHanded),
+            "Versatile" => Some(WeaponProperty::Versatile),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Weapon {
+    pub name: String,
+    /// "Simple" or "Martial".
+    pub category: String,
+    pub ranged: bool,
+    pub damage: DiceExpr,
+    pub damage_type: String,
+    /// Damage when a versatile weapon is used in two hands.
+    pub two_handed_damage: Option<DiceExpr>,
+    pub properties: Vec<WeaponProperty>,
+}
+
+impl Weapon {
+    pub fn has(&self, p: WeaponProperty) -> bool {
+        self.properties.contains(&p)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArmorCategory {
+    Light,
+    Medium,
+    Heavy,
+    Shield,
+}
+
+#[derive(Clone, Debug)]
+pub struct Armor {
+    pub name: String,
+    pub category: ArmorCategory,
+    pub base_ac: i32,
+    pub dex_bonus: bool,
+    pub max_dex_bonus: Option<i32>,
+    pub str_minimum: i32,
+    pub stealth_disadvantage: bool,
+}
+
+impl Armor {
+    /// AC from this armor for a wearer with the given DEX modifier. For a
+    /// shield this is the bonus it adds.
+    pub fn ac_for(&self, dex_mod: i32) -> i32 {
+        if !self.dex_bonus {
+            return self.base_ac;
+        }
+        match self.max_dex_bonus {
+            Some(cap) => self.base_ac + dex_mod.min(cap),
+            None => self.base_ac + dex_mod,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ItemKind {
+    Weapon(Weapon),
+    Armor(Armor),
+    /// Adventuring gear, tools, mounts: anything without combat stats.
+    Gear {
+        category: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct EquipmentItem {
+    pub name: String,
+    pub weight: f32,
+    pub desc: Vec<String>,
+    pub kind: ItemKind,
+    /// Hit points regained by drinking it, read from the description:
+    /// 2d4+2 for a Potion of Healing.
+    pub healing: Option<DiceExpr>,
+}
+
+/// SRD equipment keyed by slug: "longsword", "chain_mail", "rope_hempen_50_feet".
+#[derive(Clone, Debug, Default)]
+pub struct EquipmentLibrary {
+    by_ref: HashMap<String, EquipmentItem>,
+}
+
+impl EquipmentLibrary {
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawEquipment> = serde_json::from_str(s)?;
+        let by_ref = raw
+            .into_iter()
+            .map(|e| (slugify(&e.name), item_from_raw(e)))
+            .collect();
+        Ok(Self { by_ref })
+    }
+
+    pub fn get(&self, r: &str) -> Option<&EquipmentItem> {
+        self.by_ref.get(r)
+    }
+
+    pub fn weapon(&self, r: &str) -> Option<&Weapon> {
+        match &self.get(r)?.kind {
+            ItemKind::Weapon(w) => Some(w),
+            _ => None,
+        }
+    }
+
+    pub fn armor(&self, r: &str) -> Option<&Armor> {
+        match &self.get(r)?.kind {
+            ItemKind::Armor(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+fn item_from_raw(e: RawEquipment) -> EquipmentItem {
+    let kind = match (e.equipment_category.as_str(), e.damage, e.armor_class) {
+        ("Weapon", Some(damage), _) => ItemKind::Weapon(Weapon {
+            name: e.name.clone(),
+            category: e.weapon_category.unwrap_or_default(),
+            ranged: e.weapon_range.as_deref() == Some("Ranged"),
+            damage: weapon_damage(&damage.damage_dice),
+            damage_type: damage.damage_type.name,
+            two_handed_damage: e.two_handed_damage.map(|d| weapon_damage(&d.damage_dice)),
+            properties: e
+                .properties
+                .iter()
+                .filter_map(|p| WeaponProperty::from_name(&p.name))
+                .collect(),
+        }),
+        ("Armor", _, Some(ac)) => ItemKind::Armor(Armor {
+            name: e.name.clone(),
+            category: match e.armor_category.as_deref() {
+                Some("Medium") => ArmorCategory::Medium,
+                Some("Heavy") => ArmorCategory::Heavy,
+                Some("Shield") => ArmorCategory::Shield,
+                _ => ArmorCategory::Light,
+            },
+            base_ac: ac.base,
+            dex_bonus: ac.dex_bonus,
+            max_dex_bonus: ac.max_bonus,
+            str_minimum: e.str_minimum,
+            stealth_disadvantage: e.stealth_disadvantage,
+        }),
+        (category, _, _) => ItemKind::Gear {
+            category: category.to_string(),
+        },
+    };
+
+    EquipmentItem {
+        healing: healing(&e.desc),
+        name: e.name,
+        weight: e.weight,
+        desc: e.desc,
+        kind,
+    }
+}
+
+/// The dice in "... regains 2d4 + 2 hit points."
+fn healing(desc: &[String]) -> Option<DiceExpr> {
+    desc.iter().find_map(|line| {
+        let (_, rest) = line.split_once("regains ")?;
+        let (dice, _) = rest.split_once(" hit points")?;
+        DiceExpr::parse(&dice.replace(' ', "")).ok()
+    })
+}
+
+/// The SRD lists the Net as "1d0": it deals no damage.
+fn weapon_damage(dice: &str) -> DiceExpr {
+    DiceExpr::parse(dice).unwrap_or_else(|_| DiceExpr {
+        terms: vec![Term::Constant(0)],
+    })
+}
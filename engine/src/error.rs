@@ -0,0 +1,135 @@
+use std::fmt;
+
+/// Everything that can go wrong while loading content or driving the engine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EngineError {
+    /// `what` is the kind of document: "campaign", "character", "save", ...
+    InvalidJson {
+        what: String,
+        message: String,
+    },
+    MissingNode(String),
+    UnknownChoice {
+        node_id: String,
+        choice_id: String,
+    },
+    UnknownMonster {
+        node_id: String,
+        monster_ref: String,
+    },
+    InvalidDice(String),
+    UnknownEquipment(String),
+    InvalidEquipment(String),
+    UnknownSkill(String),
+    UnknownClass(String),
+    InvalidClass(String),
+    UnknownRace(String),
+    InvalidRace(String),
+    UnknownSpell(String),
+    InvalidSpell(String),
+    /// A campaign expression, e.g. a choice's `requires`, that doesn't parse.
+    InvalidExpression {
+        node_id: String,
+        choice_id: Option<String>,
+        message: String,
+    },
+    InvalidSave(String),
+    /// The hero was sent from node to node on arrival, by saving throws,
+    /// knock-outs or instant fights, without ever stopping. Holds the node id
+    /// the engine gave up at.
+    NodeLoop(String),
+    /// A null pointer or non-UTF-8 string crossed the FFI boundary.
+    InvalidInput(String),
+    NotInitialized,
+    Internal(String),
+}
+
+impl EngineError {
+    pub fn invalid_json(what: &str, err: serde_json::Error) -> Self {
+        EngineError::InvalidJson {
+            what: what.to_string(),
+            message: err.to_string(),
+        }
+    }
+
+    /// Stable status code reported through the C ABI. 0 is reserved for success.
+    pub fn code(&self) -> i32 {
+        match self {
+            EngineError::InvalidInput(_) => 1,
+            EngineError::InvalidJson { .. } => 2,
+            EngineError::MissingNode(_) => 3,
+            EngineError::UnknownChoice { .. } => 4,
+            EngineError::UnknownMonster { .. } => 5,
+            EngineError::InvalidDice(_) => 6,
+            EngineError::InvalidSave(_) => 7,
+            EngineError::NotInitialized => 8,
+            EngineError::UnknownEquipment(_) => 9,
+            EngineError::InvalidEquipment(_) => 10,
+            EngineError::UnknownSkill(_) => 11,
+            EngineError::UnknownClass(_) => 12,
+            EngineError::InvalidClass(_) => 13,
+            EngineError::UnknownRace(_) => 14,
+            EngineError::InvalidRace(_) => 15,
+            EngineError::UnknownSpell(_) => 16,
+            EngineError::InvalidSpell(_) => 17,
+            EngineError::InvalidExpression { .. } => 18,
+            EngineError::NodeLoop(_) => 19,
+            EngineError::Internal(_) => 99,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::InvalidJson { what, message } => {
+                write!(f, "Invalid {} JSON: {}", what, message)
+            }
+            EngineError::MissingNode(id) => write!(f, "Node not found: {}", id),
+            EngineError::UnknownChoice { node_id, choice_id } => {
+                write!(f, "Unknown choice '{}' in node '{}'", choice_id, node_id)
+            }
+            EngineError::UnknownMonster {
+                node_id,
+                monster_ref,
+            } => write!(
+                f,
+                "Unknown monster ref '{}' in node '{}'",
+                monster_ref, node_id
+            ),
+            EngineError::InvalidDice(msg) => write!(f, "{}", msg),
+            EngineError::UnknownEquipment(r) => write!(f, "Unknown equipment: {}", r),
+            EngineError::InvalidEquipment(msg) => write!(f, "Invalid equipment: {}", msg),
+            EngineError::UnknownSkill(s) => write!(f, "Unknown skill: {}", s),
+            EngineError::UnknownClass(c) => write!(f, "Unknown class: {}", c),
+            EngineError::InvalidClass(msg) => write!(f, "Invalid class: {}", msg),
+            EngineError::UnknownRace(r) => write!(f, "Unknown race: {}", r),
+            EngineError::InvalidRace(msg) => write!(f, "Invalid race: {}", msg),
+            EngineError::UnknownSpell(s) => write!(f, "Unknown spell: {}", s),
+            EngineError::InvalidSpell(msg) => write!(f, "Invalid spell: {}", msg),
+            EngineError::InvalidExpression {
+                node_id,
+                choice_id: Some(choice_id),
+                message,
+            } => write!(
+                f,
+                "Invalid expression in node '{}', choice '{}': {}",
+                node_id, choice_id, message
+            ),
+            EngineError::InvalidExpression {
+                node_id, message, ..
+            } => write!(f, "Invalid expression in node '{}': {}", node_id, message),
+            EngineError::InvalidSave(msg) => write!(f, "Invalid save: {}", msg),
+            EngineError::NodeLoop(id) => write!(
+                f,
+                "Node '{}' is part of a loop that never stops for a choice",
+                id
+            ),
+            EngineError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            EngineError::NotInitialized => write!(f, "Engine not initialized"),
+            EngineError::Internal(msg) => write!(f, "Internal engine error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
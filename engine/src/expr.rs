@@ -0,0 +1,772 @@
+use crate::campaign::GameState;
+use crate::monsters::slugify;
+use crate::rules::Ability;
+use crate::vars::Value;
+
+/// A condition or calculation written by a campaign author, evaluated against
+/// the game state. The language is small and can only read state:
+///
+/// - literals: `3`, `"rope"`, `true`, `false`
+/// - story variables: `gold` or `var.gold` for the value, `flag.spared_goblin`
+///   for whether it's true. Unset variables read as 0.
+/// - the hero: `character.level`, `character.name`, `character.hp`,
+///   `character.max_hp`, `character.gold`, `hp_pct`, `abilities.str`, ...
+/// - `monster_count`: monsters still standing in the current fight
+/// - functions: `has_item("torch")`, `item_count("torch")`,
+///   `has_condition("poisoned")`, `knows_spell("fire_bolt")`,
+///   `has_feature("Second Wind")`
+/// - operators, loosest first: `or`/`||`, `and`/`&&`, `not`, comparisons
+///   (`==`, `!=`, `<`, `<=`, `>`, `>=`), `+ -`, `* / %`, unary `!` and `-`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Lit(Value),
+    Read(Accessor),
+    Call(Function, Box<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+/// A piece of state an expression can read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Accessor {
+    /// A story variable's value.
+    Var(String),
+    /// Whether a story variable is true.
+    Flag(String),
+    Character(CharacterField),
+    Ability(Ability),
+    /// Current hit points as a percentage of the maximum.
+    HpPct,
+    /// Living monsters in the current encounter, 0 outside a fight.
+    MonsterCount,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharacterField {
+    Name,
+    Class,
+    Race,
+    Background,
+    Level,
+    Xp,
+    Hp,
+    MaxHp,
+    Ac,
+    ProficiencyBonus,
+    Speed,
+    Gold,
+}
+
+impl CharacterField {
+    const ALL: [(&'static str, CharacterField); 12] = [
+        ("name", CharacterField::Name),
+        ("class", CharacterField::Class),
+        ("race", CharacterField::Race),
+        ("background", CharacterField::Background),
+        ("level", CharacterField::Level),
+        ("xp", CharacterField::Xp),
+        ("hp", CharacterField::Hp),
+        ("max_hp", CharacterField::MaxHp),
+        ("ac", CharacterField::Ac),
+        ("proficiency_bonus", CharacterField::ProficiencyBonus),
+        ("speed", CharacterField::Speed),
+        ("gold", CharacterField::Gold),
+    ];
+}
+
+/// Functions take one string argument. All but `item_count` answer yes or no.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Function {
+    HasItem,
+    ItemCount,
+    HasCondition,
+    KnowsSpell,
+    HasFeature,
+}
+
+impl Function {
+    const ALL: [(&'static str, Function); 5] = [
+        ("has_item", Function::HasItem),
+        ("item_count", Function::ItemCount),
+        ("has_condition", Function::HasCondition),
+        ("knows_spell", Function::KnowsSpell),
+        ("has_feature", Function::HasFeature),
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Or => "or",
+            BinOp::And => "and",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Int(i64),
+    Str(String),
+    Ident(String),
+    /// Operators and punctuation: "(", ">=", "&&", ...
+    Sym(&'static str),
+}
+
+/// Longest first, so ">=" isn't read as ">".
+const SYMBOLS: [&str; 19] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")", ",", ".",
+    "=",
+];
+
+/// Split `src` into tokens, each with the column it starts at.
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = text
+                .parse()
+                .map_err(|_| format!("Number {} is too large", text))?;
+            tokens.push((start, Token::Int(n)));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Ident(chars[start..i].iter().collect())));
+        } else if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(format!("Unterminated string at column {}", start + 1)),
+                    Some('"') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        text.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&c) => {
+                        text.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((start, Token::Str(text)));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let sym = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(*s))
+                .ok_or_else(|| format!("Unexpected '{}' at column {}", c, start + 1))?;
+            if *sym == "=" {
+                return Err(format!("Use '==' to compare, at column {}", start + 1));
+            }
+            i += sym.len();
+            tokens.push((start, Token::Sym(sym)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// How deeply parentheses, operators and calls may nest. Parsing and
+/// evaluating both recurse, so without a limit a long enough expression would
+/// overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// Column reported for errors at the end of the input.
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(c, _)| *c) + 1
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T, String> {
+        match self.peek() {
+            None => Err(format!("Expected {} at the end", expected)),
+            Some(_) => Err(format!("Expected {} at column {}", expected, self.column())),
+        }
+    }
+
+    /// Consume the symbol or keyword if it's next.
+    fn eat(&mut self, word: &str) -> bool {
+        let found = match self.peek() {
+            Some(Token::Sym(s)) => *s == word,
+            Some(Token::Ident(s)) => s == word,
+            _ => false,
+        };
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Go one level deeper, failing past `MAX_DEPTH`.
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!(
+                "Expression nested more than {} deep at column {}",
+                MAX_DEPTH,
+                self.column()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse with `f` one level deeper.
+    fn nested(&mut self, f: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        self.enter()?;
+        let expr = f(self)?;
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Ident(s)) => {
+                let s = s.clone();
+                self.pos += 1;
+                Ok(s)
+            }
+            _ => self.error("a name"),
+        }
+    }
+
+    // Each operator in a chain such as `a + b + c` nests the expression one
+    // level deeper, so the chains count towards `MAX_DEPTH` too.
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut left = self.and()?;
+        while self.eat("||") || self.eat("or") {
+            self.enter()?;
+            left = Expr::Binary(BinOp::Or, Box::new(left), Box::new(self.and()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut left = self.not()?;
+        while self.eat("&&") || self.eat("and") {
+            self.enter()?;
+            left = Expr::Binary(BinOp::And, Box::new(left), Box::new(self.not()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.eat("not") {
+            return Ok(Expr::Not(Box::new(self.nested(Self::not)?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.sum()?;
+        let op = match self.peek() {
+            Some(Token::Sym("==")) => BinOp::Eq,
+            Some(Token::Sym("!=")) => BinOp::Ne,
+            Some(Token::Sym("<")) => BinOp::Lt,
+            Some(Token::Sym("<=")) => BinOp::Le,
+            Some(Token::Sym(">")) => BinOp::Gt,
+            Some(Token::Sym(">=")) => BinOp::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Expr::Binary(op, Box::new(left), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut left = self.product()?;
+        loop {
+            let op = if self.eat("+") {
+                BinOp::Add
+            } else if self.eat("-") {
+                BinOp::Sub
+            } else {
+                self.depth = depth;
+                return Ok(left);
+            };
+            self.enter()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat("*") {
+                BinOp::Mul
+            } else if self.eat("/") {
+                BinOp::Div
+            } else if self.eat("%") {
+                BinOp::Rem
+            } else {
+                self.depth = depth;
+                return Ok(left);
+            };
+            self.enter()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.nested(Self::unary)?)));
+        }
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.nested(Self::unary)?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let column = self.column();
+        let token = match self.peek() {
+            Some(t) => t.clone(),
+            None => return self.error("a value"),
+        };
+        self.pos += 1;
+        match token {
+            Token::Int(n) => Ok(Expr::Lit(Value::Int(n))),
+            Token::Str(s) => Ok(Expr::Lit(Value::Str(s))),
+            Token::Sym("(") => {
+                let inner = self.nested(Self::or)?;
+                if !self.eat(")") {
+                    return self.error("')'");
+                }
+                Ok(inner)
+            }
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Lit(Value::Bool(true))),
+                "false" => Ok(Expr::Lit(Value::Bool(false))),
+                _ if self.eat("(") => self.call(&name, column),
+                _ if self.eat(".") => {
+                    let field = self.ident()?;
+                    accessor(&name, Some(&field), column).map(Expr::Read)
+                }
+                _ => accessor(&name, None, column).map(Expr::Read),
+            },
+            Token::Sym(s) => Err(format!("Unexpected '{}' at column {}", s, column)),
+        }
+    }
+
+    /// The rest of `name(...)`, after the opening parenthesis.
+    fn call(&mut self, name: &str, column: usize) -> Result<Expr, String> {
+        let &(_, function) = Function::ALL
+            .iter()
+            .find(|(n, _)| *n == name)
+            .ok_or_else(|| format!("Unknown function '{}' at column {}", name, column))?;
+        let arg = self.nested(Self::or)?;
+        if !self.eat(")") {
+            return self.error(&format!("')' closing {}(", name));
+        }
+        Ok(Expr::Call(function, Box::new(arg)))
+    }
+}
+
+/// Resolve `name` or `name.field` to something readable.
+fn accessor(name: &str, field: Option<&str>, column: usize) -> Result<Accessor, String> {
+    let unknown = || {
+        let path = match field {
+            Some(f) => format!("{}.{}", name, f),
+            None => name.to_string(),
+        };
+        format!("Unknown name '{}' at column {}", path, column)
+    };
+    let Some(field) = field else {
+        return Ok(match name {
+            "hp_pct" => Accessor::HpPct,
+            "monster_count" => Accessor::MonsterCount,
+            "character" | "abilities" | "var" | "flag" => return Err(unknown()),
+            _ => Accessor::Var(name.to_string()),
+        });
+    };
+    match name {
+        "var" => Ok(Accessor::Var(field.to_string())),
+        "flag" => Ok(Accessor::Flag(field.to_string())),
+        "character" => CharacterField::ALL
+            .iter()
+            .find(|(n, _)| *n == field)
+            .map(|&(_, f)| Accessor::Character(f))
+            .ok_or_else(unknown),
+        "abilities" => Ability::ALL
+            .iter()
+            .find(|a| a.short() == field)
+            .map(|&a| Accessor::Ability(a))
+            .ok_or_else(unknown),
+        _ => Err(unknown()),
+    }
+}
+
+impl Expr {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+            end: src.chars().count(),
+            depth: 0,
+        };
+        let expr = parser.or()?;
+        if parser.peek().is_some() {
+            return parser.error("an operator");
+        }
+        Ok(expr)
+    }
+
+    /// Story variables the expression reads.
+    pub fn vars(&self) -> Vec<&str> {
+        match self {
+            Expr::Read(Accessor::Var(v) | Accessor::Flag(v)) => vec![v.as_str()],
+            Expr::Lit(_) | Expr::Read(_) => Vec::new(),
+            Expr::Call(_, e) | Expr::Not(e) | Expr::Neg(e) => e.vars(),
+            Expr::Binary(_, l, r) => {
+                let mut vars = l.vars();
+                vars.extend(r.vars());
+                vars
+            }
+        }
+    }
+
+    pub fn eval(&self, state: &GameState) -> Result<Value, String> {
+        match self {
+            Expr::Lit(v) => Ok(v.clone()),
+            Expr::Read(a) => Ok(read(a, state)),
+            Expr::Call(f, arg) => {
+                let Value::Str(arg) = arg.eval(state)? else {
+                    return Err("Functions take a string".to_string());
+                };
+                Ok(call(*f, &arg, state))
+            }
+            Expr::Not(e) => Ok(Value::Bool(!e.eval(state)?.truthy())),
+            Expr::Neg(e) => match e.eval(state)? {
+                Value::Int(n) => n
+                    .checked_neg()
+                    .map(Value::Int)
+                    .ok_or_else(|| "Integer overflow".to_string()),
+                v => Err(format!("Can't negate '{}'", v)),
+            },
+            Expr::Binary(BinOp::And, l, r) => Ok(Value::Bool(
+                l.eval(state)?.truthy() && r.eval(state)?.truthy(),
+            )),
+            Expr::Binary(BinOp::Or, l, r) => Ok(Value::Bool(
+                l.eval(state)?.truthy() || r.eval(state)?.truthy(),
+            )),
+            Expr::Binary(op, l, r) => binary(*op, l.eval(state)?, r.eval(state)?),
+        }
+    }
+
+    /// Whether the expression holds. One that can't be evaluated doesn't.
+    pub fn test(&self, state: &GameState) -> bool {
+        self.eval(state).is_ok_and(|v| v.truthy())
+    }
+}
+
+fn read(accessor: &Accessor, state: &GameState) -> Value {
+    let hero = &state.character;
+    let text = |s: &Option<String>| Value::Str(s.clone().unwrap_or_default());
+    match accessor {
+        Accessor::Var(name) => state.vars.get(name).cloned().unwrap_or(Value::Int(0)),
+        Accessor::Flag(name) => Value::Bool(state.vars.get(name).is_some_and(Value::truthy)),
+        Accessor::Ability(a) => Value::Int(hero.abilities.score(*a) as i64),
+        Accessor::HpPct => {
+            Value::Int(hero.current_hp.max(0) as i64 * 100 / hero.max_hp.max(1) as i64)
+        }
+        Accessor::MonsterCount => Value::Int(state.encounter.as_ref().map_or(0, |e| {
+            e.monsters.iter().filter(|m| m.is_alive()).count() as i64
+        })),
+        Accessor::Character(field) => match field {
+            CharacterField::Name => Value::Str(hero.name.clone()),
+            CharacterField::Class => text(&hero.class),
+            CharacterField::Race => text(&hero.race),
+            CharacterField::Background => text(&hero.background),
+            CharacterField::Level => Value::Int(hero.level as i64),
+            CharacterField::Xp => Value::Int(hero.xp as i64),
+            CharacterField::Hp => Value::Int(hero.current_hp as i64),
+            CharacterField::MaxHp => Value::Int(hero.max_hp as i64),
+            CharacterField::Ac => Value::Int(hero.armor_class() as i64),
+            CharacterField::ProficiencyBonus => Value::Int(hero.proficiency_bonus as i64),
+            CharacterField::Speed => Value::Int(hero.speed() as i64),
+            CharacterField::Gold => Value::Int(hero.gold as i64),
+        },
+    }
+}
+
+fn call(function: Function, arg: &str, state: &GameState) -> Value {
+    let hero = &state.character;
+    Value::Bool(match function {
+        Function::HasItem => hero.has_item(arg),
+        Function::ItemCount => return Value::Int(hero.item_count(arg) as i64),
+        Function::HasCondition => hero
+            .conditions
+            .iter()
+            .any(|c| c.condition.slug() == slugify(arg)),
+        Function::KnowsSpell => hero
+            .known_spells()
+            .iter()
+            .any(|s| slugify(&s.name) == slugify(arg)),
+        Function::HasFeature => hero
+            .features
+            .iter()
+            .map(String::as_str)
+            .chain(hero.traits())
+            .any(|f| slugify(f) == slugify(arg)),
+    })
+}
+
+fn binary(op: BinOp, l: Value, r: Value) -> Result<Value, String> {
+    let (a, b) = match (op, &l, &r) {
+        (BinOp::Eq, _, _) => return Ok(Value::Bool(l == r)),
+        (BinOp::Ne, _, _) => return Ok(Value::Bool(l != r)),
+        (BinOp::Add, Value::Str(a), Value::Str(b)) => return Ok(Value::Str(format!("{}{}", a, b))),
+        (_, Value::Int(a), Value::Int(b)) => (*a, *b),
+        _ => return Err(format!("Can't work out '{}' {} '{}'", l, op.symbol(), r)),
+    };
+    let n = match op {
+        BinOp::Lt => return Ok(Value::Bool(a < b)),
+        BinOp::Le => return Ok(Value::Bool(a <= b)),
+        BinOp::Gt => return Ok(Value::Bool(a > b)),
+        BinOp::Ge => return Ok(Value::Bool(a >= b)),
+        BinOp::Add => a.checked_add(b),
+        BinOp::Sub => a.checked_sub(b),
+        BinOp::Mul => a.checked_mul(b),
+        BinOp::Div | BinOp::Rem if b == 0 => return Err("Division by zero".to_string()),
+        BinOp::Div => a.checked_div(b),
+        BinOp::Rem => a.checked_rem(b),
+        BinOp::Or | BinOp::And | BinOp::Eq | BinOp::Ne => unreachable!("handled above"),
+    };
+    n.map(Value::Int)
+        .ok_or_else(|| "Integer overflow".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Character, ItemStack};
+    use crate::vars::Vars;
+
+    fn state() -> GameState {
+        GameState {
+            character: Character::from_json(include_str!(
+                "../../content/campaigns/pregen_fighter.json"
+            ))
+            .unwrap(),
+            current_node_id: "start".to_string(),
+            encounter: None,
+            last_log: None,
+            short_rest: None,
+            level_up: None,
+            vars: Vars::default(),
+        }
+    }
+
+    fn eval(src: &str) -> Result<Value, String> {
+        Expr::parse(src)?.eval(&state())
+    }
+
+    #[test]
+    fn arithmetic_and_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(Value::Int(7)));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(Value::Int(9)));
+        assert_eq!(eval("-7 / 2"), Ok(Value::Int(-3)));
+        assert_eq!(eval("-7 % 2"), Ok(Value::Int(-1)));
+        assert_eq!(eval("not 1 > 2 and 3 >= 3"), Ok(Value::Bool(true)));
+        assert_eq!(eval("\"a\" + \"b\" == \"ab\""), Ok(Value::Bool(true)));
+        assert_eq!(eval("unset_var + 1"), Ok(Value::Int(1)));
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(eval("1 +"), Err("Expected a value at the end".to_string()));
+        assert_eq!(eval("(1"), Err("Expected ')' at the end".to_string()));
+        assert_eq!(
+            eval("1 2"),
+            Err("Expected an operator at column 3".to_string())
+        );
+        assert_eq!(
+            eval("gold = 1"),
+            Err("Use '==' to compare, at column 6".to_string())
+        );
+        assert_eq!(
+            eval("\"rope"),
+            Err("Unterminated string at column 1".to_string())
+        );
+        assert_eq!(eval("1 $ 2"), Err("Unexpected '$' at column 3".to_string()));
+        assert_eq!(
+            eval("fly(1)"),
+            Err("Unknown function 'fly' at column 1".to_string())
+        );
+        assert_eq!(
+            eval("character.wings"),
+            Err("Unknown name 'character.wings' at column 1".to_string())
+        );
+        assert_eq!(eval("-\"rope\""), Err("Can't negate 'rope'".to_string()));
+        assert!(eval("1 < \"rope\"").is_err());
+    }
+
+    #[test]
+    fn huge_numbers() {
+        assert_eq!(eval("9223372036854775807"), Ok(Value::Int(i64::MAX)));
+        assert_eq!(
+            eval("99999999999999999999"),
+            Err("Number 99999999999999999999 is too large".to_string())
+        );
+        let overflow = Err("Integer overflow".to_string());
+        assert_eq!(eval("9223372036854775807 + 1"), overflow);
+        assert_eq!(eval("-9223372036854775807 - 2"), overflow);
+        assert_eq!(eval("9223372036854775807 * 2"), overflow);
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let too_deep = [
+            format!("{}1{}", "(".repeat(5000), ")".repeat(5000)),
+            format!("{}true", "not ".repeat(5000)),
+            format!("{}true", "!".repeat(5000)),
+            format!("{}1", "-".repeat(5000)),
+            format!("{}1{}", "has_item(".repeat(5000), ")".repeat(5000)),
+            vec!["1"; 5000].join(" + "),
+            vec!["true"; 5000].join(" and "),
+        ];
+        for src in &too_deep {
+            let err = Expr::parse(src).unwrap_err();
+            assert!(
+                err.starts_with("Expression nested more than 64 deep"),
+                "{}",
+                err
+            );
+        }
+        let ok = format!("{}1{}", "(".repeat(60), ")".repeat(60));
+        assert_eq!(eval(&ok), Ok(Value::Int(1)));
+        assert_eq!(eval(&vec!["1"; 60].join(" + ")), Ok(Value::Int(60)));
+        assert_eq!(
+            eval(&vec!["(1 + 1)"; 30].join(" * ")),
+            Ok(Value::Int(1 << 30))
+        );
+    }
+
+    #[test]
+    fn i64_min_arithmetic() {
+        let min = "(-9223372036854775807 - 1)";
+        assert_eq!(eval(min), Ok(Value::Int(i64::MIN)));
+        let overflow = Err("Integer overflow".to_string());
+        assert_eq!(eval(&format!("{} / -1", min)), overflow);
+        assert_eq!(eval(&format!("{} % -1", min)), overflow);
+        assert_eq!(eval(&format!("-{}", min)), overflow);
+        assert_eq!(eval(&format!("{} * -1", min)), overflow);
+        assert_eq!(eval(&format!("{} / 1", min)), Ok(Value::Int(i64::MIN)));
+        assert_eq!(
+            eval(&format!("{} % 0", min)),
+            Err("Division by zero".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_the_hero_and_the_story() {
+        let mut state = state();
+        state.character.current_hp = 9;
+        state.character.gold = 15;
+        state.character.items.push(ItemStack {
+            item: "torch".to_string(),
+            quantity: 3,
+        });
+        state.vars.set("spared_goblin", Value::Bool(true));
+        state.vars.set("wolves", Value::Int(2));
+        let eval = |src: &str| Expr::parse(src).unwrap().eval(&state).unwrap();
+
+        assert_eq!(
+            eval("character.name"),
+            Value::Str("Kara the Bold".to_string())
+        );
+        assert_eq!(eval("character.class"), Value::Str("Fighter".to_string()));
+        assert_eq!(eval("character.race"), Value::Str(String::new()));
+        // Chain mail, and no shield with a two-handed grip.
+        assert_eq!(eval("character.ac"), Value::Int(16));
+        assert_eq!(eval("abilities.str + character.speed"), Value::Int(46));
+        assert_eq!(eval("hp_pct"), Value::Int(75));
+        assert_eq!(eval("monster_count"), Value::Int(0));
+        assert_eq!(
+            eval("flag.spared_goblin and var.wolves == 2 and not flag.wolves_fled"),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval("has_item(\"torch\") and item_count(\"torch\") == 3"),
+            Value::Bool(true)
+        );
+        assert_eq!(eval("character.gold >= 100"), Value::Bool(false));
+        assert_eq!(eval("knows_spell(\"Fire Bolt\")"), Value::Bool(false));
+        assert_eq!(eval("has_condition(\"poisoned\")"), Value::Bool(false));
+        assert_eq!(eval("has_feature(\"Rage\")"), Value::Bool(false));
+    }
+
+    #[test]
+    fn requirements_that_cant_be_evaluated_fail() {
+        let state = state();
+        let expr = Expr::parse("has_item(3) or flag.rope").unwrap();
+        assert_eq!(
+            expr.eval(&state),
+            Err("Functions take a string".to_string())
+        );
+        assert!(!expr.test(&state));
+        assert_eq!(expr.vars(), ["rope"]);
+        assert_eq!(
+            Expr::parse("gold + var.silver > flag.rich").unwrap().vars(),
+            ["gold", "silver", "rich"]
+        );
+    }
+}
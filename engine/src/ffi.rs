@@ -1,22 +1,111 @@
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
-use std::sync::Mutex;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
 
 use lazy_static::lazy_static;
 
+use crate::builder::CharacterBuilder;
 use crate::campaign::Campaign;
-use crate::campaign::NodeView;
 use crate::engine::Engine;
+use crate::error::EngineError;
 use crate::rules::Character;
 
+/// Status code returned by the C entry points on success. Failures return
+/// `EngineError::code()` and leave a message for `engine_last_error`.
+pub const ENGINE_OK: i32 = 0;
+
 lazy_static! {
     static ref ENGINE: Mutex<Option<Engine>> = Mutex::new(None);
+    static ref BUILDER: Mutex<Option<CharacterBuilder>> = Mutex::new(None);
+    static ref LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Lock `mutex` even if an earlier call panicked while holding it, so one
+/// caught panic doesn't fail every later call.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn set_last_error(err: Option<&EngineError>) {
+    *lock(&LAST_ERROR) = err.map(|e| e.to_string());
+}
+
+/// Run `f`, turning a panic into `EngineError::Internal` so it never unwinds
+/// into the caller. Used by both the C and JNI entry points.
+pub fn catch_panic<T>(f: impl FnOnce() -> Result<T, EngineError>) -> Result<T, EngineError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic".to_string());
+        Err(EngineError::Internal(msg))
+    })
+}
+
+/// Run `f`, turning a panic into an error so it never unwinds across `extern "C"`.
+fn guarded<T>(f: impl FnOnce() -> Result<T, EngineError>) -> Result<T, EngineError> {
+    let result = catch_panic(f);
+    set_last_error(result.as_ref().err());
+    result
+}
+
+fn status(result: Result<(), EngineError>) -> i32 {
+    match result {
+        Ok(()) => ENGINE_OK,
+        Err(e) => e.code(),
+    }
+}
+
+fn into_c_string(result: Result<String, EngineError>) -> *mut c_char {
+    match result.and_then(|s| {
+        CString::new(s).map_err(|e| EngineError::Internal(format!("Interior NUL in output: {e}")))
+    }) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            set_last_error(Some(&e));
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn read_str<'a>(p: *const c_char, name: &str) -> Result<&'a str, EngineError> {
+    if p.is_null() {
+        return Err(EngineError::InvalidInput(format!("{name} is null")));
+    }
+    CStr::from_ptr(p)
+        .to_str()
+        .map_err(|e| EngineError::InvalidInput(format!("{name} is not UTF-8: {e}")))
+}
+
+fn with_engine<T>(f: impl FnOnce(&mut Engine) -> Result<T, EngineError>) -> Result<T, EngineError> {
+    let mut guard = lock(&ENGINE);
+    let engine = guard.as_mut().ok_or(EngineError::NotInitialized)?;
+    f(engine)
+}
+
+fn with_builder<T>(
+    f: impl FnOnce(&mut CharacterBuilder) -> Result<T, EngineError>,
+) -> Result<T, EngineError> {
+    let mut guard = lock(&BUILDER);
+    let builder = guard.as_mut().ok_or(EngineError::NotInitialized)?;
+    f(builder)
+}
+
+fn install(engine: Engine) -> Result<(), EngineError> {
+    let mut guard = lock(&ENGINE);
+    *guard = Some(engine);
+    Ok(())
 }
 
 /// Initialize the engine with a campaign + character JSON and RNG seed.
 ///
+/// Returns `ENGINE_OK` or an error status; see `engine_last_error`.
+///
 /// # Safety
-/// - `campaign_json` and `character_json` must be valid, null-terminated C strings.
+/// - `campaign_json` and `character_json` must be null or valid, null-terminated C strings.
 /// - They must remain valid for the duration of this call.
 /// - This function is intended to be called from JNI/FFI boundaries only.
 #[no_mangle]
@@ -24,16 +113,16 @@ pub unsafe extern "C" fn engine_init(
     campaign_json: *const c_char,
     character_json: *const c_char,
     seed: u64,
-) {
-    let camp_str = CStr::from_ptr(campaign_json).to_str().unwrap();
-    let char_str = CStr::from_ptr(character_json).to_str().unwrap();
+) -> i32 {
+    status(guarded(|| {
+        let camp_str = read_str(campaign_json, "campaign_json")?;
+        let char_str = read_str(character_json, "character_json")?;
 
-    let campaign: Campaign = Campaign::from_json(camp_str).expect("Invalid campaign JSON");
-    let character: Character = Character::from_json(char_str).expect("Invalid character JSON");
+        let campaign = Campaign::from_json(camp_str)?;
+        let character = Character::from_json(char_str)?;
 
-    let engine = Engine::new(campaign, character, seed);
-    let mut guard = ENGINE.lock().unwrap();
-    *guard = Some(engine);
+        install(Engine::new(campaign, character, seed)?)
+    }))
 }
 
 /// Get the current engine view as a newly allocated C string.
@@ -41,33 +130,149 @@ pub unsafe extern "C" fn engine_init(
 /// # Safety
 /// - The returned pointer must later be passed to `engine_free_string`.
 /// - It must not be freed by any other mechanism.
-/// - Returns null if initialization failed.
+/// - Returns null on failure; see `engine_last_error`.
 #[no_mangle]
 pub unsafe extern "C" fn engine_current_view() -> *mut c_char {
-    let guard = ENGINE.lock().unwrap();
-    let engine = guard.as_ref().expect("Engine not initialized");
-    let view: NodeView = engine.current_view();
-    let json = serde_json::to_string(&view).expect("Failed to serialize NodeView");
-    CString::new(json).unwrap().into_raw()
+    into_c_string(guarded(|| {
+        let view = with_engine(|engine| engine.current_view())?;
+        serde_json::to_string(&view)
+            .map_err(|e| EngineError::Internal(format!("Failed to serialize NodeView: {e}")))
+    }))
+}
+
+/// Get the hero's character sheet, inventory included, as a newly allocated C string.
+///
+/// # Safety
+/// - The returned pointer must later be passed to `engine_free_string`.
+/// - Returns null on failure; see `engine_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn engine_character_sheet() -> *mut c_char {
+    into_c_string(guarded(|| {
+        let sheet = with_engine(|engine| Ok(engine.character_sheet()))?;
+        serde_json::to_string(&sheet)
+            .map_err(|e| EngineError::Internal(format!("Failed to serialize CharacterSheet: {e}")))
+    }))
 }
 
 /// Apply the given choice ID to advance the engine.
 ///
+/// Returns `ENGINE_OK` or an error status; see `engine_last_error`.
+///
 /// # Safety
-/// - `choice_id` must be a valid, null-terminated C string.
-/// - Must be called only after `engine_init`.
+/// - `choice_id` must be null or a valid, null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn engine_choose(choice_id: *const c_char) {
-    let choice = CStr::from_ptr(choice_id).to_str().unwrap();
-    let mut guard = ENGINE.lock().unwrap();
-    let engine = guard.as_mut().expect("Engine not initialized");
-    engine.choose(choice);
+pub unsafe extern "C" fn engine_choose(choice_id: *const c_char) -> i32 {
+    status(guarded(|| {
+        let choice = read_str(choice_id, "choice_id")?;
+        with_engine(|engine| engine.choose(choice))
+    }))
+}
+
+/// Serialize the running game to a versioned JSON snapshot.
+///
+/// # Safety
+/// - The returned pointer must later be passed to `engine_free_string`.
+/// - Returns null on failure; see `engine_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn engine_save() -> *mut c_char {
+    into_c_string(guarded(|| with_engine(|engine| engine.save())))
+}
+
+/// Restore the engine from a campaign JSON and a snapshot from `engine_save`.
+///
+/// Returns `ENGINE_OK` or an error status; see `engine_last_error`.
+///
+/// # Safety
+/// - `campaign_json` and `save_json` must be null or valid, null-terminated C strings.
+/// - They must remain valid for the duration of this call.
+#[no_mangle]
+pub unsafe extern "C" fn engine_load(
+    campaign_json: *const c_char,
+    save_json: *const c_char,
+) -> i32 {
+    status(guarded(|| {
+        let camp_str = read_str(campaign_json, "campaign_json")?;
+        let save_str = read_str(save_json, "save_json")?;
+
+        let campaign = Campaign::from_json(camp_str)?;
+        install(Engine::load(campaign, save_str)?)
+    }))
+}
+
+/// Start creating a new character named `name`, rolling dice from `seed`.
+///
+/// Returns `ENGINE_OK` or an error status; see `engine_last_error`.
+///
+/// # Safety
+/// - `name` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn builder_start(name: *const c_char, seed: u64) -> i32 {
+    status(guarded(|| {
+        let name = read_str(name, "name")?;
+        let mut guard = lock(&BUILDER);
+        *guard = Some(CharacterBuilder::new(name, seed));
+        Ok(())
+    }))
+}
+
+/// Get the current character creation screen as a `NodeView` JSON string.
+///
+/// # Safety
+/// - The returned pointer must later be passed to `engine_free_string`.
+/// - Returns null on failure; see `engine_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn builder_current_view() -> *mut c_char {
+    into_c_string(guarded(|| {
+        let view = with_builder(|builder| Ok(builder.view()))?;
+        serde_json::to_string(&view)
+            .map_err(|e| EngineError::Internal(format!("Failed to serialize NodeView: {e}")))
+    }))
+}
+
+/// Apply a choice from the current character creation screen.
+///
+/// Returns `ENGINE_OK` or an error status; see `engine_last_error`.
+///
+/// # Safety
+/// - `choice_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn builder_choose(choice_id: *const c_char) -> i32 {
+    status(guarded(|| {
+        let choice = read_str(choice_id, "choice_id")?;
+        with_builder(|builder| builder.choose(choice))
+    }))
+}
+
+/// The finished character as JSON, to pass to `engine_init`.
+///
+/// # Safety
+/// - The returned pointer must later be passed to `engine_free_string`.
+/// - Returns null on failure, including when creation isn't finished; see
+///   `engine_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn builder_character() -> *mut c_char {
+    into_c_string(guarded(|| with_builder(|builder| builder.finish())))
+}
+
+/// Describe the error from the most recent engine call, as a newly allocated C string.
+///
+/// # Safety
+/// - Returns null if the last call succeeded.
+/// - A non-null result must later be passed to `engine_free_string`.
+#[no_mangle]
+pub unsafe extern "C" fn engine_last_error() -> *mut c_char {
+    let msg = lock(&LAST_ERROR).clone();
+    match msg.and_then(|m| CString::new(m).ok()) {
+        Some(s) => s.into_raw(),
+        None => ptr::null_mut(),
+    }
 }
 
 /// Free a string previously returned by the engine.
 ///
 /// # Safety
-/// - `s` must have been allocated by `engine_current_view`.
+/// - `s` must have been allocated by `engine_current_view`, `engine_save`,
+///   `engine_last_error`, `builder_current_view` or `builder_character`.
 /// - Must not be used after this call.
 #[no_mangle]
 pub unsafe extern "C" fn engine_free_string(s: *mut c_char) {
@@ -76,3 +281,66 @@ pub unsafe extern "C" fn engine_free_string(s: *mut c_char) {
     }
     let _ = CString::from_raw(s);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMPAIGN: &str = include_str!("../../content/campaigns/road_to_redcrest.json");
+    const HERO: &str = include_str!("../../content/campaigns/pregen_fighter.json");
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    fn last_error() -> Option<String> {
+        unsafe {
+            let p = engine_last_error();
+            if p.is_null() {
+                return None;
+            }
+            let msg = CStr::from_ptr(p).to_str().unwrap().to_string();
+            engine_free_string(p);
+            Some(msg)
+        }
+    }
+
+    // One test, since the entry points share the global engine.
+    #[test]
+    fn entry_points_report_errors_as_status_codes() {
+        unsafe {
+            assert!(engine_current_view().is_null());
+            assert_eq!(last_error().as_deref(), Some("Engine not initialized"));
+
+            assert_eq!(engine_choose(ptr::null()), 1);
+            assert_eq!(
+                last_error().as_deref(),
+                Some("Invalid input: choice_id is null")
+            );
+
+            assert_eq!(engine_init(c("{").as_ptr(), c(HERO).as_ptr(), 1), 2);
+            let lost = CAMPAIGN.replace(r#""startNodeId": "intro""#, r#""startNodeId": "lost""#);
+            assert_ne!(lost, CAMPAIGN);
+            assert_eq!(engine_init(c(&lost).as_ptr(), c(HERO).as_ptr(), 1), 3);
+            assert_eq!(last_error().as_deref(), Some("Node not found: lost"));
+
+            assert_eq!(
+                engine_init(c(CAMPAIGN).as_ptr(), c(HERO).as_ptr(), 1),
+                ENGINE_OK
+            );
+            assert_eq!(last_error(), None);
+            let view = engine_current_view();
+            assert!(!view.is_null());
+            engine_free_string(view);
+            assert_eq!(engine_choose(c("nope").as_ptr()), 4);
+        }
+    }
+
+    #[test]
+    fn panics_become_internal_errors() {
+        let result: Result<(), EngineError> = catch_panic(|| panic!("boom"));
+        assert_eq!(result, Err(EngineError::Internal("boom".to_string())));
+        let result: Result<(), EngineError> = catch_panic(|| panic!("{} dice", 2));
+        assert_eq!(result.unwrap_err().code(), 99);
+    }
+}
@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dice::DiceRoller;
+use crate::effects::{Effect, EffectKind};
+use crate::equipment::{srd_equipment, ArmorCategory, ItemKind};
+use crate::monsters::slugify;
+use crate::rules::Character;
+use crate::vars::Vars;
+
+/// An item a campaign defines for its own story: a key, a letter, a salve.
+///
+/// ```json
+/// {"id": "troll_salve", "name": "Troll salve", "weight": 0.5,
+///  "on_use": [{"type": "heal", "dice": "3d4"}]}
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ItemDef {
+    /// What effects, `has_item` and inventories call it. Shadows an SRD item
+    /// with the same slug.
+    pub id: String,
+    pub name: String,
+    /// In pounds.
+    #[serde(default)]
+    pub weight: f32,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Effects applied when the hero uses the item, from a narrative node or
+    /// as their action in a fight. Items without any can't be used.
+    #[serde(default)]
+    pub on_use: Vec<Effect>,
+    /// Kept after use instead of used up.
+    #[serde(default)]
+    pub reusable: bool,
+}
+
+/// Where an item is worn or held.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Slot {
+    Weapon,
+    Armor,
+    Shield,
+}
+
+/// What the engine knows about an item, from the campaign's items or the SRD.
+#[derive(Clone, Debug)]
+pub struct ItemInfo {
+    pub id: String,
+    pub name: String,
+    pub weight: f32,
+    pub description: Option<String>,
+    pub slot: Option<Slot>,
+    pub on_use: Vec<Effect>,
+    /// Used up when used.
+    pub consumable: bool,
+}
+
+impl ItemInfo {
+    pub fn usable(&self) -> bool {
+        !self.on_use.is_empty()
+    }
+
+    /// Whether using it would only heal.
+    pub fn heals_only(&self) -> bool {
+        self.on_use
+            .iter()
+            .all(|e| matches!(e.kind, EffectKind::Heal { .. }))
+    }
+}
+
+/// Look an item up by id, name or slug: the campaign's own items first, then
+/// SRD equipment.
+pub fn item_info(item: &str, campaign_items: &[ItemDef]) -> Option<ItemInfo> {
+    let slug = slugify(item);
+    if let Some(def) = campaign_items.iter().find(|d| slugify(&d.id) == slug) {
+        return Some(ItemInfo {
+            id: slug,
+            name: def.name.clone(),
+            weight: def.weight,
+            description: def.description.clone(),
+            slot: None,
+            on_use: def.on_use.clone(),
+            consumable: !def.reusable,
+        });
+    }
+    let srd = srd_equipment().get(&slug)?;
+    let slot = match &srd.kind {
+        ItemKind::Weapon(_) => Some(Slot::Weapon),
+        ItemKind::Armor(a) if a.category == ArmorCategory::Shield => Some(Slot::Shield),
+        ItemKind::Armor(_) => Some(Slot::Armor),
+        ItemKind::Gear { .. } => None,
+    };
+    let on_use: Vec<Effect> = srd
+        .healing
+        .iter()
+        .map(|dice| Effect {
+            kind: EffectKind::Heal {
+                dice: dice.to_string(),
+            },
+            description: None,
+        })
+        .collect();
+    Some(ItemInfo {
+        id: slug,
+        name: srd.name.clone(),
+        weight: srd.weight,
+        description: srd.desc.first().cloned(),
+        slot,
+        consumable: !on_use.is_empty(),
+        on_use,
+    })
+}
+
+/// One line of the inventory.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InventoryEntry {
+    pub item: String,
+    pub name: String,
+    pub quantity: u32,
+    /// Weight of the whole stack, in pounds.
+    pub weight: f32,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub slot: Option<Slot>,
+    #[serde(default)]
+    pub equipped: bool,
+    /// Can be used with a `use:<item>` choice.
+    #[serde(default)]
+    pub usable: bool,
+}
+
+/// Everything the hero carries, equipped gear first. Equipped items that
+/// aren't in `items` still count, one of each.
+pub fn inventory(hero: &Character, campaign_items: &[ItemDef]) -> Vec<InventoryEntry> {
+    let eq = &hero.equipment;
+    let equipped: Vec<String> = eq
+        .weapon
+        .iter()
+        .chain(&eq.armor)
+        .cloned()
+        .chain(eq.shield.then(|| "shield".to_string()))
+        .map(|i| slugify(&i))
+        .collect();
+    let mut stacks: Vec<(String, u32)> = equipped
+        .iter()
+        .map(|i| (i.clone(), hero.item_count(i).max(1)))
+        .collect();
+    for stack in &hero.items {
+        let slug = slugify(&stack.item);
+        if !stacks.iter().any(|(i, _)| *i == slug) {
+            stacks.push((slug, hero.item_count(&stack.item)));
+        }
+    }
+    stacks
+        .into_iter()
+        .map(|(item, quantity)| {
+            let info = item_info(&item, campaign_items);
+            InventoryEntry {
+                name: info
+                    .as_ref()
+                    .map_or_else(|| item.clone(), |i| i.name.clone()),
+                weight: info.as_ref().map_or(0.0, |i| i.weight) * quantity as f32,
+                description: info.as_ref().and_then(|i| i.description.clone()),
+                slot: info.as_ref().and_then(|i| i.slot),
+                usable: info.as_ref().is_some_and(ItemInfo::usable),
+                equipped: equipped.contains(&item),
+                item,
+                quantity,
+            }
+        })
+        .collect()
+}
+
+/// Use one of `item`: apply its effects and use it up unless it's reusable.
+pub fn use_item(
+    item: &str,
+    hero: &mut Character,
+    vars: &mut Vars,
+    campaign_items: &[ItemDef],
+    dice: &mut DiceRoller,
+    log_lines: &mut Vec<String>,
+) -> Result<(), String> {
+    let info =
+        item_info(item, campaign_items).ok_or_else(|| format!("Unknown item '{}'.", item))?;
+    if hero.item_count(&info.id) == 0 {
+        return Err(format!("You have no {}.", info.name));
+    }
+    if !info.usable() {
+        return Err(format!("{} can't be used.", info.name));
+    }
+    log_lines.push(format!("You use the {}.", info.name));
+    if info.consumable {
+        hero.take_item(&info.id, 1);
+    }
+    for effect in &info.on_use {
+        effect.apply(hero, vars, campaign_items, dice, log_lines);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hero() -> Character {
+        Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json")).unwrap()
+    }
+
+    fn salve(reusable: bool) -> ItemDef {
+        serde_json::from_value(serde_json::json!({
+            "id": "troll_salve", "name": "Troll salve", "weight": 0.5,
+            "on_use": [{"type": "heal", "dice": "3"}], "reusable": reusable
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn potions_of_healing_heal_2d4_plus_2_and_are_used_up() {
+        let potion = item_info("Potion of healing", &[]).unwrap();
+        assert_eq!(potion.id, "potion_of_healing");
+        assert!(potion.usable() && potion.heals_only() && potion.consumable);
+        assert_eq!(potion.slot, None);
+
+        let mut hero = hero();
+        hero.give_item("potion_of_healing", 2);
+        hero.current_hp = 1;
+        let mut log = Vec::new();
+        use_item(
+            "potion_of_healing",
+            &mut hero,
+            &mut Vars::default(),
+            &[],
+            &mut DiceRoller::new(4),
+            &mut log,
+        )
+        .unwrap();
+        assert_eq!(log[0], "You use the Potion of healing.");
+        assert!(log[1].starts_with("Healing 2d4+2"), "{}", log[1]);
+        assert!((5..=11).contains(&hero.current_hp));
+        assert_eq!(hero.item_count("potion_of_healing"), 1);
+    }
+
+    #[test]
+    fn only_carried_usable_items_can_be_used() {
+        let mut hero = hero();
+        let use_one = |item: &str, hero: &mut Character, items: &[ItemDef]| {
+            use_item(
+                item,
+                hero,
+                &mut Vars::default(),
+                items,
+                &mut DiceRoller::new(1),
+                &mut Vec::new(),
+            )
+        };
+        assert_eq!(
+            use_one("vorpal_spoon", &mut hero, &[]),
+            Err("Unknown item 'vorpal_spoon'.".to_string())
+        );
+        assert_eq!(
+            use_one("potion_of_healing", &mut hero, &[]),
+            Err("You have no Potion of healing.".to_string())
+        );
+        hero.give_item("torch", 1);
+        assert_eq!(
+            use_one("torch", &mut hero, &[]),
+            Err("Torch can't be used.".to_string())
+        );
+
+        // Reusable campaign items are kept.
+        hero.give_item("troll_salve", 1);
+        hero.current_hp = 5;
+        use_one("troll_salve", &mut hero, &[salve(true)]).unwrap();
+        assert_eq!((hero.current_hp, hero.item_count("troll_salve")), (8, 1));
+        use_one("troll_salve", &mut hero, &[salve(false)]).unwrap();
+        assert_eq!((hero.current_hp, hero.item_count("troll_salve")), (11, 0));
+    }
+
+    #[test]
+    fn the_inventory_lists_equipped_gear_first() {
+        let mut hero = hero();
+        hero.give_item("torch", 3);
+        hero.give_item("troll_salve", 2);
+        let entries = inventory(&hero, &[salve(false)]);
+        let lines: Vec<_> = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.name.as_str(),
+                    e.quantity,
+                    e.weight,
+                    e.slot,
+                    e.equipped,
+                    e.usable,
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("Longsword", 1, 3.0, Some(Slot::Weapon), true, false),
+                ("Chain Mail", 1, 55.0, Some(Slot::Armor), true, false),
+                ("Torch", 3, 3.0, None, false, false),
+                ("Troll salve", 2, 1.0, None, false, true),
+            ]
+        );
+    }
+}
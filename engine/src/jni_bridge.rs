@@ -1,4 +1,4 @@
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
 use std::ptr;
 
 use lazy_static::lazy_static;
@@ -6,55 +6,136 @@ use jni::objects::{JClass, JString};
 use jni::sys::{jlong, jstring};
 use jni::JNIEnv;
 
+use crate::builder::CharacterBuilder;
 use crate::campaign::{Campaign, NodeView};
 use crate::engine::Engine;
+use crate::error::EngineError;
+use crate::ffi::catch_panic;
 use crate::rules::Character;
 
 lazy_static! {
     static ref ENGINE_JNI: Mutex<Option<Engine>> = Mutex::new(None);
+    static ref BUILDER_JNI: Mutex<Option<CharacterBuilder>> = Mutex::new(None);
+}
+
+// A panic caught by `catch_panic` poisons the lock; carry on rather than fail
+// every later call.
+fn lock_engine() -> MutexGuard<'static, Option<Engine>> {
+    ENGINE_JNI.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn lock_builder() -> MutexGuard<'static, Option<CharacterBuilder>> {
+    BUILDER_JNI.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Raise `err` in the JVM; using the engine before init is an `IllegalStateException`.
+fn throw_engine_error(env: &mut JNIEnv, err: EngineError) {
+    let class = match err {
+        EngineError::NotInitialized => "java/lang/IllegalStateException",
+        EngineError::UnknownChoice { .. } => "java/lang/IllegalArgumentException",
+        _ => "java/lang/RuntimeException",
+    };
+    let _ = env.throw_new(class, err.to_string());
 }
 
 fn init_engine_internal(
     campaign_json: &str,
     character_json: &str,
     seed: u64,
-) -> Result<(), String> {
-    let campaign: Campaign = serde_json::from_str(campaign_json)
-        .map_err(|e| format!("Invalid campaign JSON: {e}"))?;
-    let character: Character = serde_json::from_str(character_json)
-        .map_err(|e| format!("Invalid character JSON: {e}"))?;
-
-    let engine = Engine::new(campaign, character, seed);
-
-    let mut guard = ENGINE_JNI
-        .lock()
-        .map_err(|e| format!("Engine mutex poisoned: {e}"))?;
-    *guard = Some(engine);
+) -> Result<(), EngineError> {
+    let campaign = Campaign::from_json(campaign_json)?;
+    let character = Character::from_json(character_json)?;
+
+    let engine = Engine::new(campaign, character, seed)?;
+
+    *lock_engine() = Some(engine);
     Ok(())
 }
 
-fn current_view_internal() -> Result<String, String> {
-    let guard = ENGINE_JNI
-        .lock()
-        .map_err(|e| format!("Engine mutex poisoned: {e}"))?;
-    let engine = guard
-        .as_ref()
-        .ok_or_else(|| "Engine not initialized".to_string())?;
-    let view: NodeView = engine.current_view();
-    serde_json::to_string(&view).map_err(|e| format!("Failed to serialize view: {e}"))
-}
-
-fn choose_internal(choice_id: &str) -> Result<(), String> {
-    let mut guard = ENGINE_JNI
-        .lock()
-        .map_err(|e| format!("Engine mutex poisoned: {e}"))?;
-    let engine = guard
-        .as_mut()
-        .ok_or_else(|| "Engine not initialized".to_string())?;
-    engine.choose(choice_id);
+fn load_engine_internal(campaign_json: &str, save_json: &str) -> Result<(), EngineError> {
+    let campaign = Campaign::from_json(campaign_json)?;
+
+    let engine = Engine::load(campaign, save_json)?;
+
+    *lock_engine() = Some(engine);
+    Ok(())
+}
+
+fn save_internal() -> Result<String, EngineError> {
+    let guard = lock_engine();
+    let engine = guard.as_ref().ok_or(EngineError::NotInitialized)?;
+    engine.save()
+}
+
+fn current_view_internal() -> Result<String, EngineError> {
+    let guard = lock_engine();
+    let engine = guard.as_ref().ok_or(EngineError::NotInitialized)?;
+    let view: NodeView = engine.current_view()?;
+    serde_json::to_string(&view)
+        .map_err(|e| EngineError::Internal(format!("Failed to serialize view: {e}")))
+}
+
+fn character_sheet_internal() -> Result<String, EngineError> {
+    let guard = lock_engine();
+    let engine = guard.as_ref().ok_or(EngineError::NotInitialized)?;
+    serde_json::to_string(&engine.character_sheet())
+        .map_err(|e| EngineError::Internal(format!("Failed to serialize character sheet: {e}")))
+}
+
+fn choose_internal(choice_id: &str) -> Result<(), EngineError> {
+    let mut guard = lock_engine();
+    let engine = guard.as_mut().ok_or(EngineError::NotInitialized)?;
+    engine.choose(choice_id)
+}
+
+fn builder_start_internal(name: &str, seed: u64) -> Result<(), EngineError> {
+    *lock_builder() = Some(CharacterBuilder::new(name, seed));
     Ok(())
 }
 
+fn builder_view_internal() -> Result<String, EngineError> {
+    let guard = lock_builder();
+    let builder = guard.as_ref().ok_or(EngineError::NotInitialized)?;
+    serde_json::to_string(&builder.view())
+        .map_err(|e| EngineError::Internal(format!("Failed to serialize view: {e}")))
+}
+
+fn builder_choose_internal(choice_id: &str) -> Result<(), EngineError> {
+    let mut guard = lock_builder();
+    let builder = guard.as_mut().ok_or(EngineError::NotInitialized)?;
+    builder.choose(choice_id)
+}
+
+fn builder_character_internal() -> Result<String, EngineError> {
+    let guard = lock_builder();
+    let builder = guard.as_ref().ok_or(EngineError::NotInitialized)?;
+    builder.finish()
+}
+
+/// Hand a string result back to the JVM, raising an exception on failure.
+fn return_string(env: &mut JNIEnv, result: Result<String, EngineError>) -> jstring {
+    match result {
+        Ok(json) => match env.new_string(json) {
+            Ok(java_str) => java_str.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new(
+                    "java/lang/RuntimeException",
+                    format!("Failed to create Java string: {e}"),
+                );
+                ptr::null_mut()
+            }
+        },
+        Err(err) => {
+            throw_engine_error(env, err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// JNI: initialize the engine from campaign + character JSON and an RNG seed.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineInit(
     mut env: JNIEnv,
@@ -85,36 +166,156 @@ pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineInit(
         }
     };
 
-    if let Err(err) = init_engine_internal(&camp, &chara, seed as u64) {
-        let _ = env.throw_new("java/lang/RuntimeException", err);
+    if let Err(err) = catch_panic(|| init_engine_internal(&camp, &chara, seed as u64)) {
+        throw_engine_error(&mut env, err);
     }
 }
 
+/// JNI: return the current `NodeView` as a JSON string.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineCurrentView(
     mut env: JNIEnv,
     _cls: JClass,
 ) -> jstring {
-    match current_view_internal() {
-        Ok(json) => match env.new_string(json) {
-            Ok(java_str) => java_str.into_raw(),
-            Err(e) => {
-                let _ = env.throw_new(
-                    "java/lang/RuntimeException",
-                    format!("Failed to create Java string: {e}"),
-                );
-                ptr::null_mut()
-            }
-        },
-        Err(err) => {
-            let _ = env.throw_new("java/lang/IllegalStateException", err);
-            ptr::null_mut()
+    return_string(&mut env, catch_panic(current_view_internal))
+}
+
+/// JNI: return the hero's `CharacterSheet` as a JSON string.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineCharacterSheet(
+    mut env: JNIEnv,
+    _cls: JClass,
+) -> jstring {
+    return_string(&mut env, catch_panic(character_sheet_internal))
+}
+
+/// JNI: apply the given choice ID to advance the engine.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineChoose(
+    mut env: JNIEnv,
+    _cls: JClass,
+    choice_id: JString,
+) {
+    let choice: String = match env.get_string(&choice_id) {
+        Ok(s) => s.into(),
+        Err(e) => {
+            let _ = env.throw_new(
+                "java/lang/RuntimeException",
+                format!("Failed to read choice_id: {e}"),
+            );
+            return;
         }
+    };
+
+    if let Err(err) = catch_panic(|| choose_internal(&choice)) {
+        throw_engine_error(&mut env, err);
     }
 }
 
+/// JNI: return a JSON snapshot of the running game.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
 #[no_mangle]
-pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineChoose(
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineSave(
+    mut env: JNIEnv,
+    _cls: JClass,
+) -> jstring {
+    return_string(&mut env, catch_panic(save_internal))
+}
+
+/// JNI: restore the engine from campaign JSON and a snapshot from `engineSave`.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineLoad(
+    mut env: JNIEnv,
+    _cls: JClass,
+    campaign_json: JString,
+    save_json: JString,
+) {
+    let camp: String = match env.get_string(&campaign_json) {
+        Ok(s) => s.into(),
+        Err(e) => {
+            let _ = env.throw_new(
+                "java/lang/RuntimeException",
+                format!("Failed to read campaign_json: {e}"),
+            );
+            return;
+        }
+    };
+
+    let save: String = match env.get_string(&save_json) {
+        Ok(s) => s.into(),
+        Err(e) => {
+            let _ = env.throw_new(
+                "java/lang/RuntimeException",
+                format!("Failed to read save_json: {e}"),
+            );
+            return;
+        }
+    };
+
+    if let Err(err) = catch_panic(|| load_engine_internal(&camp, &save)) {
+        throw_engine_error(&mut env, err);
+    }
+}
+
+/// JNI: start creating a new character named `name`.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_builderStart(
+    mut env: JNIEnv,
+    _cls: JClass,
+    name: JString,
+    seed: jlong,
+) {
+    let name: String = match env.get_string(&name) {
+        Ok(s) => s.into(),
+        Err(e) => {
+            let _ = env.throw_new(
+                "java/lang/RuntimeException",
+                format!("Failed to read name: {e}"),
+            );
+            return;
+        }
+    };
+
+    if let Err(err) = catch_panic(|| builder_start_internal(&name, seed as u64)) {
+        throw_engine_error(&mut env, err);
+    }
+}
+
+/// JNI: return the current character creation screen as `NodeView` JSON.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_builderCurrentView(
+    mut env: JNIEnv,
+    _cls: JClass,
+) -> jstring {
+    return_string(&mut env, catch_panic(builder_view_internal))
+}
+
+/// JNI: apply a choice from the current character creation screen.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_builderChoose(
     mut env: JNIEnv,
     _cls: JClass,
     choice_id: JString,
@@ -130,7 +331,19 @@ pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_engineChoos
         }
     };
 
-    if let Err(err) = choose_internal(&choice) {
-        let _ = env.throw_new("java/lang/RuntimeException", err);
+    if let Err(err) = catch_panic(|| builder_choose_internal(&choice)) {
+        throw_engine_error(&mut env, err);
     }
 }
+
+/// JNI: return the finished character as JSON, to pass to `engineInit`.
+///
+/// # Safety
+/// Must only be called by the JVM with a valid `JNIEnv` for the current thread.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_example_solodnd_ui_SoloEngine_builderCharacter(
+    mut env: JNIEnv,
+    _cls: JClass,
+) -> jstring {
+    return_string(&mut env, catch_panic(builder_character_internal))
+}
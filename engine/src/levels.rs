@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::dice::DiceRoller;
+use crate::monsters::slugify;
+use crate::rules::{Ability, Character};
+
+/// SRD class level tables, bundled into the library like the monster data.
+const SRD_LEVELS_JSON: &str = include_str!("../../data/json/levels.json");
+
+lazy_static! {
+    static ref SRD_LEVELS: LevelLibrary =
+        LevelLibrary::from_json(SRD_LEVELS_JSON).expect("Bundled levels.json is invalid");
+}
+
+/// The class level tables built from the bundled SRD data.
+pub fn srd_levels() -> &'static LevelLibrary {
+    &SRD_LEVELS
+}
+
+/// Total XP needed for each level, 1 through 20.
+pub const XP_THRESHOLDS: [u32; 20] = [
+    0, 300, 900, 2700, 6500, 14000, 23000, 34000, 48000, 64000, 85000, 100000, 120000, 140000,
+    165000, 195000, 225000, 265000, 305000, 355000,
+];
+
+/// XP for defeating a monster of the given challenge rating.
+pub fn xp_for_cr(cr: f32) -> u32 {
+    const BY_CR: [u32; 31] = [
+        10, 200, 450, 700, 1100, 1800, 2300, 2900, 3900, 5000, 5900, 7200, 8400, 10000, 11500,
+        13000, 15000, 18000, 20000, 22000, 25000, 33000, 41000, 50000, 62000, 75000, 90000, 105000,
+        120000, 135000, 155000,
+    ];
+    match cr {
+        c if c <= 0.0 => 10,
+        c if c <= 0.125 => 25,
+        c if c <= 0.25 => 50,
+        c if c <= 0.5 => 100,
+        c => BY_CR[(c as usize).min(30)],
+    }
+}
+
+/// The level a character with `xp` experience points has earned.
+pub fn level_for_xp(xp: u32) -> u8 {
+    XP_THRESHOLDS.iter().filter(|&&t| xp >= t).count() as u8
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct NamedRef {
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawLevel {
+    level: u8,
+    #[serde(default)]
+    ability_score_bonuses: u8,
+    #[serde(default)]
+    prof_bonus: i32,
+    #[serde(default)]
+    features: Vec<NamedRef>,
+    #[serde(default)]
+    feature_choices: Vec<NamedRef>,
+    class: NamedRef,
+    /// Empty for class rows. Subclass rows only carry the subclass's extra features.
+    #[serde(default)]
+    subclass: Option<RawSubclassRef>,
+    /// Empty for classes that don't cast.
+    #[serde(default)]
+    spellcasting: RawSpellcasting,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+struct RawSpellcasting {
+    #[serde(default)]
+    cantrips_known: u32,
+    #[serde(default)]
+    spells_known: Option<u32>,
+    #[serde(default)]
+    spell_slots_level_1: u32,
+    #[serde(default)]
+    spell_slots_level_2: u32,
+    #[serde(default)]
+    spell_slots_level_3: u32,
+    #[serde(default)]
+    spell_slots_level_4: u32,
+    #[serde(default)]
+    spell_slots_level_5: u32,
+    #[serde(default)]
+    spell_slots_level_6: u32,
+    #[serde(default)]
+    spell_slots_level_7: u32,
+    #[serde(default)]
+    spell_slots_level_8: u32,
+    #[serde(default)]
+    spell_slots_level_9: u32,
+}
+
+impl RawSpellcasting {
+    fn slots(&self) -> [u32; 9] {
+        [
+            self.spell_slots_level_1,
+            self.spell_slots_level_2,
+            self.spell_slots_level_3,
+            self.spell_slots_level_4,
+            self.spell_slots_level_5,
+            self.spell_slots_level_6,
+            self.spell_slots_level_7,
+            self.spell_slots_level_8,
+            self.spell_slots_level_9,
+        ]
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawSubclassRef {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClassLevel {
+    pub level: u8,
+    /// Ability Score Improvements gained so far, counting this level.
+    pub ability_score_bonuses: u8,
+    pub prof_bonus: i32,
+    pub features: Vec<String>,
+    /// Features that need a decision, e.g. "Choose: Fighting Style".
+    pub feature_choices: Vec<String>,
+    /// Slots per spell level, 1st through 9th. All zero for non-casters.
+    pub spell_slots: [u32; 9],
+    pub cantrips_known: u32,
+    /// `None` for classes that prepare spells from their whole list instead.
+    pub spells_known: Option<u32>,
+}
+
+/// Class level rows keyed by class slug: "fighter", "wizard". Subclass rows
+/// are kept apart, keyed by subclass slug: "champion".
+#[derive(Clone, Debug, Default)]
+pub struct LevelLibrary {
+    by_class: HashMap<String, Vec<ClassLevel>>,
+    by_subclass: HashMap<String, Vec<ClassLevel>>,
+}
+
+impl LevelLibrary {
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawLevel> = serde_json::from_str(s)?;
+        let mut by_class: HashMap<String, Vec<ClassLevel>> = HashMap::new();
+        let mut by_subclass: HashMap<String, Vec<ClassLevel>> = HashMap::new();
+        for r in raw {
+            let table = match r.subclass.and_then(|s| s.name) {
+                Some(sub) => by_subclass.entry(slugify(&sub)).or_default(),
+                None => by_class.entry(slugify(&r.class.name)).or_default(),
+            };
+            table.push(ClassLevel {
+                level: r.level,
+                ability_score_bonuses: r.ability_score_bonuses,
+                prof_bonus: r.prof_bonus,
+                features: r.features.into_iter().map(|f| f.name).collect(),
+                feature_choices: r.feature_choices.into_iter().map(|f| f.name).collect(),
+                spell_slots: r.spellcasting.slots(),
+                cantrips_known: r.spellcasting.cantrips_known,
+                spells_known: r.spellcasting.spells_known,
+            });
+        }
+        Ok(Self {
+            by_class,
+            by_subclass,
+        })
+    }
+
+    /// The row for `class` (name or slug) at `level`.
+    pub fn get(&self, class: &str, level: u8) -> Option<&ClassLevel> {
+        self.by_class
+            .get(&slugify(class))?
+            .iter()
+            .find(|l| l.level == level)
+    }
+
+    /// Extra features `subclass` grants at `level`, if any.
+    pub fn subclass_features(&self, subclass: &str, level: u8) -> Vec<String> {
+        self.by_subclass
+            .get(&slugify(subclass))
+            .into_iter()
+            .flatten()
+            .filter(|l| l.level == level)
+            .flat_map(|l| l.features.iter().cloned())
+            .collect()
+    }
+}
+
+/// The row for `level` in the character's class table. Characters without a
+/// class follow the common progression: proficiency and ASIs, no features.
+fn class_level(character: &Character, level: u8) -> ClassLevel {
+    if let Some(row) = character
+        .class
+        .as_deref()
+        .and_then(|c| srd_levels().get(c, level))
+    {
+        return row.clone();
+    }
+    ClassLevel {
+        level,
+        ability_score_bonuses: [4, 8, 12, 16, 19].iter().filter(|&&l| level >= l).count() as u8,
+        prof_bonus: (level as i32 - 1) / 4 + 2,
+        features: Vec::new(),
+        feature_choices: Vec::new(),
+        spell_slots: [0; 9],
+        cantrips_known: 0,
+        spells_known: None,
+    }
+}
+
+/// A level being gained, one decision at a time: hit points first, then any
+/// Ability Score Improvement as two +1 picks.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelUp {
+    pub level: u8,
+    /// Set once the player has rolled or taken the average.
+    #[serde(default)]
+    pub hp_gained: Option<i32>,
+    /// +1 ability increases still to assign.
+    #[serde(default)]
+    pub asi_points: u8,
+}
+
+impl LevelUp {
+    /// A level-up if the character has the XP for their next level.
+    pub fn start(character: &Character) -> Option<Self> {
+        let level = character.level + 1;
+        if level > 20 || level_for_xp(character.xp) < level {
+            return None;
+        }
+        let gains_asi = class_level(character, level).ability_score_bonuses
+            > class_level(character, character.level).ability_score_bonuses;
+        Some(Self {
+            level,
+            hp_gained: None,
+            asi_points: if gains_asi { 2 } else { 0 },
+        })
+    }
+
+    /// Choices for the current step as `(id, label)` pairs.
+    pub fn choices(&self, character: &Character) -> Vec<(String, String)> {
+        if self.hp_gained.is_none() {
+            return vec![
+                (
+                    "level_up_roll_hp".to_string(),
+                    format!(
+                        "Roll for hit points ({})",
+                        character
+                            .hit_die_expr()
+                            .map(|e| e.to_string())
+                            .unwrap_or_else(|_| format!("d{}", character.hit_die()))
+                    ),
+                ),
+                (
+                    "level_up_average_hp".to_string(),
+                    format!("Take the average ({} hit points)", average_hp(character)),
+                ),
+            ];
+        }
+        Ability::ALL
+            .iter()
+            .filter_map(|&ability| {
+                let score = character.abilities.score(ability);
+                (score < 20).then(|| {
+                    (
+                        format!("level_up_asi:{}", ability.short()),
+                        format!(
+                            "+1 {} ({} -> {}), {} of 2",
+                            ability.name(),
+                            score,
+                            score + 1,
+                            3 - self.asi_points
+                        ),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Apply one choice. Returns the log line, or `None` for an unknown choice.
+    pub fn apply(
+        &mut self,
+        choice_id: &str,
+        character: &mut Character,
+        dice: &mut DiceRoller,
+    ) -> Option<String> {
+        if self.hp_gained.is_none() {
+            let (gain, line) = match choice_id {
+                "level_up_roll_hp" => match character.hit_die_expr() {
+                    Ok(expr) => {
+                        let roll = dice.eval(&expr);
+                        let gain = roll.total.max(1) + character.hp_bonus_per_level();
+                        (gain, format!("Hit points {} => +{} max HP.", roll, gain))
+                    }
+                    Err(e) => {
+                        let gain = average_hp(character);
+                        (
+                            gain,
+                            format!("{}. You take the average: +{} max HP.", e, gain),
+                        )
+                    }
+                },
+                "level_up_average_hp" => {
+                    let gain = average_hp(character);
+                    (gain, format!("You take the average: +{} max HP.", gain))
+                }
+                _ => return None,
+            };
+            character.max_hp += gain;
+            character.current_hp += gain;
+            self.hp_gained = Some(gain);
+            return Some(line);
+        }
+
+        let short = choice_id.strip_prefix("level_up_asi:")?;
+        let &ability = Ability::ALL.iter().find(|a| a.short() == short)?;
+        if self.asi_points == 0 || character.abilities.score(ability) >= 20 {
+            return None;
+        }
+        let con_before = character.abilities.modifier(Ability::Constitution);
+        *character.abilities.score_mut(ability) += 1;
+        self.asi_points -= 1;
+        // A higher CON modifier raises max HP retroactively for every level.
+        let con_gain =
+            (character.abilities.modifier(Ability::Constitution) - con_before) * self.level as i32;
+        character.max_hp += con_gain;
+        character.current_hp += con_gain;
+        Some(format!(
+            "{} increases to {}.",
+            ability.name(),
+            character.abilities.score(ability)
+        ))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.hp_gained.is_some() && self.asi_points == 0
+    }
+
+    /// Raise the character's level and record what the class table grants.
+    pub fn finish(&self, character: &mut Character) -> String {
+        let mut row = class_level(character, self.level);
+        if let Some(sub) = &character.subclass {
+            row.features
+                .extend(srd_levels().subclass_features(sub, self.level));
+        }
+        character.level = self.level;
+        character.proficiency_bonus = row.prof_bonus;
+        character.features.extend(row.features.iter().cloned());
+
+        let mut line = format!(
+            "You are now level {}! Proficiency bonus +{}.",
+            self.level, row.prof_bonus
+        );
+        let gained: Vec<&String> = row
+            .features
+            .iter()
+            .chain(&row.feature_choices)
+            .filter(|f| !f.starts_with("Ability Score Improvement"))
+            .collect();
+        if !gained.is_empty() {
+            let names: Vec<&str> = gained.iter().map(|s| s.as_str()).collect();
+            line.push_str(&format!(" New features: {}.", names.join(", ")));
+        }
+        line
+    }
+}
+
+/// The fixed hit point increase: half the hit die plus one, plus CON.
+fn average_hp(character: &Character) -> i32 {
+    (character.hit_die() as i32 / 2 + 1 + character.abilities.modifier(Ability::Constitution))
+        .max(1)
+        + character.hp_bonus_per_level()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hero(xp: u32) -> Character {
+        let mut hero =
+            Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        hero.xp = xp;
+        hero
+    }
+
+    #[test]
+    fn xp_thresholds_and_rewards() {
+        assert_eq!(level_for_xp(0), 1);
+        assert_eq!(level_for_xp(299), 1);
+        assert_eq!(level_for_xp(300), 2);
+        assert_eq!(level_for_xp(6500), 5);
+        assert_eq!(level_for_xp(u32::MAX), 20);
+        assert_eq!(xp_for_cr(0.0), 10);
+        assert_eq!(xp_for_cr(0.25), 50);
+        assert_eq!(xp_for_cr(1.0), 200);
+        assert_eq!(xp_for_cr(30.0), 155000);
+    }
+
+    #[test]
+    fn level_ups_start_at_the_threshold() {
+        assert!(LevelUp::start(&hero(299)).is_none());
+        let level_up = LevelUp::start(&hero(300)).unwrap();
+        assert_eq!((level_up.level, level_up.asi_points), (2, 0));
+
+        let mut hero = hero(2700);
+        hero.level = 3;
+        let level_up = LevelUp::start(&hero).unwrap();
+        assert_eq!((level_up.level, level_up.asi_points), (4, 2));
+        hero.level = 20;
+        hero.xp = u32::MAX;
+        assert!(LevelUp::start(&hero).is_none());
+    }
+
+    #[test]
+    fn average_hit_points_then_class_features() {
+        let mut hero = hero(300);
+        let mut dice = DiceRoller::new(1);
+        let mut level_up = LevelUp::start(&hero).unwrap();
+        let offered: Vec<String> = level_up
+            .choices(&hero)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(offered, ["level_up_roll_hp", "level_up_average_hp"]);
+        assert_eq!(
+            level_up
+                .apply("level_up_average_hp", &mut hero, &mut dice)
+                .as_deref(),
+            Some("You take the average: +8 max HP.")
+        );
+        assert_eq!(hero.max_hp, 20);
+        assert!(level_up.is_done());
+        let line = level_up.finish(&mut hero);
+        assert!(
+            line.starts_with("You are now level 2! Proficiency bonus +2."),
+            "{}",
+            line
+        );
+        assert_eq!(hero.level, 2);
+        assert!(hero.features.iter().any(|f| f == "Action Surge (1 use)"));
+    }
+
+    #[test]
+    fn ability_score_improvements_raise_con_hit_points_retroactively() {
+        let mut hero = hero(2700);
+        hero.level = 3;
+        let mut dice = DiceRoller::new(1);
+        let mut level_up = LevelUp::start(&hero).unwrap();
+        level_up
+            .apply("level_up_roll_hp", &mut hero, &mut dice)
+            .unwrap();
+        assert!(level_up
+            .apply("level_up_asi:bogus", &mut hero, &mut dice)
+            .is_none());
+        let max_hp = hero.max_hp;
+        // CON 14 -> 15 -> 16: the modifier rises once, for all four levels.
+        level_up
+            .apply("level_up_asi:con", &mut hero, &mut dice)
+            .unwrap();
+        assert_eq!(hero.max_hp, max_hp);
+        level_up
+            .apply("level_up_asi:con", &mut hero, &mut dice)
+            .unwrap();
+        assert_eq!(hero.max_hp, max_hp + 4);
+        assert!(level_up.is_done());
+        assert!(level_up
+            .apply("level_up_asi:str", &mut hero, &mut dice)
+            .is_none());
+    }
+}
@@ -1,7 +1,24 @@
+pub mod builder;
 pub mod campaign;
+pub mod classes;
 pub mod combat;
+pub mod conditions;
 pub mod dice;
+pub mod effects;
 pub mod engine;
+pub mod equipment;
+pub mod error;
+pub mod expr;
 pub mod ffi;
+pub mod inventory;
+pub mod levels;
+pub mod monsters;
+pub mod races;
 pub mod rules;
+pub mod sheet;
+pub mod skills;
+pub mod spells;
+pub mod template;
+pub mod validation;
+pub mod vars;
 pub mod jni_bridge; // JNI API for Android
\ No newline at end of file
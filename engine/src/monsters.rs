@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::combat::Monster;
+use crate::conditions::Conditions;
+use crate::dice::DiceExpr;
+use crate::error::EngineError;
+use crate::rules::{ability_from_str, Ability, AbilityScores};
+
+/// SRD monster stat blocks, bundled into the library so Android needs no file access.
+const SRD_MONSTERS_JSON: &str = include_str!("../../data/json/monsters.json");
+
+lazy_static! {
+    static ref SRD_LIBRARY: MonsterLibrary =
+        MonsterLibrary::from_json(SRD_MONSTERS_JSON).expect("Bundled monsters.json is invalid");
+}
+
+/// The monster library built from the bundled SRD data.
+pub fn srd_library() -> &'static MonsterLibrary {
+    &SRD_LIBRARY
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct NamedRef {
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+impl<T> Default for OneOrMany<T> {
+    fn default() -> Self {
+        OneOrMany::Many(Vec::new())
+    }
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::Many(v) => v,
+            OneOrMany::One(t) => vec![t],
+        }
+    }
+}
+
+/// The SRD data uses a few different shapes for action damage.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+enum RawDamage {
+    Full {
+        damage_type: NamedRef,
+        damage_dice: String,
+        #[serde(default)]
+        damage_bonus: i32,
+        #[serde(default)]
+        dc: Option<serde_json::Value>,
+    },
+    Short {
+        #[serde(rename = "type")]
+        kind: String,
+        dice: String,
+        #[serde(default)]
+        bonus: i32,
+    },
+    Choice {
+        #[allow(dead_code)]
+        choose: u32,
+        from: Vec<RawDamage>,
+    },
+    Other(#[allow(dead_code)] serde_json::Value),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawAction {
+    name: String,
+    #[serde(default)]
+    attack_bonus: Option<i32>,
+    #[serde(default)]
+    damage: OneOrMany<RawDamage>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawProficiency {
+    name: String,
+    value: i32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawMonster {
+    name: String,
+    armor_class: i32,
+    hit_points: i32,
+    hit_dice: String,
+    strength: i32,
+    dexterity: i32,
+    constitution: i32,
+    intelligence: i32,
+    wisdom: i32,
+    charisma: i32,
+    challenge_rating: f32,
+    #[serde(default)]
+    proficiencies: Vec<RawProficiency>,
+    #[serde(default)]
+    actions: Vec<RawAction>,
+}
+
+/// One damage component of an action hit, e.g. `1d6 + 2` slashing.
+#[derive(Clone, Debug)]
+pub struct DamageRoll {
+    pub dice: String,
+    pub bonus: i32,
+    pub damage_type: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct MonsterAction {
+    pub name: String,
+    pub attack_bonus: Option<i32>,
+    pub damage: Vec<DamageRoll>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Proficiency {
+    pub name: String,
+    pub value: i32,
+}
+
+/// A full stat block as loaded from the SRD data.
+#[derive(Clone, Debug)]
+pub struct MonsterTemplate {
+    pub name: String,
+    pub armor_class: i32,
+    pub hit_points: i32,
+    pub hit_dice: String,
+    pub abilities: AbilityScores,
+    pub challenge_rating: f32,
+    pub proficiencies: Vec<Proficiency>,
+    pub actions: Vec<MonsterAction>,
+}
+
+impl MonsterTemplate {
+    /// The first action that makes an attack roll and deals damage.
+    pub fn primary_attack(&self) -> Option<&MonsterAction> {
+        self.actions
+            .iter()
+            .find(|a| a.attack_bonus.is_some() && !a.damage.is_empty())
+    }
+
+    /// Saving throw bonuses from "Saving Throw: DEX" proficiencies, keyed by
+    /// short ability name: "dex".
+    pub fn saving_throws(&self) -> HashMap<String, i32> {
+        self.proficiencies
+            .iter()
+            .filter_map(|p| {
+                let ability = ability_from_str(p.name.strip_prefix("Saving Throw: ")?)?;
+                Some((ability.short().to_string(), p.value))
+            })
+            .collect()
+    }
+
+    /// Damage expression for a hit with `action`, e.g. "2d10+6+1d8".
+    fn damage_expr(action: &MonsterAction) -> String {
+        let mut expr = String::new();
+        for (i, d) in action.damage.iter().enumerate() {
+            if i > 0 {
+                expr.push('+');
+            }
+            expr.push_str(&d.dice);
+            if d.bonus > 0 {
+                expr.push_str(&format!("+{}", d.bonus));
+            } else if d.bonus < 0 {
+                expr.push_str(&format!("-{}", -d.bonus));
+            }
+        }
+        expr
+    }
+
+    /// Create a fresh combatant from this stat block.
+    pub fn spawn(&self) -> Result<Monster, EngineError> {
+        let (attack_bonus, damage) = match self.primary_attack() {
+            Some(a) => (a.attack_bonus.unwrap_or(0), Self::damage_expr(a)),
+            // Unarmed strike: 1 + STR modifier, no dice.
+            None => (
+                0,
+                (1 + self.abilities.modifier(Ability::Strength))
+                    .max(1)
+                    .to_string(),
+            ),
+        };
+
+        Ok(Monster {
+            id: slugify(&self.name),
+            name: self.name.clone(),
+            ac: self.armor_class,
+            max_hp: self.hit_points,
+            current_hp: self.hit_points,
+            attack_bonus,
+            damage: DiceExpr::parse(&damage)
+                .map_err(|e| EngineError::InvalidDice(format!("{}: {}", self.name, e)))?,
+            damage_type: self
+                .primary_attack()
+                .and_then(|a| a.damage.first())
+                .map(|d| d.damage_type.clone()),
+            abilities: self.abilities.clone(),
+            saving_throws: self.saving_throws(),
+            challenge_rating: self.challenge_rating,
+            conditions: Conditions::default(),
+        })
+    }
+}
+
+/// Monster stat blocks keyed by campaign ref (`srd_goblin`, `srd_adult_red_dragon`, ...).
+#[derive(Clone, Debug, Default)]
+pub struct MonsterLibrary {
+    by_ref: HashMap<String, MonsterTemplate>,
+}
+
+impl MonsterLibrary {
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawMonster> = serde_json::from_str(s)?;
+        let by_ref = raw
+            .into_iter()
+            .map(|m| {
+                let t = template_from_raw(m);
+                (srd_ref(&t.name), t)
+            })
+            .collect();
+        Ok(Self { by_ref })
+    }
+
+    pub fn get(&self, r: &str) -> Option<&MonsterTemplate> {
+        self.by_ref.get(r)
+    }
+
+    pub fn contains(&self, r: &str) -> bool {
+        self.by_ref.contains_key(r)
+    }
+}
+
+/// Campaign ref for an SRD monster name: "Giant Rat" -> "srd_giant_rat".
+pub fn srd_ref(name: &str) -> String {
+    format!("srd_{}", slugify(name))
+}
+
+/// Lowercase ASCII words joined by underscores: "Goblin Boss" -> "goblin_boss".
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_sep = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if pending_sep && !slug.is_empty() {
+                slug.push('_');
+            }
+            slug.push(c.to_ascii_lowercase());
+            pending_sep = false;
+        } else {
+            pending_sep = true;
+        }
+    }
+    slug
+}
+
+fn template_from_raw(m: RawMonster) -> MonsterTemplate {
+    let actions = m
+        .actions
+        .into_iter()
+        .map(|a| MonsterAction {
+            name: a.name,
+            attack_bonus: a.attack_bonus,
+            damage: a
+                .damage
+                .into_vec()
+                .into_iter()
+                .filter_map(hit_damage)
+                .collect(),
+        })
+        .collect();
+
+    MonsterTemplate {
+        name: m.name,
+        armor_class: m.armor_class,
+        hit_points: m.hit_points,
+        hit_dice: m.hit_dice,
+        abilities: AbilityScores {
+            str: m.strength,
+            dex: m.dexterity,
+            con: m.constitution,
+            int_: m.intelligence,
+            wis: m.wisdom,
+            cha: m.charisma,
+        },
+        challenge_rating: m.challenge_rating,
+        proficiencies: m
+            .proficiencies
+            .into_iter()
+            .map(|p| Proficiency {
+                name: p.name,
+                value: p.value,
+            })
+            .collect(),
+        actions,
+    }
+}
+
+/// Damage dealt on a hit. Riders that allow a saving throw and "choose one of"
+/// options are not modelled yet; for a choice the first option is used.
+fn hit_damage(d: RawDamage) -> Option<DamageRoll> {
+    match d {
+        RawDamage::Full {
+            damage_type,
+            damage_dice,
+            damage_bonus,
+            dc,
+        } => {
+            if dc.is_some() {
+                return None;
+            }
+            Some(DamageRoll {
+                dice: damage_dice,
+                bonus: damage_bonus,
+                damage_type: damage_type.name,
+            })
+        }
+        RawDamage::Short { kind, dice, bonus } => Some(DamageRoll {
+            dice,
+            bonus,
+            damage_type: kind,
+        }),
+        RawDamage::Choice { from, .. } => from.into_iter().find_map(hit_damage),
+        RawDamage::Other(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goblin_stat_block() {
+        let goblin = srd_library().get("srd_goblin").unwrap().spawn().unwrap();
+        assert_eq!(goblin.name, "Goblin");
+        assert_eq!((goblin.ac, goblin.max_hp, goblin.current_hp), (15, 7, 7));
+        assert_eq!(goblin.attack_bonus, 4);
+        assert_eq!(goblin.damage.to_string(), "1d6+2");
+        assert_eq!(goblin.damage_type.as_deref(), Some("Slashing"));
+        assert_eq!(goblin.abilities.modifier(Ability::Dexterity), 2);
+        assert_eq!(goblin.challenge_rating, 0.25);
+    }
+
+    #[test]
+    fn multi_part_damage_and_save_proficiencies() {
+        let dragon = srd_library().get("srd_adult_red_dragon").unwrap();
+        let monster = dragon.spawn().unwrap();
+        assert_eq!(monster.attack_bonus, 14);
+        assert_eq!(monster.damage.to_string(), "2d10+8+2d6");
+        assert_eq!(monster.saving_throws.get("dex"), Some(&6));
+        assert_eq!(monster.saving_throws.get("con"), Some(&13));
+        assert_eq!(monster.saving_throws.get("str"), None);
+    }
+
+    #[test]
+    fn every_srd_monster_spawns() {
+        let library = srd_library();
+        assert!(library.by_ref.len() > 300);
+        for (r, template) in &library.by_ref {
+            let monster = template.spawn().unwrap_or_else(|e| panic!("{}: {}", r, e));
+            assert!(monster.max_hp > 0, "{}", r);
+        }
+        assert!(!library.contains("srd_beholder"));
+    }
+
+    #[test]
+    fn refs_from_names() {
+        assert_eq!(srd_ref("Giant Rat"), "srd_giant_rat");
+        assert_eq!(slugify("Goblin Boss"), "goblin_boss");
+        assert_eq!(slugify("  Will-o'-Wisp "), "will_o_wisp");
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::monsters::slugify;
+use crate::rules::{ability_from_str, Ability};
+
+/// SRD races, subraces and racial traits, bundled into the library like the
+/// monster data.
+const SRD_RACES_JSON: &str = include_str!("../../data/json/races.json");
+const SRD_SUBRACES_JSON: &str = include_str!("../../data/json/subraces.json");
+const SRD_TRAITS_JSON: &str = include_str!("../../data/json/traits.json");
+
+lazy_static! {
+    static ref SRD_RACES: RaceLibrary =
+        RaceLibrary::from_json(SRD_RACES_JSON, SRD_SUBRACES_JSON, SRD_TRAITS_JSON)
+            .expect("Bundled races.json, subraces.json or traits.json is invalid");
+}
+
+/// The race list built from the bundled SRD data.
+pub fn srd_races() -> &'static RaceLibrary {
+    &SRD_RACES
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct NamedRef {
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawBonus {
+    name: String,
+    bonus: i32,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+struct RawBonusOptions {
+    #[serde(default)]
+    choose: u32,
+    #[serde(default)]
+    from: Vec<RawBonus>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+struct RawOptions {
+    #[serde(default)]
+    choose: u32,
+    #[serde(default)]
+    from: Vec<NamedRef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawRace {
+    name: String,
+    speed: i32,
+    size: String,
+    #[serde(default)]
+    ability_bonuses: Vec<RawBonus>,
+    #[serde(default)]
+    ability_bonus_options: RawBonusOptions,
+    #[serde(default)]
+    starting_proficiencies: Vec<NamedRef>,
+    #[serde(default)]
+    languages: Vec<NamedRef>,
+    #[serde(default)]
+    language_options: RawOptions,
+    #[serde(default)]
+    traits: Vec<NamedRef>,
+    #[serde(default)]
+    subraces: Vec<NamedRef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawSubrace {
+    name: String,
+    race: NamedRef,
+    #[serde(default)]
+    ability_bonuses: Vec<RawBonus>,
+    #[serde(default)]
+    starting_proficiencies: Vec<NamedRef>,
+    #[serde(default)]
+    languages: Vec<NamedRef>,
+    #[serde(default)]
+    racial_traits: Vec<NamedRef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawTrait {
+    name: String,
+    #[serde(default)]
+    desc: Vec<String>,
+}
+
+/// Racial traits the rules act on.
+pub const BRAVE: &str = "Brave";
+pub const DARKVISION: &str = "Darkvision";
+pub const DWARVEN_RESILIENCE: &str = "Dwarven Resilience";
+pub const DWARVEN_TOUGHNESS: &str = "Dwarven Toughness";
+pub const FEY_ANCESTRY: &str = "Fey Ancestry";
+pub const HELLISH_RESISTANCE: &str = "Hellish Resistance";
+pub const LUCKY: &str = "Lucky";
+pub const RELENTLESS_ENDURANCE: &str = "Relentless Endurance";
+
+/// What a race or subrace grants on top of the base character.
+#[derive(Clone, Debug, Default)]
+pub struct RacialGrants {
+    pub ability_bonuses: Vec<(Ability, i32)>,
+    /// Weapons by equipment slug: "battleaxe".
+    pub weapons: Vec<String>,
+    /// Skills by name: "Perception".
+    pub skills: Vec<String>,
+    pub languages: Vec<String>,
+    pub traits: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Race {
+    pub name: String,
+    /// Walking speed in feet.
+    pub speed: i32,
+    /// "Small" or "Medium".
+    pub size: String,
+    pub grants: RacialGrants,
+    /// Pick `n` abilities from the list for +1 each (the half-elf's choice).
+    pub ability_choices: Option<(u32, Vec<Ability>)>,
+    /// How many extra languages the race may pick, and from which.
+    pub extra_languages: u32,
+    pub language_options: Vec<String>,
+    pub subraces: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Subrace {
+    pub name: String,
+    pub race: String,
+    pub grants: RacialGrants,
+}
+
+/// Races, subraces and traits keyed by slug: "half_orc", "hill_dwarf", "lucky".
+#[derive(Clone, Debug, Default)]
+pub struct RaceLibrary {
+    races: HashMap<String, Race>,
+    subraces: HashMap<String, Subrace>,
+    traits: HashMap<String, Vec<String>>,
+}
+
+impl RaceLibrary {
+    pub fn from_json(races: &str, subraces: &str, traits: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawRace> = serde_json::from_str(races)?;
+        let races = raw
+            .into_iter()
+            .map(|r| {
+                let race = Race {
+                    name: r.name,
+                    speed: r.speed,
+                    size: r.size,
+                    grants: grants(
+                        &r.ability_bonuses,
+                        &r.starting_proficiencies,
+                        &r.languages,
+                        &r.traits,
+                    ),
+                    ability_choices: (r.ability_bonus_options.choose > 0).then(|| {
+                        (
+                            r.ability_bonus_options.choose,
+                            r.ability_bonus_options
+                                .from
+                                .iter()
+                                .filter_map(|b| ability_from_str(&b.name))
+                                .collect(),
+                        )
+                    }),
+                    extra_languages: r.language_options.choose,
+                    language_options: r
+                        .language_options
+                        .from
+                        .into_iter()
+                        .map(|l| l.name)
+                        .collect(),
+                    subraces: r.subraces.into_iter().map(|s| s.name).collect(),
+                };
+                (slugify(&race.name), race)
+            })
+            .collect();
+
+        let raw: Vec<RawSubrace> = serde_json::from_str(subraces)?;
+        let subraces = raw
+            .into_iter()
+            .map(|s| {
+                let sub = Subrace {
+                    name: s.name,
+                    race: s.race.name,
+                    grants: grants(
+                        &s.ability_bonuses,
+                        &s.starting_proficiencies,
+                        &s.languages,
+                        &s.racial_traits,
+                    ),
+                };
+                (slugify(&sub.name), sub)
+            })
+            .collect();
+
+        let raw: Vec<RawTrait> = serde_json::from_str(traits)?;
+        let traits = raw
+            .into_iter()
+            .map(|t| (slugify(&t.name), t.desc))
+            .collect();
+
+        Ok(Self {
+            races,
+            subraces,
+            traits,
+        })
+    }
+
+    /// Look a race up by slug or display name.
+    pub fn get(&self, name: &str) -> Option<&Race> {
+        self.races.get(&slugify(name))
+    }
+
+    pub fn subrace(&self, name: &str) -> Option<&Subrace> {
+        self.subraces.get(&slugify(name))
+    }
+
+    /// Rules text for a trait, from traits.json.
+    pub fn trait_desc(&self, name: &str) -> Option<&[String]> {
+        self.traits.get(&slugify(name)).map(Vec::as_slice)
+    }
+
+    /// Every race, sorted by name.
+    pub fn all(&self) -> Vec<&Race> {
+        let mut all: Vec<&Race> = self.races.values().collect();
+        all.sort_by(|a, b| a.name.cmp(&b.name));
+        all
+    }
+}
+
+fn grants(
+    bonuses: &[RawBonus],
+    proficiencies: &[NamedRef],
+    languages: &[NamedRef],
+    traits: &[NamedRef],
+) -> RacialGrants {
+    let mut g = RacialGrants {
+        ability_bonuses: bonuses
+            .iter()
+            .filter_map(|b| Some((ability_from_str(&b.name)?, b.bonus)))
+            .collect(),
+        languages: languages.iter().map(|l| l.name.clone()).collect(),
+        traits: traits.iter().map(|t| trait_name(&t.name)).collect(),
+        ..Default::default()
+    };
+    for p in proficiencies {
+        match p.name.strip_prefix("Skill: ") {
+            Some(skill) => g.skills.push(skill.to_string()),
+            None => g.weapons.push(weapon_slug(&p.name)),
+        }
+    }
+    g
+}
+
+/// races.json calls the half-orc trait "Restless Endurance"; traits.json and
+/// the SRD call it "Relentless Endurance".
+fn trait_name(name: &str) -> String {
+    match name {
+        "Restless Endurance" => RELENTLESS_ENDURANCE.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// "Light hammers" names the weapon "Light hammer": slug "light_hammer".
+fn weapon_slug(name: &str) -> String {
+    slugify(name.strip_suffix('s').unwrap_or(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::{D20Test, DiceRoller};
+    use crate::error::EngineError;
+    use crate::rules::{Ability, Character};
+
+    /// The pregen fighter as a member of `race`, with max HP left to the rules.
+    fn hero(race: &str, subrace: Option<&str>, choices: &[&str]) -> Result<Character, EngineError> {
+        let mut value: serde_json::Value =
+            serde_json::from_str(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        value["race"] = race.into();
+        if let Some(s) = subrace {
+            value["subrace"] = s.into();
+        }
+        value["ability_bonus_choices"] = choices.into();
+        value["max_hp"] = 0.into();
+        Character::from_json(&value.to_string())
+    }
+
+    #[test]
+    fn hill_dwarves_are_tough_and_resist_poison() {
+        let dwarf = hero("Dwarf", Some("Hill Dwarf"), &[]).unwrap();
+        // CON 14 + 2 and WIS 12 + 1.
+        assert_eq!(dwarf.abilities.con, 16);
+        assert_eq!(dwarf.abilities.wis, 13);
+        assert!(dwarf.resists("Poison") && !dwarf.resists("fire"));
+        assert!(dwarf.has_save_advantage_against("poisoned"));
+        // d10 + CON +3, plus 1 from Dwarven Toughness.
+        assert_eq!(dwarf.max_hp, 14);
+        assert!(dwarf.has_trait(DARKVISION));
+    }
+
+    #[test]
+    fn half_elves_choose_two_ability_bonuses() {
+        let half_elf = hero("Half-Elf", None, &["DEX", "con"]).unwrap();
+        assert_eq!(
+            (
+                half_elf.abilities.cha,
+                half_elf.abilities.dex,
+                half_elf.abilities.con
+            ),
+            (12, 14, 15)
+        );
+        assert_eq!(
+            hero("Half-Elf", None, &["DEX", "CON", "STR"]).err(),
+            Some(EngineError::InvalidRace(
+                "3 ability bonus choices given, 2 allowed".to_string()
+            ))
+        );
+        assert_eq!(
+            hero("Half-Elf", None, &["CHA"]).err(),
+            Some(EngineError::InvalidRace(
+                "Can't choose CHA for a racial ability bonus".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn races_and_subraces_must_exist_and_match() {
+        assert_eq!(
+            hero("Dwarf", Some("High Elf"), &[]).err(),
+            Some(EngineError::InvalidRace(
+                "High Elf is a Elf subrace".to_string()
+            ))
+        );
+        assert_eq!(
+            hero("Kender", None, &[]).err(),
+            Some(EngineError::UnknownRace("Kender".to_string()))
+        );
+    }
+
+    #[test]
+    fn halflings_reroll_natural_ones() {
+        let halfling = hero("Halfling", Some("Lightfoot Halfling"), &[]).unwrap();
+        let seed = (0..).find(|&s| DiceRoller::new(s).d20() == 1).unwrap();
+        let mut expected = DiceRoller::new(seed);
+        expected.d20();
+        let reroll = expected.d20();
+        let roll = halfling.roll_d20(&mut DiceRoller::new(seed), &D20Test::new());
+        assert_eq!(roll.natural, reroll);
+        assert_eq!(halfling.abilities.score(Ability::Dexterity), 15);
+        assert_eq!(halfling.speed(), 25);
+    }
+
+    #[test]
+    fn half_orcs_shrug_off_the_first_knockout() {
+        let mut half_orc = hero("Half-Orc", None, &[]).unwrap();
+        let hp = half_orc.current_hp;
+        assert_eq!(
+            half_orc.take_damage(hp, false).as_deref(),
+            Some("Relentless Endurance: you drop to 1 hit point instead!")
+        );
+        assert_eq!(half_orc.current_hp, 1);
+        assert_eq!(
+            half_orc.take_damage(1, false).as_deref(),
+            Some("You fall unconscious!")
+        );
+    }
+}
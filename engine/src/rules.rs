@@ -1,6 +1,23 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug)]
+use crate::classes::{srd_classes, CharacterClass};
+use crate::combat::CritRule;
+use crate::conditions::{ActiveCondition, Condition, Conditions};
+use crate::dice::{D20Roll, D20Test, DiceExpr, DiceRoller};
+use crate::equipment::{srd_equipment, ArmorCategory, WeaponProperty};
+use crate::error::EngineError;
+use crate::levels::srd_levels;
+use crate::monsters::slugify;
+use crate::races::{
+    srd_races, RacialGrants, BRAVE, DARKVISION, DWARVEN_RESILIENCE, DWARVEN_TOUGHNESS,
+    FEY_ANCESTRY, HELLISH_RESISTANCE, LUCKY, RELENTLESS_ENDURANCE,
+};
+use crate::skills::{srd_skills, Skill};
+use crate::spells::{slot_name, srd_spells, Spell};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Ability {
     Strength,
     Dexterity,
@@ -21,34 +38,1131 @@ pub struct AbilityScores {
     pub cha: i32,
 }
 
+impl Ability {
+    pub const ALL: [Ability; 6] = [
+        Ability::Strength,
+        Ability::Dexterity,
+        Ability::Constitution,
+        Ability::Intelligence,
+        Ability::Wisdom,
+        Ability::Charisma,
+    ];
+
+    /// Full name as used in check descriptions: "Dexterity".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ability::Strength => "Strength",
+            Ability::Dexterity => "Dexterity",
+            Ability::Constitution => "Constitution",
+            Ability::Intelligence => "Intelligence",
+            Ability::Wisdom => "Wisdom",
+            Ability::Charisma => "Charisma",
+        }
+    }
+
+    /// Short name as used in choice ids and character JSON: "dex".
+    pub fn short(&self) -> &'static str {
+        match self {
+            Ability::Strength => "str",
+            Ability::Dexterity => "dex",
+            Ability::Constitution => "con",
+            Ability::Intelligence => "int",
+            Ability::Wisdom => "wis",
+            Ability::Charisma => "cha",
+        }
+    }
+}
+
 impl AbilityScores {
-    pub fn modifier(&self, ability: Ability) -> i32 {
-        let score = match ability {
+    pub fn score(&self, ability: Ability) -> i32 {
+        match ability {
             Ability::Strength => self.str,
             Ability::Dexterity => self.dex,
             Ability::Constitution => self.con,
             Ability::Intelligence => self.int_,
             Ability::Wisdom => self.wis,
             Ability::Charisma => self.cha,
+        }
+    }
+
+    pub fn score_mut(&mut self, ability: Ability) -> &mut i32 {
+        match ability {
+            Ability::Strength => &mut self.str,
+            Ability::Dexterity => &mut self.dex,
+            Ability::Constitution => &mut self.con,
+            Ability::Intelligence => &mut self.int_,
+            Ability::Wisdom => &mut self.wis,
+            Ability::Charisma => &mut self.cha,
+        }
+    }
+
+    /// Rounds down, so a 9 is -1 rather than 0.
+    pub fn modifier(&self, ability: Ability) -> i32 {
+        (self.score(ability) - 10).div_euclid(2)
+    }
+}
+
+/// What the character has equipped, by SRD equipment slug ("longsword", "chain_mail").
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Equipment {
+    #[serde(default)]
+    pub weapon: Option<String>,
+    #[serde(default)]
+    pub armor: Option<String>,
+    #[serde(default)]
+    pub shield: bool,
+    /// Wield a versatile weapon in both hands. Ignored while carrying a shield.
+    #[serde(default)]
+    pub two_handed: bool,
+}
+
+/// Something carried, by SRD equipment slug ("explorer_s_pack"), and how many.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: String,
+    pub quantity: u32,
+}
+
+/// What happens when the hero drops to 0 hit points.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DefeatRule {
+    /// Roll death saves; `on_defeat` only follows three failures.
+    #[default]
+    DeathSaves,
+    /// Dropping to 0 ends the fight at once: the hero is knocked out and
+    /// captured, and wakes at `on_defeat` with 1 hit point.
+    Captured,
+}
+
+/// Optional rule variants a campaign can switch on.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct HouseRules {
+    #[serde(default)]
+    pub crits: CritRule,
+    #[serde(default)]
+    pub defeat: DefeatRule,
+    /// Optional flanking rule: a monster attacking the hero has advantage while
+    /// another monster able to act is fighting them too.
+    #[serde(default)]
+    pub flanking: bool,
+}
+
+/// Death saving throws made while at 0 hit points.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeathSaves {
+    pub successes: u8,
+    pub failures: u8,
+    /// Three successes: still unconscious, but no longer rolling.
+    #[serde(default)]
+    pub stable: bool,
+}
+
+impl DeathSaves {
+    fn tally(&self) -> String {
+        format!(
+            "{} success{}, {} failure{}",
+            self.successes,
+            if self.successes == 1 { "" } else { "es" },
+            self.failures,
+            if self.failures == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// A saving throw as rolled, logged the same way as an ability check.
+#[derive(Clone, Debug)]
+pub struct SaveRoll {
+    pub ability: Ability,
+    pub dc: i32,
+    pub modifier: i32,
+    /// `None` when a condition made the save fail without a roll.
+    pub roll: Option<D20Roll>,
+    pub auto_fail: Option<Condition>,
+}
+
+impl SaveRoll {
+    /// Roll a save for a creature with `conditions`, `d20` rolling each die.
+    /// Paralyzed, petrified, stunned and unconscious creatures fail Strength
+    /// and Dexterity saves outright.
+    pub fn make(
+        ability: Ability,
+        dc: i32,
+        modifier: i32,
+        conditions: &Conditions,
+        mut test: D20Test,
+        d20: impl FnMut() -> i32,
+    ) -> Self {
+        let auto_fail = conditions.fails_save(ability);
+        let roll = if auto_fail.is_some() {
+            None
+        } else {
+            conditions.saving(ability, &mut test);
+            Some(test.roll_with(d20))
         };
-        (score - 10) / 2
+        Self {
+            ability,
+            dc,
+            modifier,
+            roll,
+            auto_fail,
+        }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.roll.as_ref().map_or(0, |r| r.natural) + self.modifier
+    }
+
+    pub fn success(&self) -> bool {
+        self.roll.is_some() && self.total() >= self.dc
+    }
+}
+
+impl fmt::Display for SaveRoll {
+    /// e.g. "Dexterity saving throw (DC 13): rolled 12 + 3 = 15 => success"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} saving throw (DC {}): ", self.ability.name(), self.dc)?;
+        match (&self.roll, self.auto_fail) {
+            (Some(roll), _) => write!(
+                f,
+                "rolled {} + {} = {} => {}",
+                roll,
+                self.modifier,
+                self.total(),
+                if self.success() { "success" } else { "failure" }
+            ),
+            (None, Some(c)) => write!(f, "{} => failure", c.slug()),
+            (None, None) => write!(f, "failure"),
+        }
     }
 }
 
+/// Everything needed to resolve one weapon attack.
+#[derive(Clone, Debug)]
+pub struct AttackProfile {
+    pub weapon_name: String,
+    pub ability: Ability,
+    pub attack_bonus: i32,
+    pub damage: DiceExpr,
+    pub damage_bonus: i32,
+    pub damage_type: String,
+    /// A ranged weapon: no advantage against prone targets, and no automatic
+    /// critical hits.
+    pub ranged: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Character {
     pub name: String,
+    /// Class name or slug ("Fighter"). Picks the levels.json table used when
+    /// levelling up.
+    #[serde(default)]
+    pub class: Option<String>,
+    /// Subclass name or slug ("Champion"), from subclasses.json.
+    #[serde(default)]
+    pub subclass: Option<String>,
+    /// Race name or slug ("Half-Orc"), from races.json. In character JSON,
+    /// `abilities` are the scores before racial bonuses.
+    #[serde(default)]
+    pub race: Option<String>,
+    /// Subrace name or slug ("Hill Dwarf"), from subraces.json.
+    #[serde(default)]
+    pub subrace: Option<String>,
+    /// Abilities picked for a race's +1 choices, e.g. a half-elf's "DEX", "CON".
+    #[serde(default)]
+    pub ability_bonus_choices: Vec<String>,
+    /// Languages known beyond those the race grants.
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Background name ("Soldier"). Its skills are listed in `skill_proficiencies`.
+    #[serde(default)]
+    pub background: Option<String>,
     pub level: u8,
+    #[serde(default)]
+    pub xp: u32,
+    /// Class features gained from levelling up.
+    #[serde(default)]
+    pub features: Vec<String>,
     pub abilities: AbilityScores,
+    /// Worked out from the class hit die and CON when left out.
+    #[serde(default)]
     pub max_hp: i32,
+    #[serde(default)]
     pub current_hp: i32,
-    pub ac: i32,
+    /// Fixed AC for characters without equipment data. Ignored once armor or a
+    /// shield is equipped.
+    #[serde(default)]
+    pub ac: Option<i32>,
     pub proficiency_bonus: i32,
+    #[serde(default)]
+    pub equipment: Equipment,
+    /// Everything carried, equipped or not.
+    #[serde(default)]
+    pub items: Vec<ItemStack>,
+    /// Coin, in gold pieces.
+    #[serde(default)]
+    pub gold: u32,
+    /// Skills the character is proficient in, by name or slug ("stealth").
+    #[serde(default)]
+    pub skill_proficiencies: Vec<String>,
+    /// Skills with expertise: proficiency bonus is doubled. Implies proficiency.
+    #[serde(default)]
+    pub expertise: Vec<String>,
+    #[serde(default)]
+    pub death_saves: DeathSaves,
+    /// Blinded, poisoned, prone and the rest, with how each ends.
+    #[serde(default)]
+    pub conditions: Conditions,
+    /// Hit die size for characters without a class: 10 for a d10. A d8 when
+    /// not given.
+    #[serde(default)]
+    pub hit_die: Option<u32>,
+    /// Hit dice spent since they were last regained. The pool is one die per level.
+    #[serde(default)]
+    pub hit_dice_used: u32,
+    /// Relentless Endurance has been used since the last long rest.
+    #[serde(default)]
+    pub relentless_endurance_used: bool,
+    /// Cantrips known, by name or slug ("fire_bolt").
+    #[serde(default)]
+    pub cantrips: Vec<String>,
+    /// Spells known or prepared, by name or slug ("magic_missile").
+    #[serde(default)]
+    pub spells: Vec<String>,
+    /// Slots spent since the last long rest, per spell level starting at 1st.
+    #[serde(default)]
+    pub spell_slots_used: Vec<u32>,
 }
 
 impl Character {
-    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(s)
+    pub fn from_json(s: &str) -> Result<Self, EngineError> {
+        let mut character: Character =
+            serde_json::from_str(s).map_err(|e| EngineError::invalid_json("character", e))?;
+        character.check_hit_die()?;
+        character.check_class()?;
+        character.check_race()?;
+        character.apply_racial_bonuses();
+        character.check_equipment()?;
+        character.check_skills()?;
+        character.check_spells()?;
+        if character.max_hp <= 0 {
+            character.max_hp = character.starting_max_hp();
+            character.current_hp = character.max_hp;
+        }
+        Ok(character)
+    }
+
+    /// The character's class from classes.json, if it has one.
+    pub fn class_def(&self) -> Option<&'static CharacterClass> {
+        srd_classes().get(self.class.as_deref()?)
+    }
+
+    /// A classless character may pick its own hit die, but only a real one.
+    pub fn check_hit_die(&self) -> Result<(), EngineError> {
+        match self.hit_die {
+            Some(die) if ![6, 8, 10, 12].contains(&die) => Err(EngineError::InvalidJson {
+                what: "character".to_string(),
+                message: format!("hit_die must be 6, 8, 10 or 12, not {}", die),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Make sure the class and subclass exist and belong together.
+    pub fn check_class(&self) -> Result<(), EngineError> {
+        let library = srd_classes();
+        let class = match &self.class {
+            Some(c) => Some(
+                library
+                    .get(c)
+                    .ok_or_else(|| EngineError::UnknownClass(c.clone()))?,
+            ),
+            None => None,
+        };
+        if let Some(s) = &self.subclass {
+            let subclass = library
+                .subclass(s)
+                .ok_or_else(|| EngineError::UnknownClass(s.clone()))?;
+            match class {
+                Some(c) if c.name == subclass.class => {}
+                _ => {
+                    return Err(EngineError::InvalidClass(format!(
+                        "{} is a {} subclass",
+                        subclass.name, subclass.class
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// What the race and subrace grant, race first.
+    pub fn racial_grants(&self) -> Vec<&'static RacialGrants> {
+        let library = srd_races();
+        let race = self.race.as_deref().and_then(|r| library.get(r));
+        let subrace = self.subrace.as_deref().and_then(|s| library.subrace(s));
+        race.map(|r| &r.grants)
+            .into_iter()
+            .chain(subrace.map(|s| &s.grants))
+            .collect()
+    }
+
+    /// Make sure the race and subrace exist, belong together and that any
+    /// ability choices are allowed.
+    pub fn check_race(&self) -> Result<(), EngineError> {
+        let library = srd_races();
+        let race = match &self.race {
+            Some(r) => Some(
+                library
+                    .get(r)
+                    .ok_or_else(|| EngineError::UnknownRace(r.clone()))?,
+            ),
+            None => None,
+        };
+        if let Some(s) = &self.subrace {
+            let subrace = library
+                .subrace(s)
+                .ok_or_else(|| EngineError::UnknownRace(s.clone()))?;
+            if race.map(|r| &r.name) != Some(&subrace.race) {
+                return Err(EngineError::InvalidRace(format!(
+                    "{} is a {} subrace",
+                    subrace.name, subrace.race
+                )));
+            }
+        }
+        let (allowed, options) = match race.and_then(|r| r.ability_choices.as_ref()) {
+            Some((n, from)) => (*n as usize, from.as_slice()),
+            None => (0, &[][..]),
+        };
+        if self.ability_bonus_choices.len() > allowed {
+            return Err(EngineError::InvalidRace(format!(
+                "{} ability bonus choices given, {} allowed",
+                self.ability_bonus_choices.len(),
+                allowed
+            )));
+        }
+        for choice in &self.ability_bonus_choices {
+            match ability_from_str(choice) {
+                Some(a) if options.contains(&a) => {}
+                _ => {
+                    return Err(EngineError::InvalidRace(format!(
+                        "Can't choose {} for a racial ability bonus",
+                        choice
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add racial ability bonuses to the base scores and racial skills to the
+    /// character's proficiencies. Run once, when the character is created.
+    fn apply_racial_bonuses(&mut self) {
+        for grants in self.racial_grants() {
+            for &(ability, bonus) in &grants.ability_bonuses {
+                *self.abilities.score_mut(ability) += bonus;
+            }
+            for skill in &grants.skills {
+                if !self
+                    .skill_proficiencies
+                    .iter()
+                    .any(|s| slugify(s) == slugify(skill))
+                {
+                    self.skill_proficiencies.push(skill.clone());
+                }
+            }
+        }
+        for choice in &self.ability_bonus_choices {
+            if let Some(ability) = ability_from_str(choice) {
+                *self.abilities.score_mut(ability) += 1;
+            }
+        }
+    }
+
+    /// Racial traits by name, e.g. "Darkvision", "Lucky".
+    pub fn traits(&self) -> Vec<&'static str> {
+        self.racial_grants()
+            .into_iter()
+            .flat_map(|g| g.traits.iter().map(String::as_str))
+            .collect()
+    }
+
+    pub fn has_trait(&self, name: &str) -> bool {
+        self.traits().contains(&name)
+    }
+
+    /// Walking speed in feet: 30 without a race, 0 while grappled or restrained.
+    pub fn speed(&self) -> i32 {
+        if self.conditions.immobilized() {
+            return 0;
+        }
+        self.race
+            .as_deref()
+            .and_then(|r| srd_races().get(r))
+            .map_or(30, |r| r.speed)
+    }
+
+    pub fn size(&self) -> &'static str {
+        self.race
+            .as_deref()
+            .and_then(|r| srd_races().get(r))
+            .map_or("Medium", |r| r.size.as_str())
+    }
+
+    /// Darkvision range in feet.
+    pub fn darkvision(&self) -> Option<i32> {
+        self.has_trait(DARKVISION).then_some(60)
+    }
+
+    pub fn known_languages(&self) -> Vec<String> {
+        let mut all: Vec<String> = self
+            .racial_grants()
+            .into_iter()
+            .flat_map(|g| g.languages.iter().cloned())
+            .collect();
+        for l in &self.languages {
+            if !all.contains(l) {
+                all.push(l.clone());
+            }
+        }
+        all
+    }
+
+    /// Damage of this type ("Poison", "Fire") is halved.
+    pub fn resists(&self, damage_type: &str) -> bool {
+        match damage_type.to_ascii_lowercase().as_str() {
+            "poison" => self.has_trait(DWARVEN_RESILIENCE),
+            "fire" => self.has_trait(HELLISH_RESISTANCE),
+            _ => false,
+        }
+    }
+
+    /// Advantage on saving throws against `effect`, e.g. "poisoned".
+    pub fn has_save_advantage_against(&self, effect: &str) -> bool {
+        self.save_advantage_trait(effect).is_some()
+    }
+
+    /// The racial trait giving advantage on saves against `effect`, if any.
+    pub fn save_advantage_trait(&self, effect: &str) -> Option<&'static str> {
+        let name = match effect.to_ascii_lowercase().as_str() {
+            "poison" | "poisoned" => DWARVEN_RESILIENCE,
+            "frightened" => BRAVE,
+            "charmed" => FEY_ANCESTRY,
+            _ => return None,
+        };
+        self.has_trait(name).then_some(name)
+    }
+
+    /// Extra hit points per level, from Dwarven Toughness.
+    pub fn hp_bonus_per_level(&self) -> i32 {
+        if self.has_trait(DWARVEN_TOUGHNESS) {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Roll a d20 test for an attack, check or save. Halfling Lucky rerolls a
+    /// natural 1 on either die.
+    pub fn roll_d20(&self, dice: &mut DiceRoller, test: &D20Test) -> D20Roll {
+        test.roll_with(|| self.d20(dice))
+    }
+
+    /// One d20, rerolling a natural 1 for Halfling Lucky.
+    fn d20(&self, dice: &mut DiceRoller) -> i32 {
+        match dice.d20() {
+            1 if self.has_trait(LUCKY) => dice.d20(),
+            r => r,
+        }
+    }
+
+    /// Make a saving throw against `dc`, with proficiency from the class.
+    /// Conditions add to `test` and can fail the save outright.
+    pub fn saving_throw(
+        &self,
+        ability: Ability,
+        dc: i32,
+        test: D20Test,
+        dice: &mut DiceRoller,
+    ) -> SaveRoll {
+        SaveRoll::make(
+            ability,
+            dc,
+            self.saving_throw_modifier(ability),
+            &self.conditions,
+            test,
+            || self.d20(dice),
+        )
+    }
+
+    /// A save against `effect` ("poison", "frightened"), with advantage from
+    /// any racial trait that protects against it.
+    pub fn save_test(&self, effect: &str) -> D20Test {
+        let mut test = D20Test::new();
+        if let Some(name) = self.save_advantage_trait(effect) {
+            test.add_advantage(name);
+        }
+        test
+    }
+
+    /// End-of-turn bookkeeping for conditions: count down durations and roll
+    /// saves to end them.
+    pub fn end_turn_conditions(&mut self, dice: &mut DiceRoller) -> Vec<String> {
+        let mut conditions = std::mem::take(&mut self.conditions);
+        let log = conditions.end_of_turn("you", |condition, ability, dc| {
+            self.saving_throw(ability, dc, self.save_test(condition.slug()), dice)
+        });
+        self.conditions = conditions;
+        log
+    }
+
+    /// Stand up from prone at the start of a turn, if the character can move.
+    pub fn stand_up(&mut self) -> Option<String> {
+        (self.speed() > 0 && self.conditions.remove(Condition::Prone))
+            .then(|| "You stand up.".to_string())
+    }
+
+    /// Max HP for a new character: the full hit die plus CON at level 1, then
+    /// the fixed average for each level after.
+    pub fn starting_max_hp(&self) -> i32 {
+        let con = self.abilities.modifier(Ability::Constitution);
+        let die = self.hit_die() as i32;
+        let later = (self.level as i32 - 1).max(0) * (die / 2 + 1 + con).max(1);
+        (die + con).max(1) + later + self.hp_bonus_per_level() * self.level as i32
+    }
+
+    /// Saving throw bonus: the ability modifier, plus proficiency when the
+    /// class grants it.
+    pub fn saving_throw_modifier(&self, ability: Ability) -> i32 {
+        let proficient = self
+            .class_def()
+            .is_some_and(|c| c.has_saving_throw(ability));
+        self.abilities.modifier(ability)
+            + if proficient {
+                self.proficiency_bonus
+            } else {
+                0
+            }
+    }
+
+    pub fn check_skills(&self) -> Result<(), EngineError> {
+        let library = srd_skills();
+        for s in self.skill_proficiencies.iter().chain(&self.expertise) {
+            if library.get(s).is_none() {
+                return Err(EngineError::UnknownSkill(s.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    fn has_skill(list: &[String], skill: &Skill) -> bool {
+        let library = srd_skills();
+        list.iter()
+            .any(|s| library.get(s).is_some_and(|k| k.name == skill.name))
+    }
+
+    /// Proficiency bonus that applies to a check with `skill`: none, single or
+    /// doubled for expertise.
+    pub fn skill_proficiency(&self, skill: &Skill) -> i32 {
+        if Self::has_skill(&self.expertise, skill) {
+            self.proficiency_bonus * 2
+        } else if Self::has_skill(&self.skill_proficiencies, skill) {
+            self.proficiency_bonus
+        } else {
+            0
+        }
+    }
+
+    /// Three failed death saves, or massive damage.
+    pub fn is_dead(&self) -> bool {
+        self.death_saves.failures >= 3
+    }
+
+    /// At 0 hit points and still rolling death saves.
+    pub fn is_dying(&self) -> bool {
+        self.current_hp <= 0 && !self.is_dead() && !self.death_saves.stable
+    }
+
+    /// Lose hit points, following the 0 HP rules: dropping to 0 knocks the
+    /// hero unconscious, damage at 0 is a failed death save (two on a critical hit),
+    /// and damage of at least max HP beyond 0 kills outright. Returns a line to
+    /// log when the hero's state changes.
+    pub fn take_damage(&mut self, amount: i32, critical: bool) -> Option<String> {
+        if self.is_dead() || amount <= 0 {
+            return None;
+        }
+        if self.current_hp > 0 {
+            let left = self.current_hp - amount;
+            if left > 0 {
+                self.current_hp = left;
+                return None;
+            }
+            self.current_hp = 0;
+            self.death_saves = DeathSaves::default();
+            if -left >= self.max_hp {
+                self.death_saves.failures = 3;
+                return Some("The blow kills you outright.".to_string());
+            }
+            if self.has_trait(RELENTLESS_ENDURANCE) && !self.relentless_endurance_used {
+                self.relentless_endurance_used = true;
+                self.current_hp = 1;
+                return Some("Relentless Endurance: you drop to 1 hit point instead!".to_string());
+            }
+            self.fall_unconscious();
+            return Some("You fall unconscious!".to_string());
+        }
+
+        self.fall_unconscious();
+        self.death_saves.stable = false;
+        if amount >= self.max_hp {
+            self.death_saves.failures = 3;
+        } else {
+            self.death_saves.failures = (self.death_saves.failures + 1 + critical as u8).min(3);
+        }
+        Some(if self.is_dead() {
+            "You die.".to_string()
+        } else {
+            format!("Damage while down: {}.", self.death_saves.tally())
+        })
+    }
+
+    /// At 0 hit points the hero is unconscious until healed, stable or not.
+    fn fall_unconscious(&mut self) {
+        self.conditions.add(ActiveCondition {
+            condition: Condition::Unconscious,
+            rounds: None,
+            save: None,
+        });
+    }
+
+    /// Regain up to `amount` hit points. Any healing brings a dying hero
+    /// round; the dead stay dead. Returns the hit points actually regained.
+    pub fn heal(&mut self, amount: i32) -> i32 {
+        if self.is_dead() || amount <= 0 {
+            return 0;
+        }
+        let before = self.current_hp.max(0);
+        self.current_hp = before.saturating_add(amount).min(self.max_hp);
+        self.death_saves = DeathSaves::default();
+        self.conditions.remove(Condition::Unconscious);
+        self.current_hp - before
+    }
+
+    /// Roll a death saving throw: 10 or higher succeeds, a natural 1 counts as
+    /// two failures and a natural 20 brings the hero back with 1 hit point.
+    pub fn death_save(&mut self, dice: &mut DiceRoller) -> String {
+        let roll = self.roll_d20(dice, &D20Test::new()).natural;
+        if roll == 20 {
+            self.heal(1);
+            return "Death saving throw: natural 20! You regain 1 hit point and come to."
+                .to_string();
+        }
+        let saves = &mut self.death_saves;
+        match roll {
+            1 => saves.failures = (saves.failures + 2).min(3),
+            r if r >= 10 => saves.successes += 1,
+            _ => saves.failures += 1,
+        }
+        let outcome = if roll >= 10 { "success" } else { "failure" };
+        let saves = self.death_saves;
+        let result = if self.is_dead() {
+            " You die.".to_string()
+        } else if saves.successes >= 3 {
+            self.death_saves.stable = true;
+            " You are stable.".to_string()
+        } else {
+            String::new()
+        };
+        format!(
+            "Death saving throw: rolled {} => {} ({}).{}",
+            roll,
+            outcome,
+            saves.tally(),
+            result
+        )
+    }
+
+    pub fn hit_die(&self) -> u32 {
+        match self.class_def() {
+            Some(c) => c.hit_die,
+            None => self.hit_die.unwrap_or(8),
+        }
+    }
+
+    pub fn hit_dice_remaining(&self) -> u32 {
+        (self.level as u32).saturating_sub(self.hit_dice_used)
+    }
+
+    /// What one hit die heals, e.g. "1d10+2".
+    pub fn hit_die_expr(&self) -> Result<DiceExpr, String> {
+        let con = self.abilities.modifier(Ability::Constitution);
+        let expr = match con {
+            0 => format!("1d{}", self.hit_die()),
+            c => format!("1d{}{:+}", self.hit_die(), c),
+        };
+        DiceExpr::parse(&expr)
+    }
+
+    /// Spend one hit die during a short rest: roll it, add CON and heal that much.
+    pub fn spend_hit_die(&mut self, dice: &mut DiceRoller) -> Result<String, String> {
+        if self.hit_dice_remaining() == 0 {
+            return Err("You have no hit dice left.".to_string());
+        }
+        if self.current_hp <= 0 {
+            return Err("You can't spend hit dice while unconscious.".to_string());
+        }
+        let expr = self.hit_die_expr()?;
+        self.hit_dice_used += 1;
+        let roll = dice.eval(&expr);
+        let healed = self.heal(roll.total.max(0));
+        Ok(format!(
+            "Hit die {} => {} HP regained ({}/{}).",
+            roll, healed, self.current_hp, self.max_hp
+        ))
+    }
+
+    /// Eight hours of rest: full hit points and back half the hit dice (at
+    /// least one). Needs at least 1 hit point.
+    pub fn long_rest(&mut self) -> Result<String, String> {
+        if self.current_hp <= 0 {
+            return Err("You can't take a long rest at 0 hit points.".to_string());
+        }
+        self.heal(self.max_hp);
+        self.relentless_endurance_used = false;
+        let regained = (self.level as u32 / 2).max(1).min(self.hit_dice_used);
+        self.hit_dice_used -= regained;
+        let mut line = format!(
+            "You take a long rest: HP {}/{}, {} of {} hit dice available.",
+            self.current_hp,
+            self.max_hp,
+            self.hit_dice_remaining(),
+            self.level
+        );
+        if self.spell_slots_used.iter().any(|&n| n > 0) {
+            line.push_str(" Your spell slots are restored.");
+        }
+        self.spell_slots_used.clear();
+        Ok(line)
+    }
+
+    /// The ability the character's class casts spells with, if it casts.
+    pub fn spellcasting_ability(&self) -> Option<Ability> {
+        srd_spells().casting_ability(self.class.as_deref()?)
+    }
+
+    /// Make sure every cantrip and spell exists and is on the class's list.
+    pub fn check_spells(&self) -> Result<(), EngineError> {
+        if self.cantrips.is_empty() && self.spells.is_empty() {
+            return Ok(());
+        }
+        let class = match (&self.class, self.spellcasting_ability()) {
+            (Some(class), Some(_)) => class,
+            _ => {
+                return Err(EngineError::InvalidSpell(format!(
+                    "{} can't cast spells",
+                    self.name
+                )))
+            }
+        };
+        for (name, cantrip) in self
+            .cantrips
+            .iter()
+            .map(|n| (n, true))
+            .chain(self.spells.iter().map(|n| (n, false)))
+        {
+            let spell = srd_spells()
+                .get(name)
+                .ok_or_else(|| EngineError::UnknownSpell(name.clone()))?;
+            if spell.is_cantrip() != cantrip {
+                return Err(EngineError::InvalidSpell(format!(
+                    "{} is {}a cantrip",
+                    spell.name,
+                    if cantrip { "not " } else { "" }
+                )));
+            }
+            if !spell.on_class_list(class) {
+                return Err(EngineError::InvalidSpell(format!(
+                    "{} is not on the {} spell list",
+                    spell.name, class
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Spell slots per level, 1st through 9th, from the class table.
+    pub fn spell_slots(&self) -> [u32; 9] {
+        self.class
+            .as_deref()
+            .and_then(|c| srd_levels().get(c, self.level))
+            .map_or([0; 9], |row| row.spell_slots)
+    }
+
+    /// Unspent slots of spell level `level` (1-9).
+    pub fn slots_left(&self, level: u8) -> u32 {
+        let i = level as usize - 1;
+        let used = self.spell_slots_used.get(i).copied().unwrap_or(0);
+        self.spell_slots()
+            .get(i)
+            .map_or(0, |&n| n.saturating_sub(used))
+    }
+
+    /// Unspent slots per spell level from 1st, up to the highest the
+    /// character has. Empty for non-casters.
+    pub fn slots_left_by_level(&self) -> Vec<u32> {
+        let levels = self.spell_slots().iter().filter(|&&n| n > 0).count();
+        (1..=levels as u8).map(|l| self.slots_left(l)).collect()
+    }
+
+    /// The lowest spell level with a slot left, at `min_level` or above.
+    pub fn lowest_slot(&self, min_level: u8) -> Option<u8> {
+        (min_level.max(1)..=9).find(|&l| self.slots_left(l) > 0)
+    }
+
+    /// The slot level casting `spell` would use: 0 for a cantrip, otherwise
+    /// the lowest slot left at the spell's level or above.
+    pub fn slot_for(&self, spell: &Spell) -> Result<u8, String> {
+        if spell.is_cantrip() {
+            return Ok(0);
+        }
+        self.lowest_slot(spell.level).ok_or_else(|| {
+            format!(
+                "You have no spell slots left to cast {} ({} or higher).",
+                spell.name,
+                slot_name(spell.level)
+            )
+        })
+    }
+
+    pub fn expend_slot(&mut self, level: u8) {
+        let i = level as usize - 1;
+        if self.spell_slots_used.len() <= i {
+            self.spell_slots_used.resize(i + 1, 0);
+        }
+        self.spell_slots_used[i] += 1;
+    }
+
+    /// Cantrips and spells known, as SRD spells.
+    pub fn known_spells(&self) -> Vec<&'static Spell> {
+        self.cantrips
+            .iter()
+            .chain(&self.spells)
+            .filter_map(|n| srd_spells().get(n))
+            .collect()
+    }
+
+    pub fn spellcasting_modifier(&self) -> i32 {
+        self.spellcasting_ability()
+            .map_or(0, |a| self.abilities.modifier(a))
+    }
+
+    pub fn spell_attack_bonus(&self) -> i32 {
+        self.proficiency_bonus + self.spellcasting_modifier()
+    }
+
+    pub fn spell_save_dc(&self) -> i32 {
+        8 + self.proficiency_bonus + self.spellcasting_modifier()
+    }
+
+    /// Total modifier for an ability check, optionally using a skill.
+    pub fn check_modifier(&self, ability: Ability, skill: Option<&Skill>) -> i32 {
+        self.abilities.modifier(ability) + skill.map_or(0, |s| self.skill_proficiency(s))
+    }
+
+    /// How many of an item the character carries, by SRD index or name.
+    pub fn item_count(&self, item: &str) -> u32 {
+        self.items
+            .iter()
+            .filter(|s| slugify(&s.item) == slugify(item))
+            .map(|s| s.quantity)
+            .sum()
+    }
+
+    /// Whether the character carries or has equipped an item.
+    pub fn has_item(&self, item: &str) -> bool {
+        let slug = slugify(item);
+        let eq = &self.equipment;
+        self.item_count(item) > 0
+            || eq
+                .weapon
+                .iter()
+                .chain(&eq.armor)
+                .any(|i| slugify(i) == slug)
+            || (eq.shield && slug == "shield")
+    }
+
+    /// How much the character can carry, in pounds: 15 times Strength.
+    pub fn carrying_capacity(&self) -> i32 {
+        self.abilities.score(Ability::Strength) * 15
+    }
+
+    /// Add `quantity` of an item, stacking with any already carried. Returns
+    /// how many were added; a stack stops growing at `u32::MAX`.
+    pub fn give_item(&mut self, item: &str, quantity: u32) -> u32 {
+        let slug = slugify(item);
+        match self.items.iter_mut().find(|s| slugify(&s.item) == slug) {
+            Some(stack) => {
+                let before = stack.quantity;
+                stack.quantity = before.saturating_add(quantity);
+                stack.quantity - before
+            }
+            None => {
+                self.items.push(ItemStack {
+                    item: slug,
+                    quantity,
+                });
+                quantity
+            }
+        }
+    }
+
+    /// Remove up to `quantity` of an item. Returns how many were removed.
+    pub fn take_item(&mut self, item: &str, quantity: u32) -> u32 {
+        let slug = slugify(item);
+        let mut taken = 0;
+        for stack in self.items.iter_mut().filter(|s| slugify(&s.item) == slug) {
+            let n = stack.quantity.min(quantity - taken);
+            stack.quantity -= n;
+            taken += n;
+        }
+        self.items.retain(|s| s.quantity > 0);
+        taken
+    }
+
+    /// Make sure carried and equipped items exist and can be used together.
+    pub fn check_equipment(&self) -> Result<(), EngineError> {
+        let library = srd_equipment();
+        if let Some(stack) = self.items.iter().find(|s| library.get(&s.item).is_none()) {
+            return Err(EngineError::UnknownEquipment(stack.item.clone()));
+        }
+        let eq = &self.equipment;
+        if let Some(w) = &eq.weapon {
+            let weapon = library
+                .weapon(w)
+                .ok_or_else(|| EngineError::UnknownEquipment(w.clone()))?;
+            if eq.shield && weapon.has(WeaponProperty::TwoHanded) {
+                return Err(EngineError::InvalidEquipment(format!(
+                    "{} needs both hands, so it can't be used with a shield",
+                    weapon.name
+                )));
+            }
+        }
+        if let Some(a) = &eq.armor {
+            match library.armor(a) {
+                Some(armor) if armor.category != ArmorCategory::Shield => {
+                    self.check_armor_proficiency(armor.category, &armor.name)?;
+                }
+                Some(_) => {
+                    return Err(EngineError::InvalidEquipment(
+                        "Equip shields with \"shield\": true, not as armor".to_string(),
+                    ))
+                }
+                None => return Err(EngineError::UnknownEquipment(a.clone())),
+            }
+        }
+        if eq.shield {
+            self.check_armor_proficiency(ArmorCategory::Shield, "a shield")?;
+        }
+        Ok(())
+    }
+
+    /// Characters without a class can wear anything.
+    fn check_armor_proficiency(
+        &self,
+        category: ArmorCategory,
+        name: &str,
+    ) -> Result<(), EngineError> {
+        match self.class_def() {
+            Some(class) if !class.has_armor(category) => Err(EngineError::InvalidEquipment(
+                format!("A {} can't use {}", class.name.to_lowercase(), name),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// AC from armor (with its DEX cap) and shield, or 10 + DEX unarmored.
+    pub fn armor_class(&self) -> i32 {
+        let library = srd_equipment();
+        let dex = self.abilities.modifier(Ability::Dexterity);
+        let armor = self
+            .equipment
+            .armor
+            .as_deref()
+            .and_then(|a| library.armor(a));
+        let shield = if self.equipment.shield {
+            library.armor("shield").map(|s| s.ac_for(dex)).unwrap_or(2)
+        } else {
+            0
+        };
+
+        match armor {
+            Some(a) => a.ac_for(dex) + shield,
+            None if shield == 0 => self.ac.unwrap_or(10 + dex),
+            None => 10 + dex + shield,
+        }
+    }
+
+    /// The attack made with the equipped weapon, or an unarmed strike.
+    pub fn attack_profile(&self) -> AttackProfile {
+        let str_mod = self.abilities.modifier(Ability::Strength);
+        let dex_mod = self.abilities.modifier(Ability::Dexterity);
+        let weapon = self
+            .equipment
+            .weapon
+            .as_deref()
+            .and_then(|w| srd_equipment().weapon(w));
+
+        let Some(weapon) = weapon else {
+            return AttackProfile {
+                weapon_name: "fists".to_string(),
+                ability: Ability::Strength,
+                attack_bonus: str_mod + self.proficiency_bonus,
+                damage: DiceExpr::parse("1").expect("constant dice expression"),
+                damage_bonus: str_mod,
+                damage_type: "Bludgeoning".to_string(),
+                ranged: false,
+            };
+        };
+
+        let use_dex = weapon.ranged || (weapon.has(WeaponProperty::Finesse) && dex_mod > str_mod);
+        let ability = if use_dex {
+            Ability::Dexterity
+        } else {
+            Ability::Strength
+        };
+        let modifier = self.abilities.modifier(ability);
+        let weapon_slug = slugify(&weapon.name);
+        let proficient = self.class_def().is_none_or(|c| c.has_weapon(weapon))
+            || self
+                .racial_grants()
+                .iter()
+                .any(|g| g.weapons.contains(&weapon_slug));
+
+        let damage = match &weapon.two_handed_damage {
+            Some(two_handed)
+                if weapon.has(WeaponProperty::Versatile)
+                    && self.equipment.two_handed
+                    && !self.equipment.shield =>
+            {
+                two_handed.clone()
+            }
+            _ => weapon.damage.clone(),
+        };
+
+        AttackProfile {
+            weapon_name: weapon.name.clone(),
+            ability,
+            attack_bonus: modifier
+                + if proficient {
+                    self.proficiency_bonus
+                } else {
+                    0
+                },
+            damage,
+            damage_bonus: modifier,
+            damage_type: weapon.damage_type.clone(),
+            ranged: weapon.ranged,
+        }
     }
 }
 
@@ -63,3 +1177,268 @@ pub fn ability_from_str(s: &str) -> Option<Ability> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREGEN: &str = include_str!("../../content/campaigns/pregen_fighter.json");
+
+    fn classless(hit_die: u32) -> String {
+        let mut value: serde_json::Value = serde_json::from_str(PREGEN).unwrap();
+        let object = value.as_object_mut().unwrap();
+        object.remove("class");
+        object.remove("subclass");
+        object.insert("hit_die".to_string(), hit_die.into());
+        value.to_string()
+    }
+
+    #[test]
+    fn modifiers_round_down() {
+        let mut scores = Character::from_json(PREGEN).unwrap().abilities;
+        for (score, modifier) in [
+            (1, -5),
+            (3, -4),
+            (8, -1),
+            (9, -1),
+            (10, 0),
+            (11, 0),
+            (20, 5),
+        ] {
+            scores.str = score;
+            assert_eq!(
+                scores.modifier(Ability::Strength),
+                modifier,
+                "score {}",
+                score
+            );
+        }
+    }
+
+    #[test]
+    fn armor_and_shield_set_armor_class() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        // Chain mail is a flat 16; DEX doesn't count.
+        assert_eq!(hero.armor_class(), 16);
+        hero.equipment.shield = true;
+        assert_eq!(hero.armor_class(), 18);
+        // Leather is 11 + DEX (+1), plus the shield.
+        hero.equipment.armor = Some("leather".to_string());
+        assert_eq!(hero.armor_class(), 14);
+        hero.equipment.armor = None;
+        hero.equipment.shield = false;
+        assert_eq!(hero.armor_class(), 11);
+    }
+
+    #[test]
+    fn the_equipped_weapon_drives_the_attack() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        let attack = hero.attack_profile();
+        assert_eq!(attack.weapon_name, "Longsword");
+        assert_eq!(attack.ability, Ability::Strength);
+        assert_eq!((attack.attack_bonus, attack.damage_bonus), (5, 3));
+        // Versatile: the two-handed die, unless a shield takes the other hand.
+        assert_eq!(attack.damage.to_string(), "1d10");
+        hero.equipment.shield = true;
+        assert_eq!(hero.attack_profile().damage.to_string(), "1d8");
+
+        hero.equipment.weapon = Some("rapier".to_string());
+        hero.abilities.dex = 18;
+        let attack = hero.attack_profile();
+        assert_eq!(attack.ability, Ability::Dexterity);
+        assert_eq!((attack.attack_bonus, attack.damage_bonus), (6, 4));
+
+        hero.equipment.weapon = Some("longbow".to_string());
+        hero.class = Some("Wizard".to_string());
+        let attack = hero.attack_profile();
+        assert!(attack.ranged);
+        assert_eq!(attack.attack_bonus, 4, "no proficiency for a wizard");
+    }
+
+    #[test]
+    fn dropping_to_zero_knocks_the_hero_unconscious_until_healed() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        assert_eq!(
+            hero.take_damage(hero.max_hp, false).as_deref(),
+            Some("You fall unconscious!")
+        );
+        assert!(hero.conditions.has(Condition::Unconscious));
+        assert!(hero.conditions.melee_hits_are_critical());
+        hero.take_damage(1, true);
+        assert_eq!(hero.death_saves.failures, 2);
+        assert_eq!(hero.heal(3), 3);
+        assert!(!hero.conditions.has(Condition::Unconscious));
+    }
+
+    fn downed() -> Character {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        hero.take_damage(hero.max_hp, false);
+        hero
+    }
+
+    #[test]
+    fn death_saves_follow_the_natural_roll() {
+        for seed in 0..60 {
+            let natural = DiceRoller::new(seed).d20();
+            let mut hero = downed();
+            let line = hero.death_save(&mut DiceRoller::new(seed));
+            let saves = hero.death_saves;
+            match natural {
+                20 => {
+                    assert_eq!(hero.current_hp, 1, "{}", line);
+                    assert!(!hero.conditions.has(Condition::Unconscious));
+                }
+                1 => assert_eq!((saves.successes, saves.failures), (0, 2), "{}", line),
+                n if n >= 10 => assert_eq!((saves.successes, saves.failures), (1, 0)),
+                _ => assert_eq!((saves.successes, saves.failures), (0, 1)),
+            }
+        }
+    }
+
+    #[test]
+    fn three_successes_stabilize_and_three_failures_kill() {
+        let mut hero = downed();
+        hero.death_saves.successes = 2;
+        let seed = (0..)
+            .find(|&s| (10..20).contains(&DiceRoller::new(s).d20()))
+            .unwrap();
+        let line = hero.death_save(&mut DiceRoller::new(seed));
+        assert!(line.ends_with(" You are stable."), "{}", line);
+        assert!(!hero.is_dying() && !hero.is_dead());
+        assert!(hero.conditions.has(Condition::Unconscious));
+
+        // Damage while stable starts the saves again.
+        hero.take_damage(1, false);
+        assert!(hero.is_dying());
+        hero.take_damage(1, true);
+        assert!(hero.is_dead());
+        assert_eq!(hero.take_damage(5, false), None);
+        assert_eq!(hero.heal(5), 0);
+    }
+
+    #[test]
+    fn massive_damage_kills_outright() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        // 12 HP: 12 to reach 0 and 12 more beyond it.
+        assert_eq!(
+            hero.take_damage(24, false).as_deref(),
+            Some("The blow kills you outright.")
+        );
+        assert!(hero.is_dead());
+
+        let mut hero = downed();
+        assert_eq!(
+            hero.take_damage(hero.max_hp, false).as_deref(),
+            Some("You die.")
+        );
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        assert_eq!(
+            hero.take_damage(23, false).as_deref(),
+            Some("You fall unconscious!")
+        );
+    }
+
+    #[test]
+    fn short_rests_spend_hit_dice() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        let mut dice = DiceRoller::new(4);
+        hero.current_hp = 1;
+        let line = hero.spend_hit_die(&mut dice).unwrap();
+        assert!(line.starts_with("Hit die 1d10+2"), "{}", line);
+        // A fighter's d10 plus CON +2.
+        assert!((4..=12).contains(&hero.current_hp), "{}", line);
+        assert_eq!(hero.hit_dice_remaining(), 0);
+        assert_eq!(
+            hero.spend_hit_die(&mut dice),
+            Err("You have no hit dice left.".to_string())
+        );
+
+        hero.level = 2;
+        hero.current_hp = 0;
+        assert_eq!(
+            hero.spend_hit_die(&mut dice),
+            Err("You can't spend hit dice while unconscious.".to_string())
+        );
+        assert_eq!(hero.hit_dice_remaining(), 1);
+    }
+
+    #[test]
+    fn long_rests_heal_and_restore_half_the_hit_dice() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        hero.current_hp = 0;
+        assert!(hero.long_rest().is_err());
+
+        hero.level = 5;
+        hero.max_hp = 44;
+        hero.current_hp = 3;
+        hero.hit_dice_used = 5;
+        hero.spell_slots_used = vec![1];
+        assert_eq!(
+            hero.long_rest().unwrap(),
+            "You take a long rest: HP 44/44, 2 of 5 hit dice available. \
+             Your spell slots are restored."
+        );
+        assert!(hero.spell_slots_used.is_empty());
+        hero.long_rest().unwrap();
+        hero.long_rest().unwrap();
+        assert_eq!(hero.hit_dice_remaining(), 5);
+
+        // Always at least one back.
+        hero.level = 1;
+        hero.hit_dice_used = 1;
+        hero.long_rest().unwrap();
+        assert_eq!(hero.hit_dice_remaining(), 1);
+    }
+
+    #[test]
+    fn hit_die_must_be_a_real_die() {
+        for bad in [0, 4, 7, 1001] {
+            assert!(matches!(
+                Character::from_json(&classless(bad)),
+                Err(EngineError::InvalidJson { .. })
+            ));
+        }
+        let hero = Character::from_json(&classless(10)).unwrap();
+        assert_eq!(hero.hit_die(), 10);
+        assert_eq!(hero.hit_die_expr().unwrap().to_string(), "1d10+2");
+    }
+
+    #[test]
+    fn fighters_are_proficient_in_strength_and_constitution_saves() {
+        let hero = Character::from_json(PREGEN).unwrap();
+        // STR +3, DEX +1, CON +2 and WIS +1, with a +2 proficiency bonus.
+        assert_eq!(hero.saving_throw_modifier(Ability::Strength), 5);
+        assert_eq!(hero.saving_throw_modifier(Ability::Dexterity), 1);
+        assert_eq!(hero.saving_throw_modifier(Ability::Constitution), 4);
+        assert_eq!(hero.saving_throw_modifier(Ability::Wisdom), 1);
+
+        let mut dice = DiceRoller::new(5);
+        let natural = DiceRoller::new(5).d20();
+        let save = hero.saving_throw(Ability::Constitution, 10, D20Test::new(), &mut dice);
+        assert_eq!(save.total(), natural + 4);
+        assert_eq!(save.success(), natural + 4 >= 10);
+        assert!(save
+            .to_string()
+            .starts_with("Constitution saving throw (DC 10): rolled "));
+    }
+
+    #[test]
+    fn paralyzed_heroes_fail_dexterity_saves_outright() {
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        hero.conditions.add(ActiveCondition {
+            condition: Condition::Paralyzed,
+            rounds: None,
+            save: None,
+        });
+        let mut dice = DiceRoller::new(1);
+        let save = hero.saving_throw(Ability::Dexterity, 1, D20Test::new(), &mut dice);
+        assert!(!save.success());
+        assert_eq!(
+            save.to_string(),
+            "Dexterity saving throw (DC 1): paralyzed => failure"
+        );
+        // Mental saves are still rolled.
+        let save = hero.saving_throw(Ability::Wisdom, 1, D20Test::new(), &mut dice);
+        assert!(save.success());
+    }
+}
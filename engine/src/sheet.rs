@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use crate::inventory::{inventory, InventoryEntry, ItemDef};
+use crate::rules::{Ability, Character};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AbilityLine {
+    /// "STR", "DEX", ...
+    pub ability: String,
+    pub score: i32,
+    pub modifier: i32,
+    pub saving_throw: i32,
+}
+
+/// Everything about the hero for a full character screen, where
+/// `CharacterSummary` is the one-line version shown under each node.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CharacterSheet {
+    pub name: String,
+    #[serde(default)]
+    pub race: Option<String>,
+    #[serde(default)]
+    pub class: Option<String>,
+    #[serde(default)]
+    pub background: Option<String>,
+    pub level: u8,
+    pub xp: u32,
+    pub current_hp: i32,
+    pub max_hp: i32,
+    pub ac: i32,
+    pub speed: i32,
+    pub proficiency_bonus: i32,
+    pub abilities: Vec<AbilityLine>,
+    #[serde(default)]
+    pub skill_proficiencies: Vec<String>,
+    /// Class features and racial traits.
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub conditions: Vec<String>,
+    #[serde(default)]
+    pub cantrips: Vec<String>,
+    #[serde(default)]
+    pub spells: Vec<String>,
+    /// Unspent slots per spell level from 1st, as in `CharacterSummary`.
+    #[serde(default)]
+    pub spell_slots: Vec<u32>,
+    pub gold: u32,
+    pub inventory: Vec<InventoryEntry>,
+    /// Total weight carried, in pounds.
+    pub carried_weight: f32,
+    pub carrying_capacity: i32,
+}
+
+impl CharacterSheet {
+    /// `items` are the campaign's own items, for names and weights.
+    pub fn new(character: &Character, items: &[ItemDef]) -> Self {
+        let inventory = inventory(character, items);
+        Self {
+            name: character.name.clone(),
+            race: character.race.clone(),
+            class: character.class.clone(),
+            background: character.background.clone(),
+            level: character.level,
+            xp: character.xp,
+            current_hp: character.current_hp,
+            max_hp: character.max_hp,
+            ac: character.armor_class(),
+            speed: character.speed(),
+            proficiency_bonus: character.proficiency_bonus,
+            abilities: Ability::ALL
+                .iter()
+                .map(|&a| AbilityLine {
+                    ability: a.short().to_uppercase(),
+                    score: character.abilities.score(a),
+                    modifier: character.abilities.modifier(a),
+                    saving_throw: character.saving_throw_modifier(a),
+                })
+                .collect(),
+            skill_proficiencies: character.skill_proficiencies.clone(),
+            features: character
+                .features
+                .iter()
+                .map(String::as_str)
+                .chain(character.traits())
+                .map(str::to_string)
+                .collect(),
+            conditions: character.conditions.names(),
+            cantrips: character.cantrips.clone(),
+            spells: character.spells.clone(),
+            spell_slots: character.slots_left_by_level(),
+            gold: character.gold,
+            carried_weight: inventory.iter().map(|e| e.weight).sum(),
+            carrying_capacity: character.carrying_capacity(),
+            inventory,
+        }
+    }
+}
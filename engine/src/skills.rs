@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::monsters::slugify;
+use crate::rules::{ability_from_str, Ability};
+
+/// SRD skills, bundled into the library like the monster data.
+const SRD_SKILLS_JSON: &str = include_str!("../../data/json/skills.json");
+
+lazy_static! {
+    static ref SRD_SKILLS: SkillLibrary =
+        SkillLibrary::from_json(SRD_SKILLS_JSON).expect("Bundled skills.json is invalid");
+}
+
+/// The skill list built from the bundled SRD data.
+pub fn srd_skills() -> &'static SkillLibrary {
+    &SRD_SKILLS
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct NamedRef {
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawSkill {
+    name: String,
+    ability_score: NamedRef,
+}
+
+#[derive(Clone, Debug)]
+pub struct Skill {
+    pub name: String,
+    pub ability: Ability,
+}
+
+/// Skills keyed by slug: "stealth", "animal_handling", "sleight_of_hand".
+#[derive(Clone, Debug, Default)]
+pub struct SkillLibrary {
+    by_ref: HashMap<String, Skill>,
+}
+
+impl SkillLibrary {
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawSkill> = serde_json::from_str(s)?;
+        let mut by_ref = HashMap::new();
+        for r in raw {
+            let ability = ability_from_str(&r.ability_score.name).ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "Unknown ability '{}' for skill {}",
+                    r.ability_score.name, r.name
+                ))
+            })?;
+            by_ref.insert(
+                slugify(&r.name),
+                Skill {
+                    name: r.name,
+                    ability,
+                },
+            );
+        }
+        Ok(Self { by_ref })
+    }
+
+    /// Look a skill up by slug or display name: "sleight_of_hand" or "Sleight of Hand".
+    pub fn get(&self, name: &str) -> Option<&Skill> {
+        self.by_ref.get(&slugify(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EngineError;
+    use crate::rules::Character;
+
+    const PREGEN: &str = include_str!("../../content/campaigns/pregen_fighter.json");
+
+    #[test]
+    fn srd_skills_by_slug_or_name() {
+        let library = srd_skills();
+        assert_eq!(library.by_ref.len(), 18);
+        let sleight = library.get("Sleight of Hand").unwrap();
+        assert_eq!(sleight.ability, Ability::Dexterity);
+        assert_eq!(library.get("sleight_of_hand").unwrap().name, sleight.name);
+        assert_eq!(library.get("athletics").unwrap().ability, Ability::Strength);
+        assert_eq!(library.get("insight").unwrap().ability, Ability::Wisdom);
+        assert!(library.get("basket_weaving").is_none());
+    }
+
+    #[test]
+    fn proficiency_and_expertise_add_to_checks() {
+        let library = srd_skills();
+        let mut hero = Character::from_json(PREGEN).unwrap();
+        let perception = library.get("perception").unwrap();
+        // STR +3 and WIS +1, with a +2 proficiency bonus.
+        assert_eq!(
+            hero.check_modifier(Ability::Strength, library.get("athletics")),
+            5
+        );
+        assert_eq!(hero.check_modifier(Ability::Wisdom, Some(perception)), 3);
+        assert_eq!(
+            hero.check_modifier(Ability::Dexterity, library.get("stealth")),
+            1
+        );
+        hero.expertise.push("Perception".to_string());
+        assert_eq!(hero.check_modifier(Ability::Wisdom, Some(perception)), 5);
+
+        let unknown = PREGEN.replace("\"perception\"", "\"basket_weaving\"");
+        assert_eq!(
+            Character::from_json(&unknown).err(),
+            Some(EngineError::UnknownSkill("basket_weaving".to_string()))
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::dice::DiceExpr;
+use crate::monsters::slugify;
+use crate::rules::{ability_from_str, Ability};
+
+/// SRD spells and class spellcasting rules, bundled into the library like the
+/// monster data.
+const SRD_SPELLS_JSON: &str = include_str!("../../data/json/spells.json");
+const SRD_SPELLCASTING_JSON: &str = include_str!("../../data/json/spellcasting.json");
+
+lazy_static! {
+    static ref SRD_SPELLS: SpellLibrary =
+        SpellLibrary::from_json(SRD_SPELLS_JSON, SRD_SPELLCASTING_JSON)
+            .expect("Bundled spells.json or spellcasting.json is invalid");
+}
+
+/// The spell list built from the bundled SRD data.
+pub fn srd_spells() -> &'static SpellLibrary {
+    &SRD_SPELLS
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct NamedRef {
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawSpell {
+    name: String,
+    level: u8,
+    #[serde(default)]
+    desc: Vec<String>,
+    #[serde(default)]
+    higher_level: Vec<String>,
+    #[serde(default)]
+    range: String,
+    #[serde(default)]
+    casting_time: String,
+    school: NamedRef,
+    #[serde(default)]
+    classes: Vec<NamedRef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct RawSpellcasting {
+    class: NamedRef,
+    spellcasting_ability: NamedRef,
+}
+
+/// A fixed number of one kind of die plus a bonus: "3d6", "1d4+1".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpellDice {
+    pub count: u32,
+    pub sides: u32,
+    pub bonus: i32,
+}
+
+impl SpellDice {
+    pub fn to_expr(self) -> DiceExpr {
+        let expr = match self.bonus {
+            0 => format!("{}d{}", self.count, self.sides),
+            b if b > 0 => format!("{}d{}+{}", self.count, self.sides, b),
+            b => format!("{}d{}-{}", self.count, self.sides, -b),
+        };
+        DiceExpr::parse(&expr).expect("spell dice are always well formed")
+    }
+}
+
+/// What casting a spell does, for the shapes the engine can resolve.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpellEffect {
+    /// Make a spell attack roll against one target; damage on a hit.
+    Attack {
+        melee: bool,
+        damage: SpellDice,
+        damage_type: String,
+    },
+    /// The target, or every creature in the area, saves or takes damage.
+    Save {
+        ability: Ability,
+        damage: SpellDice,
+        damage_type: String,
+        /// Half damage on a successful save rather than none.
+        half: bool,
+        area: bool,
+    },
+    /// The caster regains hit points: the dice plus the spellcasting modifier.
+    Healing { dice: SpellDice },
+    /// Magic Missile: darts that hit automatically, one more per slot level.
+    Darts {
+        count: u32,
+        damage: SpellDice,
+        damage_type: String,
+    },
+}
+
+impl SpellEffect {
+    /// Whether casting needs a single creature picked as the target.
+    pub fn needs_target(&self) -> bool {
+        match self {
+            SpellEffect::Attack { .. } | SpellEffect::Darts { .. } => true,
+            SpellEffect::Save { area, .. } => !area,
+            SpellEffect::Healing { .. } => false,
+        }
+    }
+}
+
+/// "1st-level", "2nd-level", ...
+pub fn slot_name(level: u8) -> String {
+    let suffix = match level {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    };
+    format!("{}{}-level", level, suffix)
+}
+
+#[derive(Clone, Debug)]
+pub struct Spell {
+    pub name: String,
+    /// 0 for cantrips.
+    pub level: u8,
+    pub school: String,
+    pub range: String,
+    pub casting_time: String,
+    /// Classes with the spell on their list: "Wizard".
+    pub classes: Vec<String>,
+    pub desc: Vec<String>,
+    /// `None` for spells the engine can't resolve yet.
+    pub effect: Option<SpellEffect>,
+    /// Extra dice for each slot level above the spell's own.
+    pub upcast: Option<SpellDice>,
+}
+
+impl Spell {
+    pub fn is_cantrip(&self) -> bool {
+        self.level == 0
+    }
+
+    pub fn on_class_list(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| slugify(c) == slugify(class))
+    }
+
+    /// The effect's dice when cast with a `slot_level` slot by a caster of
+    /// `caster_level`. Cantrip dice grow at levels 5, 11 and 17.
+    pub fn dice_at(&self, dice: SpellDice, slot_level: u8, caster_level: u8) -> SpellDice {
+        if self.is_cantrip() {
+            let tier = 1 + [5, 11, 17].iter().filter(|&&l| caster_level >= l).count() as u32;
+            return SpellDice {
+                count: dice.count * tier,
+                ..dice
+            };
+        }
+        match self.upcast {
+            Some(extra) if extra.sides == dice.sides && slot_level > self.level => SpellDice {
+                count: dice.count + extra.count * (slot_level - self.level) as u32,
+                ..dice
+            },
+            _ => dice,
+        }
+    }
+}
+
+/// Spells keyed by slug ("magic_missile"), plus each casting class's ability.
+#[derive(Clone, Debug, Default)]
+pub struct SpellLibrary {
+    by_ref: HashMap<String, Spell>,
+    casting_ability: HashMap<String, Ability>,
+}
+
+impl SpellLibrary {
+    pub fn from_json(spells: &str, spellcasting: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<RawSpell> = serde_json::from_str(spells)?;
+        let by_ref = raw
+            .into_iter()
+            .map(|s| {
+                // Reactions and spells with a longer casting time (Glyph of
+                // Warding) aren't cast on the hero's own turn.
+                let effect = if matches!(s.casting_time.as_str(), "1 action" | "1 bonus action") {
+                    parse_effect(&s.desc.join(" "))
+                } else {
+                    None
+                };
+                let spell = Spell {
+                    effect,
+                    upcast: parse_upcast(&s.higher_level.join(" ")),
+                    name: s.name,
+                    level: s.level,
+                    school: s.school.name,
+                    range: s.range,
+                    casting_time: s.casting_time,
+                    classes: s.classes.into_iter().map(|c| c.name).collect(),
+                    desc: s.desc,
+                };
+                (slugify(&spell.name), spell)
+            })
+            .collect();
+
+        let raw: Vec<RawSpellcasting> = serde_json::from_str(spellcasting)?;
+        let casting_ability = raw
+            .into_iter()
+            .filter_map(|c| {
+                let ability = ability_from_str(&c.spellcasting_ability.name)?;
+                Some((slugify(&c.class.name), ability))
+            })
+            .collect();
+
+        Ok(Self {
+            by_ref,
+            casting_ability,
+        })
+    }
+
+    /// Look a spell up by slug or display name.
+    pub fn get(&self, name: &str) -> Option<&Spell> {
+        self.by_ref.get(&slugify(name))
+    }
+
+    /// The ability `class` casts with, if it casts at all.
+    pub fn casting_ability(&self, class: &str) -> Option<Ability> {
+        self.casting_ability.get(&slugify(class)).copied()
+    }
+
+    /// Spells of `level` on the class list, sorted by name.
+    pub fn for_class(&self, class: &str, level: u8) -> Vec<&Spell> {
+        let mut spells: Vec<&Spell> = self
+            .by_ref
+            .values()
+            .filter(|s| s.level == level && s.on_class_list(class))
+            .collect();
+        spells.sort_by(|a, b| a.name.cmp(&b.name));
+        spells
+    }
+}
+
+const DAMAGE_TYPES: [&str; 13] = [
+    "acid",
+    "bludgeoning",
+    "cold",
+    "fire",
+    "force",
+    "lightning",
+    "necrotic",
+    "piercing",
+    "poison",
+    "psychic",
+    "radiant",
+    "slashing",
+    "thunder",
+];
+
+/// Words of the rules text, lowercased, with punctuation dropped.
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '+')
+                .to_ascii_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// "3d6" as dice.
+fn dice_word(word: &str) -> Option<SpellDice> {
+    let (count, sides) = word.split_once('d')?;
+    Some(SpellDice {
+        count: count.parse().ok()?,
+        sides: sides.parse().ok()?,
+        bonus: 0,
+    })
+}
+
+/// The first "XdY [+ N] <type> damage" in the text.
+fn damage(words: &[String]) -> Option<(SpellDice, String)> {
+    (0..words.len()).find_map(|i| {
+        let mut dice = dice_word(&words[i])?;
+        let mut j = i + 1;
+        if words.get(j).map(String::as_str) == Some("+") {
+            dice.bonus = words.get(j + 1)?.parse().ok()?;
+            j += 2;
+        }
+        let kind = words.get(j)?;
+        (DAMAGE_TYPES.contains(&kind.as_str())
+            && words.get(j + 1).map(String::as_str) == Some("damage"))
+        .then(|| (dice, kind.clone()))
+    })
+}
+
+fn number_word(word: &str) -> Option<u32> {
+    let n = match word {
+        "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        _ => word.parse().ok()?,
+    };
+    Some(n)
+}
+
+/// Recognize the common effect shapes in a spell's rules text.
+fn parse_effect(text: &str) -> Option<SpellEffect> {
+    let words = words(text);
+    let has = |phrase: &str| text.to_ascii_lowercase().contains(phrase);
+
+    if has("regains") && has("spellcasting ability modifier") {
+        let i = words.iter().position(|w| w == "regains")?;
+        let dice = words[i..].iter().find_map(|w| dice_word(w))?;
+        return Some(SpellEffect::Healing { dice });
+    }
+
+    if let Some(i) = words.iter().position(|w| w == "darts") {
+        let count = words[i.saturating_sub(3)..i]
+            .iter()
+            .find_map(|w| number_word(w))?;
+        let (damage, damage_type) = damage(&words[i..])?;
+        return Some(SpellEffect::Darts {
+            count,
+            damage,
+            damage_type,
+        });
+    }
+    // Damage counts only when it follows closely on the attack or save; spells
+    // like Web mention damage in passing.
+    if let Some(i) = phrase(&words, "spell", "attack") {
+        let (damage, damage_type) = damage(near(&words, i))?;
+        return Some(SpellEffect::Attack {
+            melee: i
+                .checked_sub(1)
+                .and_then(|j| words.get(j))
+                .is_some_and(|w| w == "melee"),
+            damage,
+            damage_type,
+        });
+    }
+    let i = phrase(&words, "saving", "throw")?;
+    let ability = ability_from_str(words.get(i.checked_sub(1)?)?)?;
+    let (damage, damage_type) = damage(near(&words, i))?;
+    Some(SpellEffect::Save {
+        ability,
+        damage,
+        damage_type,
+        half: has("half as much damage") || has("damage is halved"),
+        area: has("each creature"),
+    })
+}
+
+/// Where two words first appear together.
+fn phrase(words: &[String], first: &str, second: &str) -> Option<usize> {
+    words
+        .windows(2)
+        .position(|w| w[0] == first && w[1] == second)
+}
+
+/// The words shortly after `i`.
+fn near(words: &[String], i: usize) -> &[String] {
+    &words[i..words.len().min(i + 30)]
+}
+
+/// "the damage increases by 1d6 for each slot level above 1st".
+fn parse_upcast(text: &str) -> Option<SpellDice> {
+    let words = words(text);
+    let i = phrase(&words, "increases", "by")?;
+    dice_word(words.get(i + 2)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Character;
+
+    #[test]
+    fn text_opening_with_spell_attack_parses() {
+        assert!(matches!(
+            parse_effect("Spell attack: the target takes 1d10 fire damage on a hit."),
+            Some(SpellEffect::Attack { melee: false, .. })
+        ));
+        assert!(matches!(
+            parse_effect(
+                "Make a melee spell attack. On a hit, the target takes 3d8 lightning damage."
+            ),
+            Some(SpellEffect::Attack { melee: true, .. })
+        ));
+    }
+
+    fn dice(count: u32, sides: u32, bonus: i32) -> SpellDice {
+        SpellDice {
+            count,
+            sides,
+            bonus,
+        }
+    }
+
+    #[test]
+    fn srd_spells_resolve_to_effects() {
+        let library = srd_spells();
+        assert_eq!(
+            library.get("Fire Bolt").unwrap().effect,
+            Some(SpellEffect::Attack {
+                melee: false,
+                damage: dice(1, 10, 0),
+                damage_type: "fire".to_string(),
+            })
+        );
+        assert_eq!(
+            library.get("magic_missile").unwrap().effect,
+            Some(SpellEffect::Darts {
+                count: 3,
+                damage: dice(1, 4, 1),
+                damage_type: "force".to_string(),
+            })
+        );
+        let burning_hands = library.get("Burning Hands").unwrap();
+        assert!(matches!(
+            &burning_hands.effect,
+            Some(SpellEffect::Save {
+                ability: Ability::Dexterity,
+                half: true,
+                area: true,
+                ..
+            })
+        ));
+        assert!(!burning_hands.effect.as_ref().unwrap().needs_target());
+        assert_eq!(
+            library.get("Cure Wounds").unwrap().effect,
+            Some(SpellEffect::Healing {
+                dice: dice(1, 8, 0)
+            })
+        );
+        // Reactions aren't cast on the hero's turn.
+        assert_eq!(library.get("Shield").unwrap().effect, None);
+        assert!(library.get("Wish").is_some() && library.get("Fireball 2").is_none());
+    }
+
+    #[test]
+    fn cantrips_scale_with_level_and_spells_with_slots() {
+        let library = srd_spells();
+        let fire_bolt = library.get("Fire Bolt").unwrap();
+        for (level, count) in [(1, 1), (5, 2), (11, 3), (17, 4)] {
+            assert_eq!(fire_bolt.dice_at(dice(1, 10, 0), 0, level).count, count);
+        }
+        let burning_hands = library.get("Burning Hands").unwrap();
+        assert_eq!(burning_hands.dice_at(dice(3, 6, 0), 1, 1), dice(3, 6, 0));
+        assert_eq!(burning_hands.dice_at(dice(3, 6, 0), 3, 5), dice(5, 6, 0));
+        assert_eq!(dice(1, 4, 1).to_expr().to_string(), "1d4+1");
+    }
+
+    #[test]
+    fn class_lists_and_casting_abilities() {
+        let library = srd_spells();
+        assert_eq!(
+            library.casting_ability("Wizard"),
+            Some(Ability::Intelligence)
+        );
+        assert_eq!(library.casting_ability("cleric"), Some(Ability::Wisdom));
+        assert_eq!(library.casting_ability("Fighter"), None);
+        let cantrips = library.for_class("Wizard", 0);
+        assert!(cantrips.iter().any(|s| s.name == "Fire Bolt"));
+        assert!(cantrips.iter().all(|s| s.is_cantrip()));
+        assert!(cantrips.windows(2).all(|w| w[0].name < w[1].name));
+        assert!(library.for_class("Fighter", 1).is_empty());
+        assert_eq!(slot_name(1), "1st-level");
+        assert_eq!(slot_name(3), "3rd-level");
+        assert_eq!(slot_name(9), "9th-level");
+    }
+
+    #[test]
+    fn casting_spends_the_lowest_slot_left() {
+        let mut wizard =
+            Character::from_json(include_str!("../../content/campaigns/pregen_fighter.json"))
+                .unwrap();
+        wizard.class = Some("Wizard".to_string());
+        wizard.level = 3;
+        // Four 1st-level and two 2nd-level slots at 3rd level.
+        assert_eq!(wizard.slots_left_by_level(), [4, 2]);
+        // INT 10 casts with +0.
+        assert_eq!(
+            (wizard.spell_attack_bonus(), wizard.spell_save_dc()),
+            (2, 10)
+        );
+
+        let magic_missile = srd_spells().get("Magic Missile").unwrap();
+        for _ in 0..4 {
+            assert_eq!(wizard.slot_for(magic_missile), Ok(1));
+            wizard.expend_slot(1);
+        }
+        assert_eq!(wizard.slot_for(magic_missile), Ok(2));
+        wizard.expend_slot(2);
+        wizard.expend_slot(2);
+        assert_eq!(
+            wizard.slot_for(magic_missile),
+            Err(
+                "You have no spell slots left to cast Magic Missile (1st-level or higher)."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            wizard.slot_for(srd_spells().get("Fire Bolt").unwrap()),
+            Ok(0)
+        );
+    }
+}
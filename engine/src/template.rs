@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+
+use crate::campaign::GameState;
+use crate::expr::Expr;
+
+/// One paragraph of node text: either plain text or text shown only while
+/// its `when` expression holds.
+///
+/// ```json
+/// "text": [
+///   "The goblin eyes {character.name} warily.",
+///   {"text": "It remembers you spared its brother.", "when": "flag.spared_goblin"}
+/// ]
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Paragraph {
+    Plain(String),
+    Conditional { text: String, when: String },
+}
+
+impl Paragraph {
+    pub fn text(&self) -> &str {
+        match self {
+            Paragraph::Plain(text) | Paragraph::Conditional { text, .. } => text,
+        }
+    }
+
+    pub fn when(&self) -> Option<&str> {
+        match self {
+            Paragraph::Plain(_) => None,
+            Paragraph::Conditional { when, .. } => Some(when),
+        }
+    }
+
+    /// The paragraph as the player should see it, or `None` if its `when`
+    /// doesn't hold.
+    pub fn render(&self, state: &GameState) -> Option<String> {
+        if let Some(when) = self.when() {
+            if !Expr::parse(when).is_ok_and(|e| e.test(state)) {
+                return None;
+            }
+        }
+        Some(match Template::parse(self.text()) {
+            Ok(t) => t.render(state),
+            Err(_) => self.text().to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Expr(Expr),
+}
+
+/// Text with `{...}` placeholders, each holding an expression:
+/// `"{character.name} has {gold} gold"`. Write `{{` and `}}` for literal braces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let chars: Vec<char> = src.chars().collect();
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match (chars[i], chars.get(i + 1)) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    text.push(chars[i]);
+                    i += 2;
+                }
+                ('{', _) => {
+                    let start = i + 1;
+                    let Some(len) = chars[start..].iter().position(|&c| c == '}') else {
+                        return Err(format!("Unclosed '{{' at column {}", i + 1));
+                    };
+                    let inner: String = chars[start..start + len].iter().collect();
+                    let expr = Expr::parse(&inner)
+                        .map_err(|e| format!("Placeholder '{{{}}}': {}", inner, e))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Expr(expr));
+                    i = start + len + 1;
+                }
+                ('}', _) => {
+                    return Err(format!(
+                        "Unmatched '}}' at column {}; write '}}}}' for a literal brace",
+                        i + 1
+                    ))
+                }
+                (c, _) => {
+                    text.push(c);
+                    i += 1;
+                }
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Placeholder expressions, in order.
+    pub fn exprs(&self) -> impl Iterator<Item = &Expr> {
+        self.parts.iter().filter_map(|p| match p {
+            Part::Expr(e) => Some(e),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// Fill in the placeholders. One that can't be evaluated comes out empty.
+    pub fn render(&self, state: &GameState) -> String {
+        self.parts
+            .iter()
+            .map(|p| match p {
+                Part::Text(t) => t.clone(),
+                Part::Expr(e) => e.eval(state).map(|v| v.to_string()).unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Character;
+    use crate::vars::{Value, Vars};
+
+    fn state() -> GameState {
+        let mut vars = Vars::default();
+        vars.set("spared_goblin", Value::Bool(true));
+        vars.set("coins", Value::Int(12));
+        GameState {
+            character: Character::from_json(include_str!(
+                "../../content/campaigns/pregen_fighter.json"
+            ))
+            .unwrap(),
+            current_node_id: "start".to_string(),
+            encounter: None,
+            last_log: None,
+            short_rest: None,
+            level_up: None,
+            vars,
+        }
+    }
+
+    fn render(src: &str) -> Result<String, String> {
+        Template::parse(src).map(|t| t.render(&state()))
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        let state = state();
+        let name = &state.character.name;
+        assert_eq!(
+            render("{character.name} has {var.coins} coins."),
+            Ok(format!("{} has 12 coins.", name))
+        );
+        assert_eq!(render("{coins * 2}"), Ok("24".to_string()));
+        assert_eq!(render("Plain text."), Ok("Plain text.".to_string()));
+        assert_eq!(render(""), Ok(String::new()));
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(render("{{coins}}"), Ok("{coins}".to_string()));
+        assert_eq!(render("}} {coins} {{"), Ok("} 12 {".to_string()));
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(
+            Template::parse("Hello {character.name"),
+            Err("Unclosed '{' at column 7".to_string())
+        );
+        assert_eq!(
+            Template::parse("Hello }"),
+            Err("Unmatched '}' at column 7; write '}}' for a literal brace".to_string())
+        );
+        assert_eq!(
+            Template::parse("{}"),
+            Err("Placeholder '{}': Expected a value at the end".to_string())
+        );
+        assert_eq!(
+            Template::parse("{character.wings}"),
+            Err(
+                "Placeholder '{character.wings}': Unknown name 'character.wings' at column 1"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            Template::parse("{99999999999999999999}"),
+            Err(
+                "Placeholder '{99999999999999999999}': Number 99999999999999999999 is too large"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn failed_placeholders_render_empty() {
+        assert_eq!(render("[{1 / 0}]"), Ok("[]".to_string()));
+        assert_eq!(render("[{9223372036854775807 + 1}]"), Ok("[]".to_string()));
+    }
+
+    #[test]
+    fn conditional_paragraphs() {
+        let state = state();
+        let shown = Paragraph::Conditional {
+            text: "It remembers you.".to_string(),
+            when: "flag.spared_goblin".to_string(),
+        };
+        let hidden = Paragraph::Conditional {
+            text: "It attacks.".to_string(),
+            when: "not flag.spared_goblin".to_string(),
+        };
+        let broken = Paragraph::Conditional {
+            text: "Never shown.".to_string(),
+            when: "flag.".to_string(),
+        };
+        assert_eq!(shown.render(&state), Some("It remembers you.".to_string()));
+        assert_eq!(hidden.render(&state), None);
+        assert_eq!(broken.render(&state), None);
+        assert_eq!(
+            Paragraph::Plain("{coins} left".to_string()).render(&state),
+            Some("12 left".to_string())
+        );
+    }
+
+    #[test]
+    fn node_text_mixes_plain_and_conditional_paragraphs() {
+        let text: Vec<Paragraph> = serde_json::from_str(
+            r#"[
+                "The goblin eyes {character.name} warily.",
+                {"text": "It remembers you spared its brother.", "when": "flag.spared_goblin"},
+                {"text": "It owes you {coins} coins.", "when": "coins > 100"}
+            ]"#,
+        )
+        .unwrap();
+        let state = state();
+        let shown: Vec<String> = text.iter().filter_map(|p| p.render(&state)).collect();
+        assert_eq!(
+            shown,
+            [
+                "The goblin eyes Kara the Bold warily.",
+                "It remembers you spared its brother."
+            ]
+        );
+        assert_eq!(text[2].when(), Some("coins > 100"));
+
+        let template = Template::parse(text[0].text()).unwrap();
+        let exprs: Vec<&Expr> = template.exprs().collect();
+        assert_eq!(exprs, [&Expr::parse("character.name").unwrap()]);
+    }
+}
@@ -0,0 +1,776 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::campaign::{Campaign, Node, NodeType, SavingThrow, COMBAT_CHOICE_IDS};
+use crate::conditions::ActiveCondition;
+use crate::effects::{Effect, EffectKind};
+use crate::expr::Expr;
+use crate::monsters::{slugify, srd_library, MonsterLibrary};
+use crate::template::Template;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Playable, but probably not what the author meant.
+    Warning,
+    /// Will misbehave or fail at runtime.
+    Error,
+}
+
+/// One problem found in a campaign, pointing at the node (and choice) involved.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    #[serde(default)]
+    pub node_id: Option<String>,
+    #[serde(default)]
+    pub choice_id: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", level)?;
+        match (&self.node_id, &self.choice_id) {
+            (Some(n), Some(c)) => write!(f, " [{} / {}]", n, c)?,
+            (Some(n), None) => write!(f, " [{}]", n)?,
+            _ => {}
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+struct Report {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    fn push(&mut self, severity: Severity, node: Option<&str>, choice: Option<&str>, msg: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            node_id: node.map(str::to_string),
+            choice_id: choice.map(str::to_string),
+            message: msg,
+        });
+    }
+
+    fn error(&mut self, node: &str, choice: Option<&str>, msg: String) {
+        self.push(Severity::Error, Some(node), choice, msg);
+    }
+
+    fn warning(&mut self, node: &str, choice: Option<&str>, msg: String) {
+        self.push(Severity::Warning, Some(node), choice, msg);
+    }
+}
+
+/// Every node id a node can lead to, with the choice (if any) and field it came from.
+fn outgoing(node: &Node) -> Vec<(Option<&str>, &'static str, &str)> {
+    let mut out = Vec::new();
+    for c in &node.choices {
+        if let Some(next) = &c.next {
+            out.push((Some(c.id.as_str()), "next", next.as_str()));
+        }
+        if let Some(sc) = &c.skill_check {
+            if let Some(n) = &sc.success_next {
+                out.push((Some(c.id.as_str()), "success_next", n.as_str()));
+            }
+            if let Some(n) = &sc.failure_next {
+                out.push((Some(c.id.as_str()), "failure_next", n.as_str()));
+            }
+        }
+        if let Some(st) = &c.saving_throw {
+            if let Some(n) = &st.success_next {
+                out.push((Some(c.id.as_str()), "success_next", n.as_str()));
+            }
+            if let Some(n) = &st.failure_next {
+                out.push((Some(c.id.as_str()), "failure_next", n.as_str()));
+            }
+        }
+        if let Some(n) = c.rest.as_ref().and_then(|r| r.interrupted_next.as_ref()) {
+            out.push((Some(c.id.as_str()), "interrupted_next", n.as_str()));
+        }
+    }
+    if let Some(st) = &node.saving_throw {
+        if let Some(n) = &st.success_next {
+            out.push((None, "saving_throw.success_next", n.as_str()));
+        }
+        if let Some(n) = &st.failure_next {
+            out.push((None, "saving_throw.failure_next", n.as_str()));
+        }
+    }
+    if let Some(n) = &node.on_victory {
+        out.push((None, "on_victory", n.as_str()));
+    }
+    if let Some(n) = &node.on_defeat {
+        out.push((None, "on_defeat", n.as_str()));
+    }
+    out
+}
+
+/// Every effect a node can apply, with the choice (if any) it belongs to.
+fn effects(node: &Node) -> Vec<(Option<&str>, &Effect)> {
+    let mut out: Vec<(Option<&str>, &Effect)> = node.effects.iter().map(|e| (None, e)).collect();
+    if let Some(st) = &node.saving_throw {
+        out.extend(
+            st.success_effects
+                .iter()
+                .chain(&st.failure_effects)
+                .map(|e| (None, e)),
+        );
+    }
+    for c in &node.choices {
+        let id = Some(c.id.as_str());
+        out.extend(c.effects.iter().map(|e| (id, e)));
+        if let Some(sc) = &c.skill_check {
+            out.extend(
+                sc.success_effects
+                    .iter()
+                    .chain(&sc.failure_effects)
+                    .map(|e| (id, e)),
+            );
+        }
+        if let Some(st) = &c.saving_throw {
+            out.extend(
+                st.success_effects
+                    .iter()
+                    .chain(&st.failure_effects)
+                    .map(|e| (id, e)),
+            );
+        }
+    }
+    out
+}
+
+/// Loops of nodes whose saving throws on arrival lead straight back round,
+/// each starting at the node the loop was entered from.
+fn saving_throw_loops<'a>(nodes: &'a [Node], by_id: &HashMap<&str, &'a Node>) -> Vec<Vec<&'a str>> {
+    fn visit<'a>(
+        id: &'a str,
+        by_id: &HashMap<&str, &'a Node>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        loops: &mut Vec<Vec<&'a str>>,
+    ) {
+        if let Some(pos) = path.iter().position(|&p| p == id) {
+            loops.push(path[pos..].to_vec());
+            return;
+        }
+        if !done.insert(id) {
+            return;
+        }
+        let Some(st) = by_id.get(id).and_then(|n| n.saving_throw.as_ref()) else {
+            return;
+        };
+        let mut targets: Vec<&'a str> = st
+            .success_next
+            .iter()
+            .chain(&st.failure_next)
+            .map(String::as_str)
+            .collect();
+        targets.dedup();
+        path.push(id);
+        for next in targets {
+            visit(next, by_id, path, done, loops);
+        }
+        path.pop();
+    }
+
+    let mut done = HashSet::new();
+    let mut loops = Vec::new();
+    for node in nodes {
+        visit(&node.id, by_id, &mut Vec::new(), &mut done, &mut loops);
+    }
+    loops
+}
+
+impl Campaign {
+    /// Check the campaign for authoring mistakes against the bundled SRD monsters.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        self.validate_with(srd_library())
+    }
+
+    /// Check the campaign for authoring mistakes. An empty list means no problems.
+    pub fn validate_with(&self, monsters: &MonsterLibrary) -> Vec<Diagnostic> {
+        let mut report = Report {
+            diagnostics: Vec::new(),
+        };
+
+        let mut by_id: HashMap<&str, &Node> = HashMap::new();
+        for node in &self.nodes {
+            if by_id.insert(node.id.as_str(), node).is_some() {
+                report.error(&node.id, None, format!("Duplicate node id '{}'", node.id));
+            }
+        }
+
+        let mut item_ids = HashSet::new();
+        for item in &self.items {
+            if !item_ids.insert(slugify(&item.id)) {
+                report.push(
+                    Severity::Error,
+                    None,
+                    None,
+                    format!("Duplicate item id '{}'", item.id),
+                );
+            }
+            for effect in &item.on_use {
+                if let Err(msg) = effect.check(&self.items) {
+                    report.push(
+                        Severity::Error,
+                        None,
+                        None,
+                        format!("Item '{}': {}", item.id, msg),
+                    );
+                }
+            }
+        }
+
+        if !by_id.contains_key(self.start_node_id.as_str()) {
+            report.push(
+                Severity::Error,
+                None,
+                None,
+                format!("startNodeId '{}' does not exist", self.start_node_id),
+            );
+        }
+
+        // Every story variable something sets, for spotting typos in expressions.
+        let vars: HashSet<&str> = self
+            .nodes
+            .iter()
+            .flat_map(|n| {
+                let choices = n
+                    .choices
+                    .iter()
+                    .flat_map(|c| c.set.keys().chain(c.increment.keys()));
+                n.set.keys().chain(n.increment.keys()).chain(choices)
+            })
+            .map(String::as_str)
+            .chain(
+                self.nodes
+                    .iter()
+                    .flat_map(effects)
+                    .map(|(_, e)| e)
+                    .chain(self.items.iter().flat_map(|i| &i.on_use))
+                    .filter_map(|e| match &e.kind {
+                        EffectKind::Flag { flag, .. } => Some(flag.as_str()),
+                        _ => None,
+                    }),
+            )
+            .collect();
+
+        for node in &self.nodes {
+            self.check_node(node, &by_id, monsters, &vars, &mut report);
+        }
+
+        for cycle in saving_throw_loops(&self.nodes, &by_id) {
+            let path: Vec<&str> = cycle.iter().chain(cycle.first()).copied().collect();
+            report.error(
+                cycle[0],
+                None,
+                format!(
+                    "Saving throws on arrival send the hero round in a loop: {}",
+                    path.join(" -> ")
+                ),
+            );
+        }
+
+        let has_end = self.nodes.iter().any(|n| matches!(n.kind, NodeType::End));
+        if !has_end {
+            report.push(
+                Severity::Error,
+                None,
+                None,
+                "Campaign has no end node".to_string(),
+            );
+        }
+
+        // Reachability from the start node.
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut queue: VecDeque<&str> = VecDeque::new();
+        if by_id.contains_key(self.start_node_id.as_str()) {
+            queue.push_back(self.start_node_id.as_str());
+        }
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id) {
+                continue;
+            }
+            if let Some(node) = by_id.get(id) {
+                for (_, _, target) in outgoing(node) {
+                    if by_id.contains_key(target) && !seen.contains(target) {
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+        for node in &self.nodes {
+            if !seen.contains(node.id.as_str()) {
+                report.warning(
+                    &node.id,
+                    None,
+                    "Node is unreachable from the start node".to_string(),
+                );
+            }
+        }
+        if has_end
+            && !seen.is_empty()
+            && !seen
+                .iter()
+                .filter_map(|id| by_id.get(id))
+                .any(|n| matches!(n.kind, NodeType::End))
+        {
+            report.push(
+                Severity::Error,
+                None,
+                None,
+                "No end node is reachable from the start node".to_string(),
+            );
+        }
+
+        report.diagnostics
+    }
+
+    fn check_node(
+        &self,
+        node: &Node,
+        by_id: &HashMap<&str, &Node>,
+        monsters: &MonsterLibrary,
+        vars: &HashSet<&str>,
+        report: &mut Report,
+    ) {
+        let links = outgoing(node);
+        for (choice, field, target) in &links {
+            if !by_id.contains_key(target) {
+                report.error(
+                    &node.id,
+                    *choice,
+                    format!("'{}' points at missing node '{}'", field, target),
+                );
+            }
+        }
+
+        if let Some(st) = &node.saving_throw {
+            check_saving_throw(st, &node.id, None, report);
+        }
+
+        for (choice, effect) in effects(node) {
+            if let Err(msg) = effect.check(&self.items) {
+                report.error(&node.id, choice, msg);
+            }
+        }
+
+        for p in &node.text {
+            if let Some(src) = p.when() {
+                check_expr(src, "when", vars, &node.id, None, report);
+            }
+            match Template::parse(p.text()) {
+                Err(msg) => report.error(&node.id, None, format!("text: {}", msg)),
+                // Unlike a `when`, a placeholder shows its value to the player,
+                // so a misspelt `{charname}` reading 0 is an error.
+                Ok(t) => {
+                    for var in t.exprs().flat_map(Expr::vars) {
+                        if !vars.contains(var) {
+                            report.error(
+                                &node.id,
+                                None,
+                                format!("text placeholder reads '{}', which nothing sets", var),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut choice_ids = HashSet::new();
+        for c in &node.choices {
+            if !choice_ids.insert(c.id.as_str()) {
+                report.error(
+                    &node.id,
+                    Some(&c.id),
+                    format!("Duplicate choice id '{}'", c.id),
+                );
+            }
+            if let Some(src) = &c.requires {
+                check_expr(src, "requires", vars, &node.id, Some(&c.id), report);
+            }
+            if let Some(sc) = &c.skill_check {
+                if let Err(msg) = sc.resolve() {
+                    report.error(&node.id, Some(&c.id), msg);
+                }
+                check_conditions(&sc.failure_conditions, &node.id, Some(&c.id), report);
+            }
+            if let Some(st) = &c.saving_throw {
+                check_saving_throw(st, &node.id, Some(&c.id), report);
+                if c.skill_check.is_some() {
+                    report.warning(
+                        &node.id,
+                        Some(&c.id),
+                        "Choice has both a skill_check and a saving_throw; the saving_throw is ignored"
+                            .to_string(),
+                    );
+                }
+            }
+            if let Some(rest) = &c.rest {
+                if !matches!(node.kind, NodeType::Narrative) {
+                    report.error(
+                        &node.id,
+                        Some(&c.id),
+                        "Rests can only be offered in narrative nodes".to_string(),
+                    );
+                }
+                if rest.interruption_chance > 100 {
+                    report.error(
+                        &node.id,
+                        Some(&c.id),
+                        format!(
+                            "interruption_chance {} is over 100",
+                            rest.interruption_chance
+                        ),
+                    );
+                }
+                if c.skill_check.is_some() || c.saving_throw.is_some() {
+                    report.warning(
+                        &node.id,
+                        Some(&c.id),
+                        "Choice has both a rest and a roll; the roll is ignored".to_string(),
+                    );
+                }
+            }
+        }
+
+        match node.kind {
+            NodeType::End => {}
+            NodeType::Narrative => {
+                if links.is_empty() {
+                    report.error(&node.id, None, "Non-end node has no way out".to_string());
+                }
+            }
+            NodeType::Combat => {
+                match &node.encounter {
+                    None => {
+                        report.error(&node.id, None, "Combat node has no encounter".to_string())
+                    }
+                    Some(spec) => {
+                        if spec.monsters.is_empty() {
+                            report.error(&node.id, None, "Encounter has no monsters".to_string());
+                        }
+                        for m in &spec.monsters {
+                            if !monsters.contains(&m.r#ref) {
+                                report.error(
+                                    &node.id,
+                                    None,
+                                    format!("Unknown monster ref '{}'", m.r#ref),
+                                );
+                            }
+                            check_conditions(&m.conditions, &node.id, None, report);
+                        }
+                    }
+                }
+                if node.on_victory.is_none() {
+                    report.error(&node.id, None, "Combat node has no on_victory".to_string());
+                }
+                if node.on_defeat.is_none() {
+                    report.error(&node.id, None, "Combat node has no on_defeat".to_string());
+                }
+                for c in &node.choices {
+                    if COMBAT_CHOICE_IDS.contains(&c.id.as_str()) {
+                        let does_something = c.next.is_some()
+                            || c.requires.is_some()
+                            || !c.set.is_empty()
+                            || !c.increment.is_empty()
+                            || !c.effects.is_empty()
+                            || c.skill_check.is_some()
+                            || c.saving_throw.is_some()
+                            || c.rest.is_some();
+                        if does_something {
+                            report.error(
+                                &node.id,
+                                Some(&c.id),
+                                format!(
+                                    "'{}' is the engine's own combat choice; only its label \
+                                     is used and the rest of it never runs",
+                                    c.id
+                                ),
+                            );
+                        } else if c.id == "attack" {
+                            report.warning(
+                                &node.id,
+                                Some(&c.id),
+                                "Never offered: a fight offers one attack per target instead"
+                                    .to_string(),
+                            );
+                        }
+                    } else if c.skill_check.is_some()
+                        || c.saving_throw.is_some()
+                        || c.rest.is_some()
+                    {
+                        report.warning(
+                            &node.id,
+                            Some(&c.id),
+                            "In a fight a choice only applies its effects and follows next; \
+                             its roll or rest is ignored"
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Condition saves must name a real ability, and a zero-round duration ends
+/// before it does anything.
+fn check_conditions(
+    conditions: &[ActiveCondition],
+    node: &str,
+    choice: Option<&str>,
+    report: &mut Report,
+) {
+    for c in conditions {
+        if let Some(Err(msg)) = c.save.as_ref().map(|s| s.resolve()) {
+            report.error(node, choice, msg);
+        }
+        if c.rounds == Some(0) {
+            report.warning(
+                node,
+                choice,
+                format!("Condition '{}' lasts 0 rounds", c.condition.slug()),
+            );
+        }
+    }
+}
+
+/// Expressions must parse, and story variables they read should be set somewhere.
+fn check_expr(
+    src: &str,
+    field: &str,
+    vars: &HashSet<&str>,
+    node: &str,
+    choice: Option<&str>,
+    report: &mut Report,
+) {
+    match Expr::parse(src) {
+        Err(msg) => report.error(node, choice, format!("{} '{}': {}", field, src, msg)),
+        Ok(expr) => {
+            for var in expr.vars() {
+                if !vars.contains(var) {
+                    report.warning(
+                        node,
+                        choice,
+                        format!("{} reads '{}', which nothing sets", field, var),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_saving_throw(st: &SavingThrow, node: &str, choice: Option<&str>, report: &mut Report) {
+    if let Err(msg) = st.resolve() {
+        report.error(node, choice, msg);
+    }
+    check_conditions(&st.failure_conditions, node, choice, report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(json: &str) -> Vec<Diagnostic> {
+        Campaign::from_json(json)
+            .unwrap()
+            .validate()
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect()
+    }
+
+    #[test]
+    fn saving_throw_loops_are_errors() {
+        let errors = errors(
+            r#"{
+                "id": "loop", "title": "Loop", "startNodeId": "a",
+                "nodes": [
+                    {"id": "a", "type": "narrative", "text": ["A."],
+                     "saving_throw": {"ability": "dex", "dc": 10, "failure_next": "b"},
+                     "choices": [{"id": "on", "label": "On", "next": "end"}]},
+                    {"id": "b", "type": "narrative", "text": ["B."],
+                     "saving_throw": {"ability": "con", "dc": 10, "success_next": "a"}},
+                    {"id": "c", "type": "narrative", "text": ["C."],
+                     "saving_throw": {"ability": "wis", "dc": 10,
+                                      "success_next": "c", "failure_next": "c"}},
+                    {"id": "end", "type": "end", "text": ["The end."]}
+                ]
+            }"#,
+        );
+        let loops: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|d| (d.node_id.as_deref().unwrap(), d.message.as_str()))
+            .filter(|(_, m)| m.contains("loop"))
+            .collect();
+        assert_eq!(
+            loops,
+            [
+                (
+                    "a",
+                    "Saving throws on arrival send the hero round in a loop: a -> b -> a"
+                ),
+                (
+                    "c",
+                    "Saving throws on arrival send the hero round in a loop: c -> c"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn misspelt_placeholders_are_errors() {
+        let errors = errors(
+            r#"{
+                "id": "typo", "title": "Typo", "startNodeId": "a",
+                "nodes": [
+                    {"id": "a", "type": "narrative",
+                     "text": ["{character.name} counts {coins} coins, {charname}."],
+                     "set": {"coins": 3},
+                     "choices": [{"id": "on", "label": "On", "next": "end",
+                                  "requires": "crowns > 0"}]},
+                    {"id": "end", "type": "end", "text": ["The end."]}
+                ]
+            }"#,
+        );
+        assert_eq!(
+            errors,
+            [Diagnostic {
+                severity: Severity::Error,
+                node_id: Some("a".to_string()),
+                choice_id: None,
+                message: "text placeholder reads 'charname', which nothing sets".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn the_bundled_campaign_is_clean() {
+        let campaign = Campaign::from_json(include_str!(
+            "../../content/campaigns/road_to_redcrest.json"
+        ))
+        .unwrap();
+        assert_eq!(campaign.validate(), []);
+    }
+
+    #[test]
+    fn dangling_links_unreachable_nodes_and_missing_endings() {
+        let diagnostics = Campaign::from_json(
+            r#"{
+                "id": "broken", "title": "Broken", "startNodeId": "a",
+                "nodes": [
+                    {"id": "a", "type": "narrative", "text": ["A."],
+                     "choices": [{"id": "go", "label": "Go", "next": "nowhere"},
+                                 {"id": "back", "label": "Back", "next": "a"}]},
+                    {"id": "island", "type": "narrative", "text": ["Alone."],
+                     "choices": [{"id": "stay", "label": "Stay", "next": "island"}]}
+                ]
+            }"#,
+        )
+        .unwrap()
+        .validate();
+        let found: Vec<(Severity, Option<&str>, Option<&str>, &str)> = diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.severity,
+                    d.node_id.as_deref(),
+                    d.choice_id.as_deref(),
+                    d.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    Severity::Error,
+                    Some("a"),
+                    Some("go"),
+                    "'next' points at missing node 'nowhere'"
+                ),
+                (Severity::Error, None, None, "Campaign has no end node"),
+                (
+                    Severity::Warning,
+                    Some("island"),
+                    None,
+                    "Node is unreachable from the start node"
+                ),
+            ]
+        );
+    }
+
+    /// A label-only "continue" just renames pressing on.
+    const PRESS_ON: &str = r#"{
+        "id": "fight", "title": "Fight", "startNodeId": "fight",
+        "nodes": [
+            {"id": "fight", "type": "combat", "text": ["Goblins!"],
+             "encounter": {"monsters": [{"ref": "srd_goblin", "count": 1}]},
+             "choices": [{"id": "continue", "label": "Brace yourself"}],
+             "on_victory": "end", "on_defeat": "end"},
+            {"id": "end", "type": "end", "text": ["The end."]}
+        ]
+    }"#;
+
+    #[test]
+    fn authored_combat_choices_cannot_reuse_engine_ids() {
+        let errors = errors(
+            r#"{
+                "id": "fight", "title": "Fight", "startNodeId": "fight",
+                "nodes": [
+                    {"id": "fight", "type": "combat", "text": ["Goblins!"],
+                     "encounter": {"monsters": [{"ref": "srd_goblin", "count": 1}]},
+                     "choices": [
+                        {"id": "continue", "label": "Brace yourself",
+                         "effects": [{"type": "flag", "flag": "braced"}]},
+                        {"id": "death_save", "label": "Pray", "next": "end"},
+                        {"id": "attack", "label": "Attack!"},
+                        {"id": "flee", "label": "Flee", "next": "end"}
+                     ],
+                     "on_victory": "end", "on_defeat": "end"},
+                    {"id": "end", "type": "end", "text": ["The end."]}
+                ]
+            }"#,
+        );
+        let flagged: Vec<&str> = errors
+            .iter()
+            .filter_map(|d| d.choice_id.as_deref())
+            .collect();
+        assert_eq!(flagged, ["continue", "death_save"]);
+
+        let mut campaign = Campaign::from_json(PRESS_ON).unwrap();
+        assert!(campaign.validate().is_empty());
+        campaign.nodes[0].choices[0].id = "attack".to_string();
+        let diagnostics = campaign.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn saving_throws_that_move_on_are_fine() {
+        let errors = errors(
+            r#"{
+                "id": "ok", "title": "Ok", "startNodeId": "a",
+                "nodes": [
+                    {"id": "a", "type": "narrative", "text": ["A."],
+                     "saving_throw": {"ability": "dex", "dc": 10,
+                                      "success_next": "b", "failure_next": "b"}},
+                    {"id": "b", "type": "narrative", "text": ["B."],
+                     "saving_throw": {"ability": "con", "dc": 10, "success_next": "end"}},
+                    {"id": "end", "type": "end", "text": ["The end."]}
+                ]
+            }"#,
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A story variable: a flag, a counter or a bit of text.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl Value {
+    /// `false`, `0` and `""` are false; everything else is true.
+    pub fn truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Int(n) => *n != 0,
+            Value::Str(s) => !s.is_empty(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Story variables, kept for the whole adventure. Serializes as a plain
+/// object: `{"spared_goblin": true, "gold": 12}`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Vars(BTreeMap<String, Value>);
+
+impl Vars {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) {
+        self.0.insert(name.to_string(), value);
+    }
+
+    /// Add to a counter, stopping at the largest or smallest integer. Unset or
+    /// non-integer variables count as 0.
+    pub fn increment(&mut self, name: &str, by: i64) -> i64 {
+        let n = match self.0.get(name) {
+            Some(Value::Int(n)) => n.saturating_add(by),
+            _ => by,
+        };
+        self.0.insert(name.to_string(), Value::Int(n));
+        n
+    }
+
+    /// Apply a node's or choice's `set` and then its `increment`.
+    pub fn apply(&mut self, set: &BTreeMap<String, Value>, increment: &BTreeMap<String, i64>) {
+        for (name, value) in set {
+            self.set(name, value.clone());
+        }
+        for (name, by) in increment {
+            self.increment(name, *by);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truthiness_and_display() {
+        for (value, truthy, shown) in [
+            (Value::Bool(false), false, "false"),
+            (Value::Int(0), false, "0"),
+            (Value::Int(-2), true, "-2"),
+            (Value::Str(String::new()), false, ""),
+            (Value::Str("Mira".to_string()), true, "Mira"),
+        ] {
+            assert_eq!(value.truthy(), truthy, "{:?}", value);
+            assert_eq!(value.to_string(), shown);
+        }
+    }
+
+    #[test]
+    fn counters_start_at_zero_and_saturate() {
+        let mut vars = Vars::default();
+        assert_eq!(vars.increment("wolves", 2), 2);
+        assert_eq!(vars.increment("wolves", -3), -1);
+        vars.set("name", Value::Str("Mira".to_string()));
+        assert_eq!(vars.increment("name", 4), 4);
+        vars.set("gold", Value::Int(i64::MAX));
+        assert_eq!(vars.increment("gold", 1), i64::MAX);
+    }
+
+    #[test]
+    fn set_applies_before_increment_and_round_trips() {
+        let mut vars = Vars::default();
+        let set = BTreeMap::from([
+            ("spared_goblin".to_string(), Value::Bool(true)),
+            ("favour".to_string(), Value::Int(10)),
+        ]);
+        let increment = BTreeMap::from([("favour".to_string(), 1)]);
+        vars.apply(&set, &increment);
+        assert_eq!(vars.get("favour"), Some(&Value::Int(11)));
+
+        let json = serde_json::to_string(&vars).unwrap();
+        assert_eq!(json, r#"{"favour":11,"spared_goblin":true}"#);
+        assert_eq!(serde_json::from_str::<Vars>(&json).unwrap(), vars);
+        assert_eq!(vars.iter().count(), 2);
+    }
+}